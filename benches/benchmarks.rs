@@ -1,14 +1,10 @@
-use std::fmt;
-
-#[cfg(test)]
-mod grammars_tests {
-    use lrp::{grammar_map, Grammar};
-    include!("../grammars_tests.rs");
-}
+use std::{cell::RefCell, fmt, time::Duration};
 
 use grammars_tests::GRAMMARS;
 use hermes_bench::{BenchSize, Bencher, ClassicBench, IterBench};
-use lrp::{dfa::Error, to_tokens, Clr, Lalr, Parser, Slr, Tabler};
+use lrp::{
+    dfa::Error, to_tokens, BaseResult, Clr, Lalr, LalrDp, Layout, Pager, Parser, Slr, Tabler, Token,
+};
 
 const BENCH_SIZE: BenchSize = BenchSize::Iters(100);
 
@@ -47,9 +43,9 @@ fn test_dfa<P: Parser<&'static str>>(name: &str) {
     for (grammar, inputs, grammar_name) in GRAMMARS {
         let parser = P::new(grammar());
         let iter = inputs
-            .into_iter()
+            .iter()
             .cycle()
-            .map(|i| parser.simple_dfa(to_tokens(i.into_iter().cloned())));
+            .map(|i| parser.simple_dfa(to_tokens(i.iter().copied())));
         let assert = |r| {
             assert!(
                 matches!(r, Ok(_) | Err(Error::Conflict(_, _))),
@@ -65,12 +61,64 @@ fn test_dfa<P: Parser<&'static str>>(name: &str) {
     }
 }
 
+type Tokens = std::vec::IntoIter<Token<(), &'static str>>;
+
+/// Mean time parsing the `inputs`, cyclically, with `parse`
+fn parse_mean(
+    inputs: &[Vec<Token<(), &'static str>>],
+    parse: &dyn Fn(Tokens) -> BaseResult<(), Error<&'static str>>,
+) -> Duration {
+    let iter = inputs.iter().cycle().map(|i| i.clone().into_iter());
+    let assert = |r| {
+        assert!(
+            matches!(r, Ok(_) | Err(Error::Conflict(_, _))),
+            "rased {r:?}"
+        )
+    };
+    let mut bench = IterBench::new(iter, parse)
+        .with_post(&assert)
+        .with_size(BENCH_SIZE);
+    bench.run();
+    bench.mean()
+}
+
+/// Parses the same inputs with the same `Dfa` over the `BTreeMap` actions table and over the dense
+/// ones, side by side
+fn test_dense_dfa<P: Parser<&'static str>>(name: &str) {
+    println!("\n{name}'s DFA over the BTreeMap | flat | comb tables:");
+    for (grammar, inputs, grammar_name) in GRAMMARS {
+        let parser = P::new(grammar());
+        let inputs: Vec<Vec<_>> = (inputs.iter())
+            .map(|i| to_tokens(i.iter().copied()).collect())
+            .collect();
+        let start = parser.tables().start();
+        let maps = || parser.empty::<Tokens>();
+        let map = RefCell::new(parser.dfa(&start, Vec::new().into_iter(), maps()));
+        let dense = |layout| RefCell::new(parser.dense_dfa(Vec::new().into_iter(), maps(), layout));
+        let (flat, comb) = (dense(Layout::Flat), dense(Layout::Comb));
+        let map = parse_mean(&inputs, &|i| map.borrow_mut().parse(i));
+        let flat = parse_mean(&inputs, &|i| flat.borrow_mut().parse(i));
+        let comb = parse_mean(&inputs, &|i| comb.borrow_mut().parse(i));
+        println!("\t{grammar_name} parsing inputs: ± {map:?} | {flat:?} | {comb:?} /iter");
+    }
+}
+
 fn main() {
     test_table_gen();
     test_table_parser_prod::<Clr<&'static str>>("Canonical LR");
     test_table_parser_prod::<Lalr<&'static str>>("LALR(1)");
+    test_table_parser_prod::<LalrDp<&'static str>>("DeRemer-Pennello LALR(1)");
     test_table_parser_prod::<Slr<&'static str>>("SLR");
+    test_table_parser_prod::<Pager<&'static str>>("Pager's minimal LR(1)");
     test_dfa::<Clr<&'static str>>("Canonical LR");
     test_dfa::<Lalr<&'static str>>("LALR(1)");
     test_dfa::<Slr<&'static str>>("SLR");
+    test_dense_dfa::<Lalr<&'static str>>("LALR(1)");
+}
+
+#[cfg(test)]
+#[allow(dead_code)] // shared with the lib tests
+mod grammars_tests {
+    use lrp::{grammar_map, Assoc, CharClass, Grammar};
+    include!("../grammars_tests.rs");
 }
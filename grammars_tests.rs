@@ -11,6 +11,9 @@ pub const GRAMMARS: &[(GrammarProd, &[&[&str]], &str)] = &[
     (wikipedia, WIKIPEDIA_INPUTS, "wikipedia"),
     (puncs, PUNCS_INPUTS, "punctuations"),
     (scanner, SCANNER_INPUTS, "scanner"),
+    (declarations, DECLARATIONS_INPUTS, "declarations"),
+    (arithmetic, ARITHMETIC_INPUTS, "arithmetic"),
+    (statements, STATEMENTS_INPUTS, "statements"),
 ];
 
 pub const DRAGON_BOOK_INPUTS: &[&[&str]] = &[
@@ -58,6 +61,7 @@ pub fn dragon_book() -> Grammar<&'static str> {
             | "d"
     };
     Grammar::new("S", grammar, "$")
+        .unwrap()
 }
 
 pub const SEROKELL_INPUTS: &[&[&str]] = &[
@@ -106,6 +110,7 @@ pub fn serokell() -> Grammar<&'static str> {
     };
 
     Grammar::new("Start", grammar, "$")
+        .unwrap()
 }
 
 pub const NON_LALR_UCALGARY_UNI_OTH_LR1_INPUTS: &[&[&str]] = &[
@@ -164,6 +169,7 @@ pub fn ucalgary_uni_oth_lr1() -> Grammar<&'static str> {
     };
 
     Grammar::new("S", grammar, "$")
+        .unwrap()
 }
 
 pub const WIKIPEDIA_INPUTS: &[&[&str]] = &[
@@ -217,6 +223,7 @@ pub fn wikipedia() -> Grammar<&'static str> {
     };
 
     Grammar::new("S", grammar, "$")
+        .unwrap()
 }
 
 pub const PUNCS_INPUTS: &[&[&str]] = &[
@@ -262,7 +269,6 @@ pub fn puncs() -> Grammar<&'static str> {
         S -> { S }.
     */
     let grammar = grammar_map! {
-        "S'" -> "S",
         "S" -> "(" ")"
             | "(" "S" ")"
             | "[" "]"
@@ -271,7 +277,7 @@ pub fn puncs() -> Grammar<&'static str> {
             | "{" "S" "}"
     };
 
-    Grammar::new("S'", grammar, "$")
+    Grammar::augmented("S", grammar, "$").unwrap()
 }
 
 pub const SCANNER_INPUTS: &[&[&str]] = &[
@@ -370,4 +376,272 @@ pub fn scanner() -> Grammar<&'static str> {
     };
 
     Grammar::new("S", grammar, "$")
+        .unwrap()
+}
+
+pub fn scannerless() -> Grammar<&'static str> {
+    /*
+    Phrase -> Phrase Item.
+    Phrase -> Item.
+
+    Item -> Word.
+    Item -> Num.
+    Item -> Space.
+
+    Word -> alpha Word.
+    Word -> alpha.
+
+    Num -> digit Num.
+    Num -> digit.
+
+    Space -> blank Space.
+    Space -> blank.
+
+    alpha = [a-z].
+    digit = [0-9].
+    blank = [ \t].
+
+    Word -/- [a-z].
+    Num -/- [0-9].
+    Space -/- [ \t].
+     */
+    let grammar = grammar_map! {
+        "S" -> "Phrase",
+
+        "Phrase" -> "Phrase" "Item"
+            | "Item",
+
+        "Item" -> "Word" | "Num" | "Space",
+
+        "Word" -> "alpha" "Word"
+            | "alpha",
+
+        "Num" -> "digit" "Num"
+            | "digit",
+
+        "Space" -> "blank" "Space"
+            | "blank"
+    };
+
+    let alpha = CharClass::new(['a'..='z']);
+    let digit = CharClass::new(['0'..='9']);
+    let blank = CharClass::new([' '..=' ', '\t'..='\t']);
+    Grammar::new("S", grammar, "$")
+        .unwrap()
+        .with_class("alpha", alpha.clone())
+        .with_class("digit", digit.clone())
+        .with_class("blank", blank.clone())
+        .with_restriction("Word", alpha)
+        .with_restriction("Num", digit)
+        .with_restriction("Space", blank)
+}
+
+pub const DECLARATIONS_INPUTS: &[&[&str]] = &[
+    &[],
+    &["let", "id", ";"],
+    &["let", "mut", "id", ";"],
+    &["let", "id", ":", "id", ";"],
+    &["let", "mut", "id", ":", "id", ";"],
+    &["let", "id", ";", "let", "id", ";"],
+    &["let", "mut", "id", ":", "id", ";", "let", "id", ";"],
+    &[
+        "let", "id", ":", "id", ";", "let", "mut", "id", ";", "let", "id", ":", "id", ";",
+    ],
+];
+
+pub fn declarations() -> Grammar<&'static str> {
+    /*
+        S -> Decls.
+        Decls -> Decls Decl.
+        Decls -> .
+        Decl -> let Pat Type ;.
+        Pat -> Mut id.
+        Mut -> mut.
+        Mut -> .
+        Type -> : id.
+        Type -> .
+    */
+    let grammar = grammar_map! {
+        "S" -> "Decls",
+        "Decls" -> "Decls" "Decl"
+            | ,
+        "Decl" -> "let" "Pat" "Type" ";",
+        "Pat" -> "Mut" "id",
+        "Mut" -> "mut"
+            | ,
+        "Type" -> ":" "id"
+            |
+    };
+
+    Grammar::new("S", grammar, "$")
+        .unwrap()
+}
+
+pub const ARITHMETIC_INPUTS: &[&[&str]] = &[
+    &["n"],
+    &["-", "n"],
+    &["n", "+", "n"],
+    &["n", "-", "n", "-", "n"],
+    &["n", "+", "n", "*", "n"],
+    &["n", "*", "n", "+", "n"],
+    &["n", "^", "n", "^", "n"],
+    &["-", "n", "^", "n"],
+    &["n", "==", "n", "+", "n"],
+    &["(", "n", "==", "n", ")", "==", "n"],
+    &["(", "n", "+", "n", ")", "*", "-", "n", "/", "n"],
+];
+
+pub const NON_ASSOC_ARITHMETIC_INPUTS: &[&[&str]] = &[
+    &["n", "==", "n", "==", "n"],
+    &["n", "+", "n", "==", "n", "==", "n"],
+];
+
+pub fn arithmetic() -> Grammar<&'static str> {
+    /*
+        %nonassoc ==.
+        %left + -.
+        %left * /.
+        %right ^.
+        %right NEG.
+
+        S -> E.
+        E -> E == E.
+        E -> E + E.
+        E -> E - E.
+        E -> E * E.
+        E -> E / E.
+        E -> E ^ E.
+        E -> - E %prec NEG.
+        E -> ( E ).
+        E -> n.
+    */
+    let grammar = grammar_map! {
+        "S" -> "E",
+        "E" -> "E" "==" "E"
+            | "E" "+" "E"
+            | "E" "-" "E"
+            | "E" "*" "E"
+            | "E" "/" "E"
+            | "E" "^" "E"
+            | "-" "E"
+            | "(" "E" ")"
+            | "n"
+    };
+
+    Grammar::new("S", grammar, "$")
+        .unwrap()
+        .with_level(Assoc::NonAssoc, ["=="])
+        .with_level(Assoc::Left, ["+", "-"])
+        .with_level(Assoc::Left, ["*", "/"])
+        .with_level(Assoc::Right, ["^"])
+        .with_level(Assoc::Right, ["NEG"])
+        .with_prec("E", 6, "NEG")
+}
+
+pub const STATEMENTS_INPUTS: &[&[&str]] = &[
+    &["id", "=", "n", ";"],
+    &["id", "=", "n", ";", "id", "=", "id", ";"],
+    &["id", "=", "n", ";", "id", "=", "id", ";", "id", "=", "n", ";"],
+];
+
+pub const BAD_STATEMENTS_INPUTS: &[&[&str]] = &[
+    &["id", "=", "=", ";"],
+    &["id", "=", "n", ";", "id", ";", "id", "=", "n", ";"],
+    &["id", "=", "n", "n", ";", "=", ";", "id", "=", "n", ";"],
+];
+
+pub fn statements() -> Grammar<&'static str> {
+    /*
+        S -> Stmts.
+        Stmts -> Stmts Stmt.
+        Stmts -> Stmt.
+        Stmt -> id = Value ;.
+        Stmt -> error ;.
+        Value -> n.
+        Value -> id.
+    */
+    let grammar = grammar_map! {
+        "S" -> "Stmts",
+        "Stmts" -> "Stmts" "Stmt"
+            | "Stmt",
+        "Stmt" -> "id" "=" "Value" ";"
+            | "error" ";",
+        "Value" -> "n"
+            | "id"
+    };
+
+    Grammar::new("S", grammar, "$")
+        .unwrap()
+}
+
+pub fn entries() -> Grammar<&'static str> {
+    /*
+        %entry Module Expr Type.
+
+        Module -> Module Decl.
+        Module -> Decl.
+        Decl -> let id : Type = Expr ;.
+        Expr -> Expr + n.
+        Expr -> n.
+        Type -> Type [ ].
+        Type -> id.
+    */
+    let grammar = grammar_map! {
+        "Module" -> "Module" "Decl"
+            | "Decl",
+        "Decl" -> "let" "id" ":" "Type" "=" "Expr" ";",
+        "Expr" -> "Expr" "+" "n"
+            | "n",
+        "Type" -> "Type" "[" "]"
+            | "id"
+    };
+
+    Grammar::with_entries(["Module", "Expr", "Type"], grammar, "$").unwrap()
+}
+
+pub const LR2_INPUTS: &[&[&str]] = &[&["d", "a", "b"], &["d", "a", "c"]];
+
+pub const BAD_LR2_INPUTS: &[&[&str]] = &[&["d", "a"], &["d", "a", "d"], &["d", "b", "c"]];
+
+pub fn lr2() -> Grammar<&'static str> {
+    /*
+        S -> X.
+        X -> A a b.
+        X -> B a c.
+        A -> d.
+        B -> d.
+    */
+    let grammar = grammar_map! {
+        "S" -> "X",
+        "X" -> "A" "a" "b"
+            | "B" "a" "c",
+        "A" -> "d",
+        "B" -> "d"
+    };
+
+    Grammar::new("S", grammar, "$")
+        .unwrap()
+}
+
+pub const AMBIGUOUS_INPUTS: &[&[&str]] = &[
+    &["n"],
+    &["n", "+", "n"],
+    &["n", "+", "n", "+", "n"],
+    &["n", "+", "n", "+", "n", "+", "n"],
+];
+
+pub fn ambiguous() -> Grammar<&'static str> {
+    /*
+        S -> E.
+        E -> E + E.
+        E -> n.
+    */
+    let grammar = grammar_map! {
+        "S" -> "E",
+        "E" -> "E" "+" "E"
+            | "n"
+    };
+
+    Grammar::new("S", grammar, "$")
+        .unwrap()
 }
@@ -1,4 +1,4 @@
-use crate::{transitive, Action, Map, Parser, Position, Set, State, Tabler};
+use crate::{transitive, Action, Map, Parser, Position, State, Tabler};
 use std::fmt::Debug;
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
@@ -9,6 +9,8 @@ where
     pub table: Tabler<T>,
 }
 
+// the `#[must_use]` of the trait methods, repeated on the impl
+#[allow(unused_attributes)]
 impl<T> Parser<T> for Clr<T>
 where
     T: PartialEq + Ord + Clone + Debug,
@@ -39,7 +41,7 @@ where
     T: PartialEq + Ord + Clone + Debug,
 {
     pub fn proc_closures_first_row(&mut self) {
-        let start = self.prop_closure(State::from([self.table.basis_pos()]));
+        let start = self.prop_closure(self.table.basis_state());
         self.table.kernels.insert(State::new(), 0);
         self.table.states.push(start);
     }
@@ -63,6 +65,7 @@ where
             }
             self.table.actions.push(map);
         }
+        self.table.solve_conflicts();
     }
 
     #[must_use]
@@ -73,11 +76,7 @@ where
                 if self.table.grammar.is_terminal(&top) {
                     continue;
                 }
-                // TODO: Remove `clone`
-                let look = pos.next().map_or_else(
-                    || pos.look.clone(),
-                    |locus| self.table.first_of(&Set::from([locus])),
-                );
+                let look = self.table.first_seq(pos.rest(), &pos.look);
                 for prod in self.table.grammar.rules[&top].prods() {
                     new_state.insert(Position::new(top.clone(), prod.clone(), 0, look.clone()));
                 }
@@ -230,4 +229,28 @@ mod tests {
             assert!(clr.validate(to_tokens(input.iter().cloned())));
         }
     }
+
+    #[test]
+    pub fn declarations() {
+        let clr = Clr::new(grammars_tests::declarations());
+        assert_eq!(0, clr.tables().conflicts().count());
+
+        for input in grammars_tests::DECLARATIONS_INPUTS {
+            assert!(clr.validate(to_tokens(input.iter().cloned())));
+        }
+    }
+
+    #[test]
+    pub fn arithmetic() {
+        let clr = Clr::new(grammars_tests::arithmetic());
+        assert_eq!(0, clr.tables().conflicts().count());
+
+        for input in grammars_tests::ARITHMETIC_INPUTS {
+            assert!(clr.validate(to_tokens(input.iter().cloned())));
+        }
+
+        for input in grammars_tests::NON_ASSOC_ARITHMETIC_INPUTS {
+            assert!(!clr.validate(to_tokens(input.iter().cloned())));
+        }
+    }
 }
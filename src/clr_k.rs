@@ -0,0 +1,418 @@
+use crate::{
+    transitive, Action, Actions, Dfa, Grammar, Map, Position, ReductMap, Set, Step, Tabler, Token,
+};
+use std::{fmt::Debug, ops::Bound};
+
+/// Position with lookahead sequences of up to k terminals
+pub type KPosition<T> = Position<T, Vec<T>>;
+pub type KState<T> = Set<KPosition<T>>;
+
+/// FIRST_k table: the prefixes of up to k terminals derived by each non-terminal
+pub type SeqTable<T> = Map<T, Set<Vec<T>>>;
+
+/// Actions table indexed by lookahead sequences. Terminal sequences have `k` symbols or end with
+/// the eof symbol, and gotos are indexed by a single non-terminal.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct LookTable<T> {
+    pub k: usize,
+    pub rows: Vec<Map<Vec<T>, Action<T>>>,
+}
+
+impl<T> Actions<T> for LookTable<T>
+where
+    T: Ord + Clone,
+{
+    type Code = T;
+
+    fn states(&self) -> usize {
+        self.rows.len()
+    }
+
+    fn code(&self, symbol: &T) -> Option<T> {
+        Some(symbol.clone())
+    }
+
+    /// Some action of `state` over a sequence starting with `symbol`
+    fn action(&self, state: usize, symbol: &T) -> Option<Step<T, T>> {
+        let start = std::slice::from_ref(symbol);
+        self.rows
+            .get(state)?
+            .range::<[T], _>((Bound::Included(start), Bound::Unbounded))
+            .next()
+            .filter(|(seq, _)| seq.first() == Some(symbol))
+            .map(|(_, act)| Step::from(act))
+    }
+
+    fn step(&self, state: usize, window: &[T]) -> Option<Step<T, T>> {
+        self.rows.get(state)?.get(window).map(Step::from)
+    }
+
+    fn expected(&self, state: usize) -> Vec<T> {
+        self.rows.get(state).map_or_else(Vec::new, |row| {
+            let firsts: Set<_> = row.keys().filter_map(|seq| seq.first()).collect();
+            firsts.into_iter().cloned().collect()
+        })
+    }
+
+    fn lookahead(&self) -> usize {
+        self.k
+    }
+}
+
+/// Canonical LR(k) parser: `Clr` with lookaheads of up to `k` terminals. Its `Dfa` peeks `k`
+/// tokens to decide each action.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ClrK<T>
+where
+    T: PartialEq + Ord + Clone + Debug,
+{
+    pub table: Tabler<T>,
+    pub k: usize,
+    pub firsts: SeqTable<T>,
+    pub states: Vec<KState<T>>,
+    pub kernels: Map<KState<T>, usize>,
+    pub actions: LookTable<T>,
+}
+
+impl<T> ClrK<T>
+where
+    T: PartialEq + Ord + Clone + Debug,
+{
+    /// # Panics
+    /// If `k` is 0
+    #[must_use]
+    pub fn new(grammar: Grammar<T>, k: usize) -> Self {
+        Self::with_table(Tabler::new(grammar), k)
+    }
+
+    /// # Panics
+    /// If `k` is 0
+    #[must_use]
+    pub fn with_table(table: Tabler<T>, k: usize) -> Self {
+        assert!(k > 0, "LR(k) parsers must look ahead at least one symbol");
+        let mut parser = Self {
+            table,
+            k,
+            firsts: SeqTable::new(),
+            states: Vec::new(),
+            kernels: Map::new(),
+            actions: LookTable {
+                k,
+                rows: Vec::new(),
+            },
+        };
+        parser.proc_firsts();
+        parser.proc_actions();
+        parser
+    }
+
+    pub fn proc_firsts(&mut self) {
+        let seed = self
+            .table
+            .grammar
+            .rules()
+            .map(|r| (r.name.clone(), Set::new()))
+            .collect();
+        self.firsts = transitive(seed, |t| self.firsts_step(&t));
+    }
+
+    #[must_use]
+    pub fn firsts_step(&self, input: &SeqTable<T>) -> SeqTable<T> {
+        let empty = Set::from([Vec::new()]);
+        self.table
+            .grammar
+            .rules()
+            .map(|r| {
+                let firsts = r
+                    .prods()
+                    .flat_map(|prod| self.first_k(input, &prod.0, &empty))
+                    .collect();
+                (r.name.clone(), firsts)
+            })
+            .collect()
+    }
+
+    /// Concatenates every sequence of `a` with every one of `b`, truncated to `k` symbols
+    #[must_use]
+    pub fn concat(&self, a: &Set<Vec<T>>, b: &Set<Vec<T>>) -> Set<Vec<T>> {
+        let mut seqs = Set::new();
+        for x in a {
+            if x.len() >= self.k {
+                seqs.insert(x.clone());
+                continue;
+            }
+            for y in b {
+                let mut seq = x.clone();
+                seq.extend(y.iter().take(self.k - x.len()).cloned());
+                seqs.insert(seq);
+            }
+        }
+        seqs
+    }
+
+    fn first_k(&self, firsts: &SeqTable<T>, seq: &[T], look: &Set<Vec<T>>) -> Set<Vec<T>> {
+        let mut prefixes = Set::from([Vec::new()]);
+        for sym in seq {
+            if prefixes.iter().all(|p| p.len() >= self.k) {
+                break;
+            }
+            let sym_firsts = firsts
+                .get(sym)
+                .cloned()
+                .unwrap_or_else(|| Set::from([vec![sym.clone()]]));
+            prefixes = self.concat(&prefixes, &sym_firsts);
+        }
+        self.concat(&prefixes, look)
+    }
+
+    /// Returns the FIRST_k set of a symbols sequence followed by `look`. I.e, with k = 2:
+    /// `first_seq`([B c], {[$]}) = {[b c]} if FIRST_2(B) = {[b]}
+    #[must_use]
+    pub fn first_seq(&self, seq: &[T], look: &Set<Vec<T>>) -> Set<Vec<T>> {
+        self.first_k(&self.firsts, seq, look)
+    }
+
+    #[must_use]
+    pub fn closure(&self, state: KState<T>) -> KState<T> {
+        let mut new_state = KState::new();
+        for pos in &state {
+            if let Some(top) = pos.top() {
+                if self.table.grammar.is_terminal(&top) {
+                    continue;
+                }
+                let look = self.first_seq(pos.rest(), &pos.look);
+                for prod in self.table.grammar.rules[&top].prods() {
+                    new_state.insert(Position::new(top.clone(), prod.clone(), 0, look.clone()));
+                }
+            }
+        }
+        new_state.extend(state);
+        new_state
+    }
+
+    #[must_use]
+    pub fn prop_closure(&self, seed: KState<T>) -> KState<T> {
+        transitive(seed, |s| self.closure(s))
+    }
+
+    #[must_use]
+    pub fn sym_filter(state: &KState<T>, sym: &T) -> KState<T> {
+        state
+            .iter()
+            .filter(|p| p.top().as_ref() == Some(sym))
+            .filter_map(Position::clone_next)
+            .collect()
+    }
+
+    pub fn proc_closures(&mut self) {
+        let basis = self
+            .table
+            .basis_state()
+            .into_iter()
+            .map(|pos| {
+                let look = pos.look.iter().map(|l| vec![l.clone()]).collect();
+                Position::new(pos.rule, pos.seq, pos.point, look)
+            })
+            .collect();
+        self.states = vec![self.prop_closure(basis)];
+        self.kernels = Map::from([(KState::new(), 0)]);
+        let mut idx = 0;
+        while idx < self.states.len() {
+            for s in self.table.grammar.symbols() {
+                let kernel = Self::sym_filter(&self.states[idx], &s);
+                if kernel.is_empty() || self.kernels.contains_key(&kernel) {
+                    continue;
+                }
+                let closures = self.prop_closure(kernel.clone());
+                self.kernels.insert(kernel, self.states.len());
+                self.states.push(closures);
+            }
+            idx += 1;
+        }
+    }
+
+    /// Maps the decisions for the passed position. Shifts are indexed by the FIRST_k sequences
+    /// from the position's top.
+    #[must_use]
+    pub fn decision(
+        &self,
+        start: &T,
+        pos: &KPosition<T>,
+        row: &KState<T>,
+    ) -> Map<Vec<T>, Action<T>> {
+        let Some(top) = pos.top() else {
+            let act = if &pos.rule == start {
+                Action::Acc
+            } else {
+                Action::Reduce(pos.rule.clone(), pos.seq.clone())
+            };
+            return pos.look.iter().map(|l| (l.clone(), act.clone())).collect();
+        };
+        let state = self.kernels[&Self::sym_filter(row, &top)];
+        if self.table.grammar.is_terminal(&top) {
+            self.first_seq(&pos.seq.0[pos.point..], &pos.look)
+                .into_iter()
+                .map(|seq| (seq, Action::Shift(state)))
+                .collect()
+        } else {
+            Map::from([(vec![top], Action::Goto(state))])
+        }
+    }
+
+    /// # Panics
+    /// Never.
+    pub fn proc_actions(&mut self) {
+        self.proc_closures();
+        let start = self.table.basis_pos().rule;
+        for row in &self.states {
+            let mut map: Map<Vec<T>, Action<T>> = Map::new();
+            for item in row {
+                for (seq, act) in self.decision(&start, item, row) {
+                    if map.contains_key(&seq) && map[&seq] != act {
+                        *map.get_mut(&seq).unwrap() =
+                            Action::Conflict(Box::new(map.get(&seq).unwrap().clone()), act.into());
+                    } else {
+                        map.insert(seq, act);
+                    }
+                }
+            }
+            self.actions.rows.push(map);
+        }
+        self.solve_conflicts();
+    }
+
+    /// Removes the restricted reductions and solves the shift/reduce conflicts with
+    /// `Tabler::solve`, by the first terminal of each sequence.
+    pub fn solve_conflicts(&mut self) {
+        for row in &mut self.actions.rows {
+            let solved: Vec<_> = row
+                .iter()
+                .filter(|(_, act)| matches!(act, Action::Conflict(..) | Action::Reduce(..)))
+                .map(|(seq, act)| {
+                    (
+                        seq.clone(),
+                        Tabler::solve(&self.table.grammar, &seq[0], act),
+                    )
+                })
+                .collect();
+            for (seq, act) in solved {
+                if let Some(act) = act {
+                    row.insert(seq, act);
+                } else {
+                    row.remove(&seq);
+                }
+            }
+        }
+    }
+
+    pub fn conflicts(&self) -> impl Iterator<Item = &Action<T>> + '_ {
+        self.actions
+            .rows
+            .iter()
+            .flat_map(Map::values)
+            .filter(|a| matches!(a, Action::Conflict(..)))
+    }
+
+    #[must_use]
+    pub fn dfa<'a, M, I: Iterator<Item = Token<M, T>>>(
+        &self,
+        buffer: I,
+        maps: ReductMap<'a, M, T>,
+    ) -> Dfa<'a, M, T, I, LookTable<T>>
+    where
+        M: Clone,
+    {
+        Dfa::new(buffer, self.actions.clone(), maps, self.table.eof())
+    }
+
+    /// Parses the `buffer` without reductors and checks by errors
+    #[must_use]
+    pub fn validate<I: IntoIterator<Item = Token<(), T>>>(&self, buffer: I) -> bool {
+        fn empty<A>(_: Vec<Token<(), A>>) {}
+        let maps = Dfa::<(), T, I::IntoIter>::transparent(&self.table, empty::<T>);
+        self.dfa(buffer.into_iter(), maps).start().is_ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{grammars_tests, to_tokens, Action, CharClass, Clr, ClrK, Map, Parser};
+
+    #[test]
+    fn lr1_equivalence() {
+        for (grammar, inputs, name) in grammars_tests::GRAMMARS {
+            let clr = Clr::new(grammar());
+            let clr_1 = ClrK::new(grammar(), 1);
+            assert_eq!(clr.tables().states.len(), clr_1.states.len(), "{name}");
+            assert_eq!(
+                clr.tables().conflicts().count(),
+                clr_1.conflicts().count(),
+                "{name}"
+            );
+
+            for input in *inputs {
+                assert_eq!(
+                    clr.validate(to_tokens(input.iter().copied())),
+                    clr_1.validate(to_tokens(input.iter().copied())),
+                    "{name}: {input:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn lr2() {
+        let clr = Clr::new(grammars_tests::lr2());
+        assert_ne!(0, clr.tables().conflicts().count());
+
+        let clr_2 = ClrK::new(grammars_tests::lr2(), 2);
+        assert_eq!(0, clr_2.conflicts().count());
+        for input in grammars_tests::LR2_INPUTS {
+            assert!(
+                clr_2.validate(to_tokens(input.iter().copied())),
+                "{input:?}"
+            );
+        }
+        for input in grammars_tests::BAD_LR2_INPUTS {
+            assert!(
+                !clr_2.validate(to_tokens(input.iter().copied())),
+                "{input:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn first_seq() {
+        let clr_2 = ClrK::new(grammars_tests::dragon_book(), 2);
+        let firsts = clr_2.first_seq(&["C", "C"], &[vec!["$"]].into());
+        assert_eq!(
+            firsts,
+            [
+                vec!["c", "c"],
+                vec!["c", "d"],
+                vec!["d", "c"],
+                vec!["d", "d"]
+            ]
+            .into()
+        );
+        let firsts = clr_2.first_seq(&["d"], &[vec!["$"]].into());
+        assert_eq!(firsts, [vec!["d", "$"]].into());
+    }
+
+    #[test]
+    fn restrictions() {
+        let grammar = grammars_tests::scannerless()
+            .with_restriction("Word", CharClass::new(['a'..='z', '0'..='9']));
+        let clr_2 = ClrK::new(grammar, 2);
+        let word_digit = clr_2
+            .actions
+            .rows
+            .iter()
+            .flat_map(Map::iter)
+            .any(|(seq, act)| {
+                seq[0] == "digit" && matches!(act.flatten()[..], [Action::Reduce("Word", _)])
+            });
+        assert!(!word_digit);
+        assert_eq!(clr_2.conflicts().count(), 0);
+    }
+}
@@ -0,0 +1,278 @@
+use std::fmt::{self, Write};
+
+use crate::{ActTable, Action, Map, Tabler};
+
+/// Driver loop of the generated parsers. It uses the emitted `ACTIONS`, `PRODUCTIONS`, `ENTRIES`
+/// and `EOF`.
+const DRIVER: &str = include_str!("codegen/driver.rs");
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CodegenError {
+    /// A symbol from the table isn't in the symbols list
+    UnknownSymbol(String),
+    /// A conflicting action in a state, over a symbol
+    UnsolvedConflict(usize, String),
+    /// A state or production index not fitting in the 30 bits of an action target
+    TooLarge(usize),
+}
+
+impl fmt::Display for CodegenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownSymbol(s) => f.write_fmt(format_args!("unknown symbol {s}")),
+            Self::UnsolvedConflict(state, sym) => f.write_fmt(format_args!(
+                "unsolved conflict in state {state} over {sym}"
+            )),
+            Self::TooLarge(n) => f.write_fmt(format_args!("{n} is too large for an action")),
+        }
+    }
+}
+
+/// `target << 2 | kind`, if `target` fits in 30 bits
+fn encode(target: usize, kind: u32) -> Result<u32, CodegenError> {
+    u32::try_from(target)
+        .ok()
+        .filter(|target| target.leading_zeros() >= 2)
+        .and_then(|target| target.checked_shl(2))
+        .map(|code| code | kind)
+        .ok_or(CodegenError::TooLarge(target))
+}
+
+/// Emits a Rust source with the actions table as static arrays over symbol codes (indexes in
+/// `symbols`), and a specialized driver loop (`parse`). So, the generated parser doesn't depend on
+/// lrp at runtime. Emitted items:
+/// - `SYMBOLS`: `Debug` name of each symbol code
+/// - `EOF`: end of input symbol code
+/// - `ENTRIES`: initial state of each entry point (`entries`), selected by the `entry` argument of
+///   `parse`
+/// - `PRODUCTIONS`: rule code, body length and index in rule of each reducible production
+/// - `ACTIONS`: a row of actions by state, indexed by symbol code. Each action is coded as
+///   `target << 2 | kind`, where kind is 0 for errors, 1 for shifts and gotos (targeting a state),
+///   2 for reductions (targeting a production) and 3 for acceptance.
+/// # Errors
+/// If a symbol of `table` isn't in `symbols`, raises a `CodegenError::UnknownSymbol`. Conflicting
+/// actions can't be emitted, raising a `CodegenError::UnsolvedConflict`. States and productions
+/// past 30 bits raise a `CodegenError::TooLarge`.
+/// # Panics
+/// Never.
+pub fn table_rust<T>(
+    table: &ActTable<T>,
+    eof: &T,
+    symbols: &[T],
+    entries: &[usize],
+) -> Result<String, CodegenError>
+where
+    T: Ord + fmt::Debug,
+{
+    let ids: Map<&T, usize> = symbols.iter().zip(0..).collect();
+    let id = |sym: &T| {
+        ids.get(sym)
+            .copied()
+            .ok_or_else(|| CodegenError::UnknownSymbol(format!("{sym:?}")))
+    };
+
+    let mut prods = Map::new();
+    for act in table.iter().flat_map(Map::values) {
+        if let Action::Reduce(rule, prod) = act {
+            let next = prods.len();
+            prods
+                .entry((id(rule)?, prod.1))
+                .or_insert((next, prod.0.len()));
+        }
+    }
+
+    let mut rows = Vec::with_capacity(table.len());
+    for (state, row) in table.iter().enumerate() {
+        let mut codes = vec![0; symbols.len()];
+        for (sym, act) in row {
+            codes[id(sym)?] = match act {
+                Action::Shift(to) | Action::Goto(to) => encode(*to, 1)?,
+                Action::Reduce(rule, prod) => encode(prods[&(id(rule)?, prod.1)].0, 2)?,
+                Action::Acc => 3,
+                Action::Conflict(..) => {
+                    return Err(CodegenError::UnsolvedConflict(state, format!("{sym:?}")))
+                }
+            };
+        }
+        rows.push(codes);
+    }
+
+    let mut ordered: Vec<_> = prods.iter().collect();
+    ordered.sort_by_key(|(_, (i, _))| *i);
+
+    let mut out = String::from("// Generated by lrp. Do not edit.\n\n");
+    let names: Vec<_> = symbols
+        .iter()
+        .map(|s| format!("{:?}", format!("{s:?}")))
+        .collect();
+    writeln!(
+        out,
+        "pub static SYMBOLS: [&str; {}] = [{}];\n",
+        names.len(),
+        names.join(", ")
+    )
+    .unwrap();
+    writeln!(out, "pub const EOF: usize = {};\n", id(eof)?).unwrap();
+    let entries: Vec<_> = entries.iter().map(ToString::to_string).collect();
+    writeln!(
+        out,
+        "pub static ENTRIES: [usize; {}] = [{}];\n",
+        entries.len(),
+        entries.join(", ")
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "pub static PRODUCTIONS: [(usize, usize, usize); {}] = [",
+        ordered.len()
+    )
+    .unwrap();
+    for ((rule, idx), (_, len)) in ordered {
+        writeln!(out, "    ({rule}, {len}, {idx}),").unwrap();
+    }
+    writeln!(out, "];\n").unwrap();
+    writeln!(
+        out,
+        "pub static ACTIONS: [[u32; {}]; {}] = [",
+        symbols.len(),
+        rows.len()
+    )
+    .unwrap();
+    for row in rows {
+        let codes: Vec<_> = row.iter().map(ToString::to_string).collect();
+        writeln!(out, "    [{}],", codes.join(", ")).unwrap();
+    }
+    writeln!(out, "];\n").unwrap();
+    out.push_str(DRIVER);
+    Ok(out)
+}
+
+impl<T> Tabler<T>
+where
+    T: PartialEq + PartialOrd + Ord + Clone + fmt::Debug,
+{
+    /// Emits the actions table with `table_rust`, using the grammar's symbols (in
+    /// `Grammar::symbols` order) as the symbol codes. The entry points are those of
+    /// `Grammar::with_entries`, in declaration order, or just the state 0 without them.
+    /// # Errors
+    /// The same of `table_rust`
+    pub fn to_rust(&self) -> Result<String, CodegenError> {
+        let symbols: Vec<_> = self.grammar.symbols().collect();
+        let entries = if self.grammar.entries.is_empty() {
+            vec![0]
+        } else {
+            self.grammar
+                .entries
+                .iter()
+                .filter_map(|(start, _)| self.entry(start))
+                .collect()
+        };
+        table_rust(&self.actions, &self.eof(), &symbols, &entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CodegenError;
+    use crate::{grammar_map, grammars_tests, Action, Grammar, Lalr, Map, Parser};
+
+    const DRAGON_BOOK: &str = r#"// Generated by lrp. Do not edit.
+
+pub static SYMBOLS: [&str; 5] = ["\"$\"", "\"C\"", "\"S\"", "\"c\"", "\"d\""];
+
+pub const EOF: usize = 0;
+
+pub static ENTRIES: [usize; 1] = [0];
+
+pub static PRODUCTIONS: [(usize, usize, usize); 2] = [
+    (1, 1, 1),
+    (1, 2, 0),
+];
+
+pub static ACTIONS: [[u32; 5]; 6] = [
+    [0, 5, 0, 9, 13],
+    [0, 17, 0, 9, 13],
+    [0, 21, 0, 9, 13],
+    [2, 0, 0, 2, 2],
+    [3, 0, 0, 0, 0],
+    [6, 0, 0, 6, 6],
+];
+
+"#;
+
+    /// The same of `DRAGON_BOOK`
+    mod dragon_book {
+        pub static SYMBOLS: [&str; 5] = ["\"$\"", "\"C\"", "\"S\"", "\"c\"", "\"d\""];
+
+        pub const EOF: usize = 0;
+
+        pub static ENTRIES: [usize; 1] = [0];
+
+        pub static PRODUCTIONS: [(usize, usize, usize); 2] = [(1, 1, 1), (1, 2, 0)];
+
+        pub static ACTIONS: [[u32; 5]; 6] = [
+            [0, 5, 0, 9, 13],
+            [0, 17, 0, 9, 13],
+            [0, 21, 0, 9, 13],
+            [2, 0, 0, 2, 2],
+            [3, 0, 0, 0, 0],
+            [6, 0, 0, 6, 6],
+        ];
+
+        include!("codegen/driver.rs");
+    }
+
+    #[test]
+    fn dragon_book() {
+        let lalr = Lalr::new(grammars_tests::dragon_book());
+        let code = lalr.tables().to_rust().unwrap();
+        assert_eq!(code, format!("{DRAGON_BOOK}{}", super::DRIVER));
+
+        let code = |sym: &&str| {
+            dragon_book::SYMBOLS
+                .iter()
+                .position(|s| s == &format!("{sym:?}"))
+                .unwrap()
+        };
+        let sum = |_, body: Vec<usize>| body.iter().sum();
+        for input in grammars_tests::DRAGON_BOOK_INPUTS {
+            let tokens = input.iter().map(|sym| (code(sym), 1));
+            assert!(dragon_book::parse(0, tokens, sum).is_ok(), "{input:?}");
+        }
+        let tokens = ["d", "d", "d"].iter().map(|sym| (code(sym), 1));
+        let err = dragon_book::parse(0, tokens, sum);
+        assert!(matches!(err, Err(dragon_book::ParseError { index: 2, .. })));
+    }
+
+    #[test]
+    fn conflicts() {
+        let lalr = Lalr::new(grammars_tests::ucalgary_uni_oth_lr1());
+        assert!(matches!(
+            lalr.tables().to_rust(),
+            Err(CodegenError::UnsolvedConflict(..))
+        ));
+    }
+
+    #[test]
+    fn entries() {
+        let rules = grammar_map! {
+            "S" -> "x",
+            "E" -> "E" "+" "n" | "n"
+        };
+        let grammar = Grammar::with_entries(["S", "E"], rules, "$").unwrap();
+        let lalr = Lalr::new(grammar);
+        let code = lalr.tables().to_rust().unwrap();
+        let s = lalr.tables().entry(&"S").unwrap();
+        let e = lalr.tables().entry(&"E").unwrap();
+        assert!(code.contains(&format!("pub static ENTRIES: [usize; 2] = [{s}, {e}];\n")));
+    }
+
+    #[test]
+    fn too_large() {
+        let row = Map::from([("a", Action::Shift(1 << 30))]);
+        assert_eq!(
+            super::table_rust(&vec![row], &"a", &["a"], &[0]),
+            Err(CodegenError::TooLarge(1 << 30))
+        );
+    }
+}
@@ -0,0 +1,69 @@
+const KIND_MASK: u32 = 0b11;
+const SHIFT: u32 = 1;
+const REDUCE: u32 = 2;
+const ACC: u32 = 3;
+
+/// Unexpected `symbol` at the `index`-th input token, in `state`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseError {
+    pub index: usize,
+    pub state: usize,
+    pub symbol: usize,
+}
+
+/// Parses `input`, a sequence of symbol codes (indexes in `SYMBOLS`) and items, from the `entry`
+/// point (index in `ENTRIES`). `reduce` receives the reduced production (index in `PRODUCTIONS`)
+/// and the items of its body.
+/// # Errors
+/// When `input` isn't accepted by the grammar.
+/// # Panics
+/// If `entry` isn't an index in `ENTRIES`.
+pub fn parse<T>(
+    entry: usize,
+    input: impl IntoIterator<Item = (usize, T)>,
+    mut reduce: impl FnMut(usize, Vec<T>) -> T,
+) -> Result<T, ParseError> {
+    let mut input = input.into_iter();
+    let mut next = input.next();
+    let mut index = 0;
+    let mut states = vec![ENTRIES[entry]];
+    let mut items = Vec::new();
+    loop {
+        let state = states[states.len() - 1];
+        let symbol = next.as_ref().map_or(EOF, |(symbol, _)| *symbol);
+        let error = ParseError {
+            index,
+            state,
+            symbol,
+        };
+        let action = *ACTIONS[state].get(symbol).ok_or(error)?;
+        let to = (action >> 2) as usize;
+        match action & KIND_MASK {
+            SHIFT => {
+                let (_, item) = next.ok_or(error)?;
+                items.push(item);
+                states.push(to);
+                next = input.next();
+                index += 1;
+            }
+            REDUCE => {
+                let (rule, len, _) = PRODUCTIONS[to];
+                let body = items.split_off(items.len() - len);
+                states.truncate(states.len() - len);
+                items.push(reduce(to, body));
+                let state = states[states.len() - 1];
+                let goto = ACTIONS[state][rule];
+                if goto & KIND_MASK != SHIFT {
+                    return Err(ParseError {
+                        index,
+                        state,
+                        symbol: rule,
+                    });
+                }
+                states.push((goto >> 2) as usize);
+            }
+            ACC if next.is_none() => return items.pop().ok_or(error),
+            _ => return Err(error),
+        }
+    }
+}
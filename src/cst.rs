@@ -0,0 +1,248 @@
+use std::fmt;
+
+use crate::{Meta, Span, Token};
+
+/// Lossless concrete syntax tree, built by a `Dfa` without reductors (see `Parser::cst`). Every
+/// input token is kept as a leaf, and the root is the start rule.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Cst<L, M> {
+    Leaf {
+        ty: M,
+        item: L,
+        span: Span,
+    },
+    Node {
+        rule: M,
+        /// Index of the production in `rule`
+        prod: usize,
+        children: Vec<Self>,
+        /// Span covering the tokens of the node. Empty productions have none.
+        span: Option<Span>,
+    },
+}
+
+impl<L, M> Cst<L, M>
+where
+    M: Clone,
+{
+    /// Wraps an input token into a leaf token
+    #[must_use]
+    pub fn leaf(token: Token<Meta<L>, M>) -> Token<Self, M> {
+        let ty = token.ty.clone();
+        Token::new(
+            Self::Leaf {
+                ty: token.ty,
+                item: token.item.item,
+                span: token.item.span,
+            },
+            ty,
+        )
+    }
+
+    /// Wraps input tokens with `Cst::leaf`
+    pub fn leaves(
+        input: impl IntoIterator<Item = Token<Meta<L>, M>>,
+    ) -> impl Iterator<Item = Token<Self, M>> {
+        input.into_iter().map(Self::leaf)
+    }
+
+    /// Builds the node of the `prod`-th production of `rule`. It's a `TreeFn`.
+    #[must_use]
+    pub fn node(rule: &M, prod: usize, children: Vec<Token<Self, M>>) -> Self {
+        let children: Vec<_> = children.into_iter().map(|t| t.item).collect();
+        let mut spans = children.iter().filter_map(Self::span);
+        let span = spans.next().map(|first| {
+            let last = spans.last().unwrap_or(first);
+            Span::new(first.start, last.end)
+        });
+        Self::Node {
+            rule: rule.clone(),
+            prod,
+            children,
+            span,
+        }
+    }
+}
+
+impl<L, M> Cst<L, M> {
+    /// Token type of a leaf or rule of a node
+    #[must_use]
+    pub const fn symbol(&self) -> &M {
+        match self {
+            Self::Leaf { ty, .. } => ty,
+            Self::Node { rule, .. } => rule,
+        }
+    }
+
+    #[must_use]
+    pub const fn span(&self) -> Option<Span> {
+        match self {
+            Self::Leaf { span, .. } => Some(*span),
+            Self::Node { span, .. } => *span,
+        }
+    }
+
+    #[must_use]
+    pub fn children(&self) -> &[Self] {
+        match self {
+            Self::Leaf { .. } => &[],
+            Self::Node { children, .. } => children,
+        }
+    }
+
+    #[must_use]
+    pub const fn is_leaf(&self) -> bool {
+        matches!(self, Self::Leaf { .. })
+    }
+
+    /// Iterates the tree in pre-order: each node before its children
+    pub fn preorder(&self) -> impl Iterator<Item = &Self> {
+        let mut stack = vec![self];
+        std::iter::from_fn(move || {
+            let node = stack.pop()?;
+            stack.extend(node.children().iter().rev());
+            Some(node)
+        })
+    }
+
+    /// Iterates the leaves, in input order
+    pub fn tokens(&self) -> impl Iterator<Item = &Self> {
+        self.preorder().filter(|node| node.is_leaf())
+    }
+
+    /// Walks the tree in depth-first order with a `Visitor`
+    pub fn walk(&self, visitor: &mut impl Visitor<L, M>) {
+        if visitor.enter(self) {
+            for child in self.children() {
+                child.walk(visitor);
+            }
+        }
+        visitor.leave(self);
+    }
+
+    /// Renders the tree as a s-expression. I.e: `("S" ("C" "c" ("C" "d")) ("C" "d"))`
+    #[must_use]
+    pub fn sexp(&self) -> String
+    where
+        M: fmt::Debug,
+    {
+        match self {
+            Self::Leaf { ty, .. } => format!("{ty:?}"),
+            Self::Node { rule, children, .. } => {
+                let mut out = format!("({rule:?}");
+                for child in children {
+                    out.push(' ');
+                    out.push_str(&child.sexp());
+                }
+                out.push(')');
+                out
+            }
+        }
+    }
+}
+
+/// Depth-first `Cst` visitor
+pub trait Visitor<L, M> {
+    /// Called before the children of `node`. Returns if they must be visited.
+    fn enter(&mut self, node: &Cst<L, M>) -> bool {
+        let _ = node;
+        true
+    }
+
+    /// Called after the children of `node`
+    fn leave(&mut self, node: &Cst<L, M>) {
+        let _ = node;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{grammars_tests, Cst, Error, Lalr, Meta, Parser, Span, Token, Visitor};
+
+    fn tokens(src: &str) -> Vec<Token<Meta<&str>, &str>> {
+        src.split(' ')
+            .scan(0, |start, word| {
+                let span = Span::new(*start, *start + word.len());
+                *start = span.end + 1;
+                Some(Token::new(Meta::new(word, span), word))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn dragon_book() {
+        let lalr = Lalr::new(grammars_tests::dragon_book());
+        let src = "c d c c d";
+        let cst = lalr.cst(tokens(src)).unwrap();
+        assert_eq!(
+            cst.sexp(),
+            r#"("S" ("C" "c" ("C" "d")) ("C" "c" ("C" "c" ("C" "d"))))"#
+        );
+        assert_eq!(cst.span(), Some(Span::new(0, src.len())));
+        assert_eq!(cst.children()[1].span(), Some(Span::new(4, src.len())));
+
+        assert!(lalr.cst(tokens("c c")).is_err());
+        assert!(matches!(
+            lalr.cst(tokens("d d c")),
+            Err(Error::UnexpectedToken {
+                index: 2,
+                span: Some(Span { start: 4, end: 5 }),
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn lossless() {
+        let lalr = Lalr::new(grammars_tests::statements());
+        let src = "id = n ; id = id ;";
+        let cst = lalr.cst(tokens(src)).unwrap();
+        let Cst::Node { rule, prod, .. } = &cst else {
+            panic!("{cst:?}")
+        };
+        assert_eq!((*rule, *prod), ("S", 0));
+        assert_eq!(cst.span(), Some(Span::new(0, src.len())));
+        let words: Vec<_> = cst
+            .tokens()
+            .map(|leaf| match leaf {
+                Cst::Leaf { item, .. } => *item,
+                Cst::Node { .. } => unreachable!(),
+            })
+            .collect();
+        assert_eq!(words.join(" "), src);
+        assert_eq!(
+            cst.sexp(),
+            r#"("S" ("Stmts" ("Stmts" ("Stmt" "id" "=" ("Value" "n") ";")) ("Stmt" "id" "=" ("Value" "id") ";")))"#
+        );
+    }
+
+    #[test]
+    fn visitor() {
+        #[derive(Default)]
+        struct Depth {
+            current: usize,
+            max: usize,
+            leaves: usize,
+        }
+        impl Visitor<&str, &str> for Depth {
+            fn enter(&mut self, node: &Cst<&str, &str>) -> bool {
+                self.current += 1;
+                self.max = self.max.max(self.current);
+                self.leaves += usize::from(node.is_leaf());
+                // values aren't visited
+                node.symbol() != &"Value"
+            }
+
+            fn leave(&mut self, _: &Cst<&str, &str>) {
+                self.current -= 1;
+            }
+        }
+        let lalr = Lalr::new(grammars_tests::statements());
+        let cst = lalr.cst(tokens("id = n ; id = n ;")).unwrap();
+        let mut depth = Depth::default();
+        cst.walk(&mut depth);
+        assert_eq!(depth.current, 0);
+        assert_eq!((depth.max, depth.leaves), (5, 6));
+        assert_eq!(cst.preorder().count(), 15);
+    }
+}
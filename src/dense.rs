@@ -0,0 +1,353 @@
+use std::fmt;
+
+use crate::{ActTable, Action, Actions, Map, Set, Step, Tabler};
+
+/// Storage of the `DenseTable` rows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Layout {
+    /// Every row is fully stored: fast, but wastes space with empty entries
+    Flat,
+    /// Row displacement (comb) compression: rows are overlapped, so empty entries are reused
+    Comb,
+}
+
+/// Cells of a `DenseTable`. Each cell is an action encoded as an integer (see `DenseTable::new`),
+/// or 0 for no action.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Rows {
+    /// The cell of `state` and `symbol` is `cells[state * width + symbol]`
+    Flat { width: usize, cells: Vec<u32> },
+    /// The cell of `state` and `symbol` is `cells[base[state] + symbol]`, if `check` at the same
+    /// position is `state`
+    Comb {
+        base: Vec<usize>,
+        check: Vec<u32>,
+        cells: Vec<u32>,
+    },
+}
+
+/// Production reduced by a `DenseTable`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reduction<T> {
+    pub rule: T,
+    /// Interned code of `rule`, to look up the goto after the reduction
+    pub code: u16,
+    /// Amount of popped items
+    pub size: usize,
+    /// Index of the production in `rule`
+    pub prod: usize,
+}
+
+const SHIFT: u32 = 1;
+const REDUCE: u32 = 2;
+const GOTO: u32 = 3;
+
+/// A compact actions table: symbols are interned into `u16` codes, and each action is encoded into
+/// an integer cell. So, a reduction is referenced by its production id in `DenseTable::prods`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DenseTable<T> {
+    /// Interned code of each symbol
+    pub symbols: Map<T, u16>,
+    /// Reduced productions, by id
+    pub prods: Vec<Reduction<T>>,
+    /// Conflicting actions, by id
+    pub conflicts: Vec<(Action<T>, Action<T>)>,
+    pub rows: Rows,
+    pub states: usize,
+}
+
+impl<T> DenseTable<T>
+where
+    T: Ord + Clone,
+{
+    /// Builds the table, encoding each action as `value << 2 | kind`: shifts and gotos by their
+    /// state, with kinds 1 and 3, and reductions by their production id, with kind 2. With kind 0,
+    /// the value 1 accepts and the next ones are conflicts, by id plus 2.
+    /// # Panics
+    /// If there are more than 65536 distinct symbols, or a state or id doesn't fit in 30 bits.
+    #[must_use]
+    pub fn new(table: &ActTable<T>, layout: Layout) -> Self {
+        let rules = table
+            .iter()
+            .flat_map(Map::values)
+            .filter_map(|act| match act {
+                Action::Reduce(rule, _) => Some(rule),
+                _ => None,
+            });
+        let symbols: Map<T, u16> = (table.iter().flat_map(Map::keys))
+            .chain(rules)
+            .cloned()
+            .collect::<Set<_>>()
+            .into_iter()
+            .enumerate()
+            .map(|(i, sym)| {
+                (
+                    sym,
+                    u16::try_from(i).expect("more than 65536 distinct symbols"),
+                )
+            })
+            .collect();
+        let mut prods = Vec::new();
+        let mut ids = Map::new();
+        let mut conflicts = Vec::new();
+        let rows: Vec<Vec<(usize, u32)>> = table
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|(sym, act)| {
+                        let cell = match act {
+                            Action::Shift(to) => Self::encode(*to, SHIFT),
+                            Action::Goto(to) => Self::encode(*to, GOTO),
+                            Action::Reduce(rule, prod) => {
+                                let id = *ids.entry((rule, prod.1)).or_insert_with(|| {
+                                    prods.push(Reduction {
+                                        rule: rule.clone(),
+                                        code: symbols[rule],
+                                        size: prod.0.len(),
+                                        prod: prod.1,
+                                    });
+                                    prods.len() - 1
+                                });
+                                Self::encode(id, REDUCE)
+                            }
+                            Action::Acc => Self::encode(1, 0),
+                            Action::Conflict(a, b) => {
+                                conflicts.push(((**a).clone(), (**b).clone()));
+                                Self::encode(conflicts.len() + 1, 0)
+                            }
+                        };
+                        (usize::from(symbols[sym]), cell)
+                    })
+                    .collect()
+            })
+            .collect();
+        let rows = match layout {
+            Layout::Flat => Self::flat(&rows, symbols.len()),
+            Layout::Comb => Self::comb(&rows),
+        };
+        Self {
+            symbols,
+            prods,
+            conflicts,
+            rows,
+            states: table.len(),
+        }
+    }
+
+    fn encode(value: usize, kind: u32) -> u32 {
+        let value = u32::try_from(value)
+            .ok()
+            .and_then(|value| value.checked_mul(4));
+        value.expect("a state or id doesn't fit in 30 bits") | kind
+    }
+
+    fn decode(&self, cell: u32) -> Option<Step<T, u16>> {
+        let value = usize::try_from(cell >> 2).ok()?;
+        match cell & 3 {
+            SHIFT => Some(Step::Shift(value)),
+            GOTO => Some(Step::Goto(value)),
+            REDUCE => {
+                let prod = self.prods.get(value)?;
+                Some(Step::Reduce {
+                    rule: prod.rule.clone(),
+                    code: prod.code,
+                    size: prod.size,
+                    prod: prod.prod,
+                })
+            }
+            _ => match value {
+                0 => None,
+                1 => Some(Step::Acc),
+                id => {
+                    let (a, b) = self.conflicts.get(id - 2)?;
+                    Some(Step::Conflict(a.clone(), b.clone()))
+                }
+            },
+        }
+    }
+
+    fn flat(rows: &[Vec<(usize, u32)>], width: usize) -> Rows {
+        let mut cells = vec![0; rows.len() * width];
+        for (state, row) in rows.iter().enumerate() {
+            for &(sym, cell) in row {
+                cells[state * width + sym] = cell;
+            }
+        }
+        Rows::Flat { width, cells }
+    }
+
+    /// Places the densest rows first, each one in the first displacement where its entries don't
+    /// collide with the already placed ones.
+    fn comb(rows: &[Vec<(usize, u32)>]) -> Rows {
+        let mut order: Vec<_> = (0..rows.len()).collect();
+        order.sort_by_key(|&state| std::cmp::Reverse(rows[state].len()));
+        let mut base = vec![0; rows.len()];
+        let mut check = Vec::new();
+        let mut cells = Vec::new();
+        for state in order {
+            let row = &rows[state];
+            let fits = |b: usize| {
+                row.iter()
+                    .all(|&(sym, _)| check.get(b + sym).into_iter().all(|&c| c == u32::MAX))
+            };
+            let displacement = (0..).find(|&b| fits(b)).unwrap_or_default();
+            base[state] = displacement;
+            for &(sym, cell) in row {
+                let pos = displacement + sym;
+                if pos >= cells.len() {
+                    cells.resize(pos + 1, 0);
+                    check.resize(pos + 1, u32::MAX);
+                }
+                cells[pos] = cell;
+                check[pos] = u32::try_from(state).expect("too many states");
+            }
+        }
+        Rows::Comb { base, check, cells }
+    }
+
+    /// Amount of stored cells
+    #[must_use]
+    pub fn size(&self) -> usize {
+        match &self.rows {
+            Rows::Flat { cells, .. } | Rows::Comb { cells, .. } => cells.len(),
+        }
+    }
+
+    fn cell(&self, state: usize, sym: usize) -> u32 {
+        match &self.rows {
+            Rows::Flat { width, cells } => cells.get(state * width + sym).copied(),
+            Rows::Comb { base, check, cells } => base.get(state).and_then(|b| {
+                let pos = b + sym;
+                (check.get(pos).map(|&c| c as usize) == Some(state)).then(|| cells[pos])
+            }),
+        }
+        .unwrap_or_default()
+    }
+}
+
+impl<T> Actions<T> for DenseTable<T>
+where
+    T: Ord + Clone,
+{
+    type Code = u16;
+
+    fn states(&self) -> usize {
+        self.states
+    }
+
+    fn code(&self, symbol: &T) -> Option<u16> {
+        self.symbols.get(symbol).copied()
+    }
+
+    fn step(&self, state: usize, window: &[u16]) -> Option<Step<T, u16>> {
+        let sym = usize::from(*window.first()?);
+        if state >= self.states {
+            return None;
+        }
+        self.decode(self.cell(state, sym))
+    }
+
+    fn expected(&self, state: usize) -> Vec<T> {
+        self.symbols
+            .iter()
+            .filter(|(_, &sym)| self.cell(state, usize::from(sym)) != 0)
+            .map(|(sym, _)| sym.clone())
+            .collect()
+    }
+}
+
+impl<T> Tabler<T>
+where
+    T: PartialEq + PartialOrd + Ord + Clone + fmt::Debug,
+{
+    /// Builds a `DenseTable` from the actions table
+    #[must_use]
+    pub fn dense(&self, layout: Layout) -> DenseTable<T> {
+        DenseTable::new(&self.actions, layout)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DenseTable;
+    use crate::{grammars_tests, Action, Actions, Layout, Map, Parser, Slr, Step, Token};
+
+    /// Drops the code of the rule of a reduction, that is interned by each table
+    fn uncoded<T, C>(step: Option<Step<T, C>>) -> Option<Step<T, ()>> {
+        step.map(|step| match step {
+            Step::Shift(to) => Step::Shift(to),
+            Step::Goto(to) => Step::Goto(to),
+            Step::Reduce {
+                rule, size, prod, ..
+            } => Step::Reduce {
+                rule,
+                code: (),
+                size,
+                prod,
+            },
+            Step::Acc => Step::Acc,
+            Step::Conflict(a, b) => Step::Conflict(a, b),
+        })
+    }
+
+    #[test]
+    fn equivalence() {
+        for (grammar, inputs, name) in grammars_tests::GRAMMARS {
+            let slr = Slr::new(grammar());
+            let table = &slr.tables().actions;
+            for layout in [Layout::Flat, Layout::Comb] {
+                let dense = slr.tables().dense(layout);
+                assert_eq!(dense.states(), table.states());
+                for state in 0..=table.len() {
+                    assert_eq!(dense.expected(state), table.expected(state), "{name}");
+                    for sym in slr.tables().grammar.symbols() {
+                        assert_eq!(
+                            uncoded(dense.action(state, &sym)),
+                            uncoded(table.action(state, &sym)),
+                            "{name}: {layout:?} {state} {sym}"
+                        );
+                    }
+                }
+
+                for input in *inputs {
+                    let buffer = input.iter().map(|&t| Token::new((), t));
+                    let mut dfa =
+                        slr.dense_dfa(buffer.clone(), slr.empty::<std::iter::Empty<_>>(), layout);
+                    assert_eq!(
+                        dfa.parse(buffer.clone()).is_ok(),
+                        slr.validate(buffer),
+                        "{name}: {input:?}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn comb_compression() {
+        let slr = Slr::new(grammars_tests::scanner());
+        let flat = slr.tables().dense(Layout::Flat);
+        let comb = slr.tables().dense(Layout::Comb);
+        assert!(comb.size() < flat.size());
+        let reductions = (slr.tables().actions.iter().flat_map(Map::values))
+            .filter(|act| matches!(act, Action::Reduce(..)))
+            .count();
+        assert!(comb.prods.len() < reductions);
+    }
+
+    #[test]
+    #[should_panic(expected = "more than 65536 distinct symbols")]
+    fn too_many_symbols() {
+        let row = (0..=usize::from(u16::MAX) + 1)
+            .map(|s| (s, Action::Acc))
+            .collect();
+        let _ = DenseTable::new(&vec![row], Layout::Flat);
+    }
+
+    #[test]
+    #[should_panic(expected = "a state or id doesn't fit in 30 bits")]
+    fn too_many_states() {
+        let row = Map::from([("a", Action::Shift(1 << 30))]);
+        let _ = DenseTable::new(&vec![row], Layout::Flat);
+    }
+}
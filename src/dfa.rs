@@ -1,6 +1,6 @@
-use std::{fmt, iter::Peekable, rc::Rc};
+use std::{collections::VecDeque, convert::Infallible, fmt, iter::Peekable, rc::Rc};
 
-use crate::{ActTable, Map, Production, Tabler, Token};
+use crate::{ActTable, Cst, Grammar, Map, Meta, Production, Set, Span, Tabler, Token};
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Action<T> {
@@ -11,14 +11,30 @@ pub enum Action<T> {
     Conflict(Box<Action<T>>, Box<Action<T>>),
 }
 
+impl<T> Action<T> {
+    /// Lists every action inside a conflict tree. For non-conflicting actions, returns itself.
+    #[must_use]
+    pub fn flatten(&self) -> Vec<&Self> {
+        match self {
+            Self::Conflict(a, b) => a.flatten().into_iter().chain(b.flatten()).collect(),
+            _ => vec![self],
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub enum Error<T>
+pub enum Error<T, E = Infallible>
 where
     T: fmt::Debug,
 {
-    /// Found a unexpected token. Contains a vector with correct tokens after it. Indicates a bad
-    /// input.
-    UnexpectedToken(T, Vec<T>),
+    /// Found a unexpected token. Contains the correct tokens after it, the token index in the input
+    /// and its span, when known. Indicates a bad input.
+    UnexpectedToken {
+        found: T,
+        expected: Vec<T>,
+        index: usize,
+        span: Option<Span>,
+    },
     /// Specialized version of `Error::UnexpectedToken` for buffer end in a Shifting Action. Indicates a bad input.
     UnexpectedEof,
     /// Unsolved conflict. When the current state contains a conflicting action. It's the unique
@@ -31,16 +47,37 @@ where
     /// Incomplete execution. Finished the parsing without consume entire buffer. Indicates a bad
     /// input.
     IncompleteExec,
+    /// Found a char outside every character class terminal, at the char `index` of the input.
+    /// Indicates a bad input.
+    UnknownChar {
+        found: char,
+        index: usize,
+        span: Span,
+    },
+    /// A semantic action failed reducing the `prod`-th production of `rule`. Contains the span of
+    /// the reduced items, when known.
+    Semantic {
+        rule: T,
+        prod: usize,
+        span: Option<Span>,
+        error: E,
+    },
 }
 
-impl<T> fmt::Display for Error<T>
+impl<T, E> fmt::Display for Error<T, E>
 where
     T: fmt::Debug,
+    E: fmt::Display,
 {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Self::UnexpectedToken(found, expected) => f.write_fmt(format_args!(
-                "unexpected token {found:?}. expected {expected:?}"
+            Self::UnexpectedToken {
+                found,
+                expected,
+                index,
+                ..
+            } => f.write_fmt(format_args!(
+                "unexpected token {found:?} at {index}. expected {expected:?}"
             )),
             Self::UnexpectedEof => f.write_str("unexpected eof"),
             Self::Conflict(a, b) => f.write_fmt(format_args!("conflicting action {a:?} and {b:?}")),
@@ -51,74 +88,393 @@ where
             Self::IncompleteExec => {
                 f.write_str("finished the parsing without consume entire buffer")
             }
+            Self::UnknownChar { found, index, .. } => {
+                f.write_fmt(format_args!("unknown char {found:?} at {index}"))
+            }
+            Self::Semantic {
+                rule, prod, error, ..
+            } => f.write_fmt(format_args!(
+                "{error} reducing the production {prod} of {rule:?}"
+            )),
+        }
+    }
+}
+
+impl<T> Error<T>
+where
+    T: fmt::Debug,
+{
+    /// Converts a parsing error into an error of any semantic error type
+    #[must_use]
+    pub fn cast<E>(self) -> Error<T, E> {
+        match self {
+            Self::UnexpectedToken {
+                found,
+                expected,
+                index,
+                span,
+            } => Error::UnexpectedToken {
+                found,
+                expected,
+                index,
+                span,
+            },
+            Self::UnexpectedEof => Error::UnexpectedEof,
+            Self::Conflict(a, b) => Error::Conflict(a, b),
+            Self::MissingPreviousState => Error::MissingPreviousState,
+            Self::StateNotSpecified => Error::StateNotSpecified,
+            Self::IncompleteExec => Error::IncompleteExec,
+            Self::UnknownChar { found, index, span } => Error::UnknownChar { found, index, span },
+            Self::Semantic { error, .. } => match error {},
+        }
+    }
+}
+
+impl<T, E> Error<T, E>
+where
+    T: fmt::Debug,
+{
+    /// Span of the token, or reduced items, that raised the error, when known
+    #[must_use]
+    pub const fn span(&self) -> Option<Span> {
+        match self {
+            Self::UnexpectedToken { span, .. } | Self::Semantic { span, .. } => *span,
+            Self::UnknownChar { span, .. } => Some(*span),
+            _ => None,
         }
     }
+
+    /// Renders the error message with its position in `src`, pointing the token with carets. Errors
+    /// without span are rendered as their message.
+    #[must_use]
+    pub fn diagnostic(&self, src: &str) -> String
+    where
+        E: fmt::Display,
+    {
+        self.span().map_or_else(
+            || format!("error: {self}"),
+            |span| format!("error: {self}\n{}", span.caret(src)),
+        )
+    }
 }
 
 pub type Result<T> = BaseResult<T, Error<T>>;
 pub type BaseResult<T, E> = std::result::Result<T, E>;
 
-// TODO: Allow to move the value
-pub type ReductFn<T, M> = fn(&[Token<T, M>]) -> T;
-pub type ReductMap<T, M> = Map<M, Vec<ReductFn<T, M>>>;
+/// Reduces the popped items of a production, taking them by value. As a closure, it can capture a
+/// context, like symbol tables or arenas, borrowed for `'a`.
+pub type ReductFn<'a, T, M> = Box<dyn FnMut(Vec<Token<T, M>>) -> T + 'a>;
+pub type ReductMap<'a, T, M> = Map<M, Vec<ReductFn<'a, T, M>>>;
+/// Plain reductor, that can be shared by many productions
+pub type BuildFn<T, M> = fn(Vec<Token<T, M>>) -> T;
+
+/// Boxes a function or closure as a `ReductFn`
+pub fn reductor<'a, T, M>(func: impl FnMut(Vec<Token<T, M>>) -> T + 'a) -> ReductFn<'a, T, M> {
+    Box::new(func)
+}
+
+/// Fallible reductor of `Dfa::parse_with`, that gets a user context, like a symbol table. On
+/// failure, it gives the items back along with the error, so the error node can be built from them.
+pub type SemanticFn<'a, C, T, M, E> =
+    Box<dyn FnMut(&mut C, Vec<Token<T, M>>) -> BaseResult<T, (E, Vec<Token<T, M>>)> + 'a>;
+pub type SemanticMap<'a, C, T, M, E> = Map<M, Vec<SemanticFn<'a, C, T, M, E>>>;
+
+/// Boxes a function or closure as a `SemanticFn`
+pub fn semantic<'a, C, T, M, E>(
+    func: impl FnMut(&mut C, Vec<Token<T, M>>) -> BaseResult<T, (E, Vec<Token<T, M>>)> + 'a,
+) -> SemanticFn<'a, C, T, M, E> {
+    Box::new(func)
+}
+
+pub type SpanFn<T, M> = fn(&Token<T, M>) -> Span;
+/// Reduces the items of any production, given its rule and index
+pub type TreeFn<T, M> = fn(&M, usize, Vec<Token<T, M>>) -> T;
 
-#[derive(Clone)]
-pub struct Dfa<T, M, I: Iterator<Item = Token<T, M>>>
+/// Error recovery strategy. When a unexpected token is found, the `Dfa` records the error and:
+/// 1. If `error` is defined, pops the stack until a state that shifts it (like yacc's `error`
+///    pseudo-terminal in productions), pushes an error node built from the popped items and
+///    discards the input until a acceptable token.
+/// 2. Otherwise, discards the input until a `sync` terminal and pops the stack until a state that
+///    accepts it or, if there isn't one, the next token after it (panic-mode).
+#[derive(Debug, Clone)]
+pub struct Recovery<T, M> {
+    /// Pseudo-terminal used by error productions
+    pub error: Option<M>,
+    /// Synchronization terminals for panic-mode
+    pub sync: Set<M>,
+    /// Builds the error node from the popped stack items
+    pub builder: BuildFn<T, M>,
+}
+
+impl<T, M> Recovery<T, M>
+where
+    M: Ord,
+{
+    #[must_use]
+    pub const fn new(builder: BuildFn<T, M>) -> Self {
+        Self {
+            error: None,
+            sync: Set::new(),
+            builder,
+        }
+    }
+
+    #[must_use]
+    pub fn with_error(self, error: M) -> Self {
+        Self {
+            error: Some(error),
+            ..self
+        }
+    }
+
+    #[must_use]
+    pub fn with_sync(self, sync: impl IntoIterator<Item = M>) -> Self {
+        Self {
+            sync: sync.into_iter().collect(),
+            ..self
+        }
+    }
+}
+
+/// State of a `Dfa` after `Dfa::feed`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Status {
+    /// Every fed token was shifted or, when recovering from an error, discarded
+    Consumed,
+    /// Amount of fed tokens kept as lookahead, waiting for more input
+    Pending(usize),
+}
+
+/// Action decided by an `Actions` table, returned by value. Reductions carry the code of their
+/// rule, to look up the goto after them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Step<M, C> {
+    Shift(usize),
+    Goto(usize),
+    /// Reduction of the `prod`-th production of `rule`, popping `size` items
+    Reduce {
+        rule: M,
+        code: C,
+        size: usize,
+        prod: usize,
+    },
+    Acc,
+    Conflict(Action<M>, Action<M>),
+}
+
+impl<M> From<&Action<M>> for Step<M, M>
+where
+    M: Clone,
+{
+    fn from(action: &Action<M>) -> Self {
+        match action {
+            Action::Shift(to) => Self::Shift(*to),
+            Action::Goto(to) => Self::Goto(*to),
+            Action::Reduce(rule, prod) => Self::Reduce {
+                rule: rule.clone(),
+                code: rule.clone(),
+                size: prod.0.len(),
+                prod: prod.1,
+            },
+            Action::Acc => Self::Acc,
+            Action::Conflict(a, b) => Self::Conflict((**a).clone(), (**b).clone()),
+        }
+    }
+}
+
+/// Phase of an error recovery waiting for more input pushed by `Dfa::feed`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Suspended {
+    /// Discarding the input until a token acceptable after the error node
+    Discard,
+    /// Discarding the input until a sync terminal
+    Sync,
+    /// Popping the stack until a state that accepts the token after a sync terminal
+    AfterSync,
+}
+
+/// Token with the code of its symbol in an `Actions` table, if it's there
+pub type Coded<T, M, C> = (Token<T, M>, Option<C>);
+
+/// Actions table read by a `Dfa` at runtime. Symbols are looked up by their `Actions::Code`, so
+/// the `Dfa` interns each token once, when it enters.
+pub trait Actions<M> {
+    /// Interned form of a symbol
+    type Code: Clone;
+
+    /// Amount of states
+    fn states(&self) -> usize;
+
+    /// Code of `symbol`, if it's in the table
+    fn code(&self, symbol: &M) -> Option<Self::Code>;
+
+    /// Action of `state` over `symbol`, if any
+    fn action(&self, state: usize, symbol: &M) -> Option<Step<M, Self::Code>> {
+        self.step(state, std::slice::from_ref(&self.code(symbol)?))
+    }
+
+    /// Action of `state` over the codes of the `window` of the next `Actions::lookahead` symbols.
+    /// Near the input end, the window is shorter and ends with the eof symbol.
+    fn step(&self, state: usize, window: &[Self::Code]) -> Option<Step<M, Self::Code>>;
+
+    /// Symbols with an action in `state`
+    fn expected(&self, state: usize) -> Vec<M>;
+
+    /// Amount of symbols peeked to decide each action
+    fn lookahead(&self) -> usize {
+        1
+    }
+}
+
+impl<M> Actions<M> for ActTable<M>
+where
+    M: Ord + Clone,
+{
+    type Code = M;
+
+    fn states(&self) -> usize {
+        self.len()
+    }
+
+    fn code(&self, symbol: &M) -> Option<M> {
+        Some(symbol.clone())
+    }
+
+    fn action(&self, state: usize, symbol: &M) -> Option<Step<M, M>> {
+        self.get(state)?.get(symbol).map(Step::from)
+    }
+
+    fn step(&self, state: usize, window: &[M]) -> Option<Step<M, M>> {
+        self.action(state, window.first()?)
+    }
+
+    fn expected(&self, state: usize) -> Vec<M> {
+        self.get(state)
+            .map_or_else(Vec::new, |row| row.keys().cloned().collect())
+    }
+}
+
+pub struct Dfa<'a, T, M, I: Iterator<Item = Token<T, M>>, A = ActTable<M>>
 where
     T: Clone,
     M: fmt::Debug + Clone,
+    A: Actions<M>,
 {
     pub buffer: Peekable<I>,
+    /// Tokens taken from `buffer`, or fed, and not consumed yet, with the code of their symbol
+    pub ahead: VecDeque<Coded<T, M, A::Code>>,
     pub states: Vec<usize>,
     pub items: Vec<Token<T, M>>,
-    pub table: ActTable<M>,
+    pub table: A,
     pub top: usize,
+    /// Initial state, of the parsed entry point (see `Tabler::entry`)
+    pub entry: usize,
+    /// Amount of consumed tokens. I.e, the index of the current token in the input
+    pub index: usize,
     pub finished: bool,
-    pub reductors: ReductMap<T, M>,
+    pub reductors: ReductMap<'a, T, M>,
     pub eof: M,
+    pub recovery: Option<Recovery<T, M>>,
+    /// Errors recovered during the parsing
+    pub errors: Vec<Error<M>>,
+    /// Gets the span of a input token, to be reported in errors
+    pub spanner: Option<SpanFn<T, M>>,
+    /// Generic reductor, used instead of `reductors` when defined
+    pub tree: Option<TreeFn<T, M>>,
+    /// Whether the input is pushed by `Dfa::feed`, so running out of tokens waits for more of them
+    /// instead of reaching the eof
+    pub feeding: bool,
+    /// Error recovery waiting for more input
+    pub suspended: Option<Suspended>,
+    /// Index of the last token recovered from. Failing again on it skips it, so a recovery can't
+    /// loop.
+    pub last_error: Option<usize>,
 }
 
 #[allow(clippy::mismatching_type_param_order)]
-impl<T, I: Iterator<Item = Token<T, T>>> Dfa<T, T, I> where
+impl<T, I: Iterator<Item = Token<T, T>>> Dfa<'_, T, T, I> where
     T: Clone + fmt::Debug + fmt::Display + Ord
 {
 }
 
-impl<T, M, I: Iterator<Item = Token<T, M>>> Dfa<T, M, I>
+impl<'a, T, M, I: Iterator<Item = Token<T, M>>> Dfa<'a, T, M, I>
+where
+    T: Clone + 'a,
+    M: fmt::Debug + Clone + Ord + 'a,
+{
+    #[must_use]
+    pub fn transparent(table: &Tabler<M>, func: BuildFn<T, M>) -> ReductMap<'a, T, M> {
+        table
+            .grammar
+            .rules()
+            .map(|r| {
+                let prods = r.prods().map(|_| reductor(func));
+                (r.name.clone(), prods.collect::<Vec<_>>())
+            })
+            .collect()
+    }
+}
+
+impl<'a, T, M, I: Iterator<Item = Token<T, M>>, A: Actions<M>> Dfa<'a, T, M, I, A>
 where
     T: Clone,
     M: fmt::Debug + Clone + Ord,
 {
     #[must_use]
-    pub fn new(buffer: I, table: ActTable<M>, reductors: ReductMap<T, M>, eof: M) -> Self {
+    pub fn new(buffer: I, table: A, reductors: ReductMap<'a, T, M>, eof: M) -> Self {
         Self {
             states: vec![0],
             items: Vec::new(),
             buffer: buffer.peekable(),
+            ahead: VecDeque::new(),
             top: 0,
+            entry: 0,
+            index: 0,
             table,
             reductors,
             finished: false,
             eof,
+            recovery: None,
+            errors: Vec::new(),
+            spanner: None,
+            tree: None,
+            feeding: false,
+            suspended: None,
+            last_error: None,
         }
     }
 
+    /// Starts the parsing from the `entry` state, instead of the state 0
     #[must_use]
-    pub fn transparent(table: &Tabler<M>, func: ReductFn<T, M>) -> ReductMap<T, M> {
-        table
-            .grammar
-            .rules()
-            .map(|r| {
-                let prods = r.prods().map(|_| func);
-                (r.name.clone(), prods.collect::<Vec<_>>())
-            })
-            .collect()
+    pub fn with_entry(self, entry: usize) -> Self {
+        Self {
+            states: vec![entry],
+            top: entry,
+            entry,
+            ..self
+        }
+    }
+
+    #[must_use]
+    pub fn with_tree(self, tree: TreeFn<T, M>) -> Self {
+        Self {
+            tree: Some(tree),
+            ..self
+        }
+    }
+
+    #[must_use]
+    pub fn with_recovery(self, recovery: Recovery<T, M>) -> Self {
+        Self {
+            recovery: Some(recovery),
+            ..self
+        }
     }
 
     /// # Errors
     /// When there is no more data in buffer, raises an `Error::UnexepectedEof`
     pub fn shift(&mut self, to: usize) -> BaseResult<(), Error<M>> {
-        let item = self.buffer.next().ok_or(Error::UnexpectedEof)?;
+        let item = self.skip().ok_or(Error::UnexpectedEof)?;
         self.items.push(item);
         self.top = to;
         self.states.push(self.top);
@@ -129,7 +485,7 @@ where
     /// When finished parse without consume entire buffer, raises an `Error::IncompleteExec`
     pub fn accept(&mut self) -> BaseResult<(), Error<M>> {
         self.finished = true;
-        if self.buffer.peek().is_none() {
+        if self.ahead.is_empty() && self.buffer.peek().is_none() {
             Ok(())
         } else {
             Err(Error::IncompleteExec)
@@ -143,20 +499,306 @@ where
     }
 
     /// # Errors
-    /// The same of `dfa::travel`
+    /// The same of `dfa::travel`. With a `Recovery`, unexpected tokens are recorded in
+    /// `Dfa::errors` and only raised when impossible to recover.
     pub fn trace(&mut self, mut f: impl FnMut(&mut Self)) -> BaseResult<(), Error<M>> {
         while !self.finished {
             f(self);
-            let symbol = self
-                .buffer
-                .peek()
-                .map_or_else(|| &self.eof, |t| &t.ty)
-                .clone();
-            self.travel(&symbol)?;
+            self.step()?;
         }
         Ok(())
     }
 
+    /// Runs the action of the current state over the lookahead, recovering from unexpected tokens
+    /// when possible
+    fn step(&mut self) -> BaseResult<(), Error<M>> {
+        self.step_with(&mut Self::reduct)
+    }
+
+    /// The same of `Dfa::step`, building the reduced items with `reduce`. A suspended recovery is
+    /// resumed first, raising its error if it fails.
+    fn step_with<E, R>(&mut self, reduce: &mut R) -> BaseResult<(), Error<M, E>>
+    where
+        R: FnMut(&mut Self, &M, usize, Vec<Token<T, M>>) -> BaseResult<T, E>,
+    {
+        if let Some(phase) = self.suspended.take() {
+            return self
+                .resume(phase)
+                .ok_or_else(|| self.errors.pop().map_or(Error::UnexpectedEof, Error::cast));
+        }
+        self.fill(self.table.lookahead());
+        match self.decide() {
+            Ok(step) => self.exec(step, reduce),
+            Err(e @ Error::UnexpectedToken { .. }) if self.recovery.is_some() => {
+                // failing again in the same token means that the last recovery wasn't
+                // enough, so it's skipped to avoid an infinite loop
+                let retry = self.last_error == Some(self.index);
+                if retry {
+                    self.skip().ok_or_else(|| e.clone().cast())?;
+                }
+                self.recover().ok_or_else(|| e.clone().cast())?;
+                if !retry {
+                    self.errors.push(e);
+                }
+                self.last_error = Some(self.index);
+                Ok(())
+            }
+            Err(e) => Err(e.cast()),
+        }
+    }
+
+    /// Pushes a token to the input, running the actions over it until it's consumed. So, the
+    /// `Dfa` can be fed chunk by chunk, while its `buffer` is empty, and suspended between them.
+    /// Tables with more than one lookahead symbol keep the last tokens until feeding the next ones,
+    /// and error recoveries needing more tokens are suspended until the next ones, or
+    /// `Dfa::finish`, returning `Status::Pending`.
+    /// # Errors
+    /// The same of `Dfa::trace`. After accepting the input, raises an `Error::IncompleteExec`.
+    pub fn feed(&mut self, token: Token<T, M>) -> BaseResult<Status, Error<M>> {
+        if self.finished {
+            return Err(Error::IncompleteExec);
+        }
+        self.feeding = true;
+        let code = self.table.code(&token.ty);
+        self.ahead.push_back((token, code));
+        while self.ahead.len() >= self.table.lookahead()
+            || self.suspended.is_some() && !self.ahead.is_empty()
+        {
+            self.step()?;
+        }
+        Ok(if self.ahead.is_empty() && self.suspended.is_none() {
+            Status::Consumed
+        } else {
+            Status::Pending(self.ahead.len())
+        })
+    }
+
+    /// Ends the input pushed by `Dfa::feed` and runs the actions until accepting it. The `Dfa`
+    /// must be reset before parsing another input.
+    /// # Errors
+    /// The same of `Dfa::trace`
+    pub fn finish(&mut self) -> BaseResult<T, Error<M>> {
+        self.feeding = false;
+        while !self.finished {
+            self.step()?;
+        }
+        let res = self.items.pop().ok_or(Error::MissingPreviousState)?;
+        Ok(res.item)
+    }
+
+    /// Parses `src` char by char, for scannerless grammars. Each char is fed as the first
+    /// character class terminal of `grammar` (see `Grammar::with_class`) containing it and
+    /// acceptable in the current state, or just containing it when none is acceptable. The `Dfa`
+    /// must be reset before parsing another input.
+    /// # Errors
+    /// The same of `Dfa::feed` and `Dfa::finish`. A char outside every class raises an
+    /// `Error::UnknownChar`.
+    pub fn parse_chars(
+        &mut self,
+        src: &str,
+        grammar: &Grammar<M>,
+        mut leaf: impl FnMut(Token<Meta<char>, M>) -> Token<T, M>,
+    ) -> BaseResult<T, Error<M>> {
+        let mut pending = false;
+        for (index, (start, c)) in src.char_indices().enumerate() {
+            let span = Span::new(start, start + c.len_utf8());
+            let acceptable = if pending {
+                Vec::new()
+            } else {
+                self.acceptable()
+            };
+            let ty = grammar
+                .classify(c, |term| acceptable.contains(term))
+                .or_else(|| grammar.classify(c, |_| true))
+                .ok_or(Error::UnknownChar {
+                    found: c,
+                    index,
+                    span,
+                })?;
+            let token = Token::new(Meta::new(c, span), ty.clone());
+            pending = matches!(self.feed(leaf(token))?, Status::Pending(_));
+        }
+        self.finish()
+    }
+
+    /// Discards the current token
+    pub fn skip(&mut self) -> Option<Token<T, M>> {
+        let token =
+            (self.ahead.pop_front().map(|(token, _)| token)).or_else(|| self.buffer.next())?;
+        self.index += 1;
+        Some(token)
+    }
+
+    /// Takes tokens from `buffer` until there are `k` in `ahead`, interning their symbols
+    fn fill(&mut self, k: usize) {
+        while self.ahead.len() < k {
+            let Some(token) = self.buffer.next() else {
+                break;
+            };
+            let code = self.table.code(&token.ty);
+            self.ahead.push_back((token, code));
+        }
+    }
+
+    /// Returns the current token, without consuming it
+    pub fn current(&mut self) -> Option<&Token<T, M>> {
+        self.fill(1);
+        self.ahead.front().map(|(token, _)| token)
+    }
+
+    /// Returns the current lookahead symbol
+    #[must_use]
+    pub fn symbol(&mut self) -> M {
+        match self.current() {
+            Some(token) => token.ty.clone(),
+            None => self.eof.clone(),
+        }
+    }
+
+    /// Returns the next `Actions::lookahead` symbols, ending with the eof symbol when the input
+    /// is shorter.
+    #[must_use]
+    pub fn window(&mut self) -> Vec<M> {
+        let k = self.table.lookahead();
+        self.fill(k);
+        let window = self.ahead.iter().take(k).map(|(token, _)| token.ty.clone());
+        let mut window: Vec<_> = window.collect();
+        if window.len() < k {
+            window.push(self.eof.clone());
+        }
+        window
+    }
+
+    /// Checks if the current state has an action for `symbol`
+    #[must_use]
+    pub fn accepts(&self, state: usize, symbol: &M) -> bool {
+        self.table.action(state, symbol).is_some()
+    }
+
+    /// Terminals with an action in the current state. So, a context-aware lexer can try only the
+    /// terminals valid right now.
+    #[must_use]
+    pub fn acceptable(&self) -> Vec<M> {
+        self.table
+            .expected(self.top)
+            .into_iter()
+            .filter(|sym| !matches!(self.table.action(self.top, sym), Some(Step::Goto(_))))
+            .collect()
+    }
+
+    /// Recovers the `Dfa` from a unexpected token, following the `Recovery` strategy. Returns
+    /// `None` if impossible to recover. Running out of fed tokens, the recovery is kept in
+    /// `Dfa::suspended` until more input.
+    pub fn recover(&mut self) -> Option<()> {
+        let recovery = self.recovery.clone()?;
+        if let Some(error) = &recovery.error {
+            if self.recover_error(error, recovery.builder).is_some() {
+                return Some(());
+            }
+        }
+        self.recover_sync(&recovery.sync, false)
+    }
+
+    /// Goes on with a recovery suspended at `phase`
+    fn resume(&mut self, phase: Suspended) -> Option<()> {
+        match phase {
+            Suspended::Discard => self.discard(),
+            Suspended::Sync | Suspended::AfterSync => {
+                let sync = self.recovery.as_ref()?.sync.clone();
+                self.recover_sync(&sync, phase == Suspended::AfterSync)
+            }
+        }?;
+        if self.suspended.is_none() {
+            self.last_error = Some(self.index);
+        }
+        Some(())
+    }
+
+    /// Whether the fed tokens ran out, so the input must wait for more
+    fn starved(&mut self) -> bool {
+        self.feeding && self.current().is_none()
+    }
+
+    fn suspend(&mut self, phase: Suspended) -> Option<()> {
+        self.suspended = Some(phase);
+        Some(())
+    }
+
+    /// Pops the stack until a state that shifts `error` and shifts it as a node built from the
+    /// popped items. So, discards the input until a acceptable token.
+    fn recover_error(&mut self, error: &M, builder: BuildFn<T, M>) -> Option<()> {
+        let (base, to) = self.states.iter().enumerate().rev().find_map(|(i, &s)| {
+            match self.table.action(s, error) {
+                Some(Step::Shift(to)) => Some((i, to)),
+                _ => None,
+            }
+        })?;
+        self.states.truncate(base + 1);
+        let popped = self.items.split_off(base.min(self.items.len()));
+        self.items.push(Token::new(builder(popped), error.clone()));
+        self.top = to;
+        self.states.push(to);
+        self.discard()
+    }
+
+    /// Discards the input until a token acceptable by the current state
+    fn discard(&mut self) -> Option<()> {
+        loop {
+            if self.starved() {
+                return self.suspend(Suspended::Discard);
+            }
+            let symbol = self.symbol();
+            if self.accepts(self.top, &symbol) {
+                return Some(());
+            }
+            self.skip()?;
+        }
+    }
+
+    /// Discards the input until a `sync` terminal, so pops the stack until a state that accepts it
+    /// or, `after` it, the next token.
+    fn recover_sync(&mut self, sync: &Set<M>, mut after: bool) -> Option<()> {
+        loop {
+            if self.starved() {
+                let phase = if after {
+                    Suspended::AfterSync
+                } else {
+                    Suspended::Sync
+                };
+                return self.suspend(phase);
+            }
+            let symbol = self.symbol();
+            if after {
+                if self.pop_until(&symbol).is_some() {
+                    return Some(());
+                }
+                after = false;
+            } else if symbol == self.eof || sync.contains(&symbol) {
+                if self.pop_until(&symbol).is_some() {
+                    return Some(());
+                }
+                self.skip()?;
+                after = true;
+            } else {
+                self.skip();
+            }
+        }
+    }
+
+    /// Pops the stack until a state that accepts `symbol`
+    fn pop_until(&mut self, symbol: &M) -> Option<()> {
+        let depth = self
+            .states
+            .iter()
+            .rev()
+            .position(|&s| self.accepts(s, symbol))?;
+        let base = self.states.len() - depth - 1;
+        self.states.truncate(base + 1);
+        self.items.truncate(base);
+        self.top = self.states[base];
+        Some(())
+    }
+
     /// # Errors
     /// None
     pub fn goto(&mut self, to: usize) -> BaseResult<(), Error<M>> {
@@ -170,52 +812,176 @@ where
     /// If there isn't an action in current state for `symbol`, raises an `Error::UnexpectedToken`
     /// Returns the action result
     pub fn travel(&mut self, symbol: &M) -> BaseResult<(), Error<M>> {
-        let state = self.table.get(self.top).ok_or(Error::StateNotSpecified)?;
-        let action = state.get(symbol).ok_or_else(|| {
-            let expecteds = state.keys().cloned().collect();
-            Error::UnexpectedToken(symbol.clone(), expecteds)
-        })?;
-        match action {
-            Action::Shift(to) => self.shift(*to),
-            Action::Goto(to) => self.goto(*to),
-            Action::Reduce(name, prod) => self.reduce(&name.clone(), &prod.clone()),
-            Action::Acc => self.accept(),
-            Action::Conflict(a, b) => Err(Error::Conflict(*a.clone(), *b.clone())),
+        self.travel_seq(std::slice::from_ref(symbol))
+    }
+
+    /// The same of `Dfa::travel`, deciding the action by a lookahead `window`. So, the unexpected
+    /// token is its first symbol.
+    /// # Errors
+    /// The same of `Dfa::travel`
+    pub fn travel_seq(&mut self, window: &[M]) -> BaseResult<(), Error<M>> {
+        if self.top >= self.table.states() {
+            return Err(Error::StateNotSpecified);
         }
+        let codes: Option<Vec<_>> = window.iter().map(|sym| self.table.code(sym)).collect();
+        let step = codes.and_then(|codes| self.table.step(self.top, &codes));
+        let step = step.ok_or_else(|| self.unexpected(window.first().unwrap_or(&self.eof)))?;
+        self.exec(step, &mut Self::reduct)
     }
 
+    /// Action of the current state over the codes of the lookahead tokens, taken by `Dfa::fill`
+    fn decide(&self) -> BaseResult<Step<M, A::Code>, Error<M>> {
+        if self.top >= self.table.states() {
+            return Err(Error::StateNotSpecified);
+        }
+        let k = self.table.lookahead();
+        let eof = (self.ahead.len() < k).then(|| self.table.code(&self.eof));
+        let mut codes = (self.ahead.iter().take(k))
+            .map(|(_, code)| code.as_ref())
+            .chain(eof.as_ref().map(Option::as_ref));
+        let step = if k == 1 {
+            let code = codes.next().flatten();
+            code.and_then(|code| self.table.step(self.top, std::slice::from_ref(code)))
+        } else {
+            let window: Option<Vec<_>> = codes.map(|code| code.cloned()).collect();
+            window.and_then(|window| self.table.step(self.top, &window))
+        };
+        step.ok_or_else(|| {
+            let found = self.ahead.front().map_or(&self.eof, |(token, _)| &token.ty);
+            self.unexpected(found)
+        })
+    }
+
+    /// Error of finding `found` in the current state, spanning the current token
+    fn unexpected(&self, found: &M) -> Error<M> {
+        let spanner = self.spanner;
+        let current = self.ahead.front().map(|(token, _)| token);
+        Error::UnexpectedToken {
+            found: found.clone(),
+            expected: self.table.expected(self.top),
+            index: self.index,
+            span: spanner.and_then(|spanner| current.map(spanner)),
+        }
+    }
+
+    fn exec<E, R>(&mut self, step: Step<M, A::Code>, reduce: &mut R) -> BaseResult<(), Error<M, E>>
+    where
+        R: FnMut(&mut Self, &M, usize, Vec<Token<T, M>>) -> BaseResult<T, E>,
+    {
+        match step {
+            Step::Shift(to) => self.shift(to).map_err(Error::cast),
+            Step::Goto(to) => self.goto(to).map_err(Error::cast),
+            Step::Reduce {
+                rule,
+                code,
+                size,
+                prod,
+            } => self.reduce_with(rule, Some(code), size, prod, reduce),
+            Step::Acc => self.accept().map_err(Error::cast),
+            Step::Conflict(a, b) => Err(Error::Conflict(a, b)),
+        }
+    }
+
+    /// Reduces the last `prod.0.len()` items into a `name` item. Empty productions push a new
+    /// item without popping any state.
     /// # Errors
-    /// If stack doesn't contains the necessary terms amount, raises an `Error::UnexepectedEof`
+    /// If stack doesn't contains the necessary terms amount, raises an `Error::MissingPreviousState`
     /// If there isn't a previous state, raises an `Error::MissingPreviousState`
     pub fn reduce(&mut self, name: &M, prod: &Production<M>) -> BaseResult<(), Error<M>> {
-        let len = self.items.len();
-        let items = &self.items[len - prod.0.len()..];
+        self.reduce_by(name, prod.0.len(), prod.1)
+    }
+
+    /// Reduces the last `size` items into a `name` item, with the reductor of the `prod`-th
+    /// production of `name`.
+    /// # Errors
+    /// The same of `Dfa::reduce`
+    pub fn reduce_by(&mut self, name: &M, size: usize, prod: usize) -> BaseResult<(), Error<M>> {
+        let code = self.table.code(name);
+        self.reduce_with(name.clone(), code, size, prod, &mut Self::reduct)
+    }
+
+    /// The same of `Dfa::reduce_by`, building the item with `reduce`. So, its failure is raised as
+    /// an `Error::Semantic`.
+    fn reduce_with<E, R>(
+        &mut self,
+        name: M,
+        code: Option<A::Code>,
+        size: usize,
+        prod: usize,
+        reduce: &mut R,
+    ) -> BaseResult<(), Error<M, E>>
+    where
+        R: FnMut(&mut Self, &M, usize, Vec<Token<T, M>>) -> BaseResult<T, E>,
+    {
+        let items_start = self
+            .items
+            .len()
+            .checked_sub(size)
+            .ok_or(Error::MissingPreviousState)?;
+        let states_start = self
+            .states
+            .len()
+            .checked_sub(size)
+            .ok_or(Error::MissingPreviousState)?;
+        let items = self.items.split_off(items_start);
+        let span = self.items_span(&items);
+        let new_item = reduce(self, &name, prod, items).map_err(|error| Error::Semantic {
+            rule: name.clone(),
+            prod,
+            span,
+            error,
+        })?;
+
+        self.states.truncate(states_start);
+        self.top = *self.states.last().ok_or(Error::MissingPreviousState)?;
+        let step = code.and_then(|code| self.table.step(self.top, std::slice::from_ref(&code)));
+        let step = step.ok_or_else(|| self.unexpected(&name).cast())?;
+        self.items.push(Token::new(new_item, name));
+        self.exec(step, reduce)
+    }
+
+    /// Builds the item of the `prod`-th production of `name` with the `tree` function or the
+    /// `reductors`
+    fn reduct(
+        &mut self,
+        name: &M,
+        prod: usize,
+        items: Vec<Token<T, M>>,
+    ) -> BaseResult<T, Infallible> {
+        if let Some(tree) = self.tree {
+            return Ok(tree(name, prod, items));
+        }
         // TODO: Create a custom Error
         debug_assert!(
-            self.reductors.get(name).is_some(),
+            self.reductors.contains_key(name),
             "missing reductor table for {name:?}"
         );
         debug_assert!(
-            self.reductors[name].get(prod.1).is_some(),
-            "missing production {} reductor for {name:?}",
-            prod.1
+            self.reductors[name].get(prod).is_some(),
+            "missing production {prod} reductor for {name:?}",
         );
-        let new_item = Token::new(self.reductors[name][prod.1](items), name.clone());
-        self.items.truncate(len - prod.0.len());
-        self.items.push(new_item);
-
-        let len = self.states.len();
-        self.top = self.states[len - prod.0.len() - 1];
+        let reductor = &mut self.reductors.get_mut(name).unwrap()[prod];
+        Ok(reductor(items))
+    }
 
-        self.states.truncate(len - prod.0.len());
-        self.travel(name)
+    /// Span from the first to the last of `items`, when there is a `spanner`
+    fn items_span(&self, items: &[Token<T, M>]) -> Option<Span> {
+        let spanner = self.spanner?;
+        let (first, last) = (items.first()?, items.last()?);
+        Some(Span::new(spanner(first).start, spanner(last).end))
     }
 
     pub fn reset(&mut self) {
         self.finished = false;
-        self.states.clear();
+        self.states = vec![self.entry];
+        self.ahead.clear();
         self.items.clear();
-        self.top = 0;
+        self.errors.clear();
+        self.top = self.entry;
+        self.index = 0;
+        self.feeding = false;
+        self.suspended = None;
+        self.last_error = None;
     }
 
     /// # Errors
@@ -229,6 +995,78 @@ where
         Ok(res.item)
     }
 
+    /// Parses the entire input, recovering from the errors when possible. Returns the parsing
+    /// result, if recovered, and all the errors found.
+    pub fn parse_all(&mut self, input: I) -> (Option<T>, Vec<Error<M>>) {
+        self.reset();
+        self.buffer = input.peekable();
+        let res = self.start();
+        let mut errors = std::mem::take(&mut self.errors);
+        let item = match res {
+            Ok(()) => self.items.pop().map(|t| t.item),
+            Err(e) => {
+                errors.push(e);
+                None
+            }
+        };
+        self.reset();
+        (item, errors)
+    }
+
+    /// The same of `Dfa::parse_all`, but building the items with `actions`, that get the `ctx` and
+    /// can fail. With a `Recovery`, the node of a failed action is built by its `builder`, from the
+    /// items given back by the action, and the error is recorded after the syntax ones, otherwise
+    /// the parsing is aborted.
+    pub fn parse_with<C, E>(
+        &mut self,
+        input: I,
+        ctx: &mut C,
+        actions: &mut SemanticMap<'_, C, T, M, E>,
+    ) -> (Option<T>, Vec<Error<M, E>>) {
+        self.reset();
+        self.buffer = input.peekable();
+        let builder = self.recovery.as_ref().map(|r| r.builder);
+        let mut semantic_errors = Vec::new();
+        let mut reduce = |dfa: &mut Self, name: &M, prod: usize, items: Vec<Token<T, M>>| {
+            debug_assert!(
+                actions.get(name).and_then(|a| a.get(prod)).is_some(),
+                "missing production {prod} action for {name:?}",
+            );
+            let action = &mut actions.get_mut(name).unwrap()[prod];
+            let span = dfa.items_span(&items);
+            action(ctx, items).or_else(|(error, items)| {
+                let Some(builder) = builder else {
+                    return Err(error);
+                };
+                semantic_errors.push(Error::Semantic {
+                    rule: name.clone(),
+                    prod,
+                    span,
+                    error,
+                });
+                Ok(builder(items))
+            })
+        };
+        let mut res = Ok(());
+        while !self.finished && res.is_ok() {
+            res = self.step_with(&mut reduce);
+        }
+        let mut errors: Vec<_> = std::mem::take(&mut self.errors)
+            .into_iter()
+            .map(Error::cast)
+            .collect();
+        errors.extend(semantic_errors);
+        let item = match res {
+            Ok(()) => self.items.pop().map(|t| t.item),
+            Err(e) => {
+                errors.push(e);
+                None
+            }
+        };
+        self.reset();
+        (item, errors)
+    }
+
     #[must_use]
     pub fn stack_fmt(&self) -> String {
         let mut fmts = Vec::new();
@@ -268,3 +1106,434 @@ where
         fmts.join(" ")
     }
 }
+
+impl<T, M, I: Iterator<Item = Token<Meta<T>, M>>, A: Actions<M>> Dfa<'_, Meta<T>, M, I, A>
+where
+    T: Clone,
+    M: fmt::Debug + Clone + Ord,
+{
+    /// Reports the `Meta` span of the input tokens in the errors
+    #[must_use]
+    pub fn spanned(self) -> Self {
+        Self {
+            spanner: Some(|tok| tok.item.span),
+            ..self
+        }
+    }
+}
+
+impl<L, M, I: Iterator<Item = Token<Cst<L, M>, M>>, A: Actions<M>> Dfa<'_, Cst<L, M>, M, I, A>
+where
+    L: Clone,
+    M: fmt::Debug + Clone + Ord,
+{
+    /// Reports the span of the input leaves in the errors. Nodes of empty productions, without
+    /// span, take an empty one at the start.
+    #[must_use]
+    pub fn spanned(self) -> Self {
+        Self {
+            spanner: Some(|tok| tok.item.span().unwrap_or(Span::new(0, 0))),
+            ..self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use crate::{
+        grammars_tests, reductor, semantic, CharClass, ClrK, Cst, Dfa, Error, Lalr, Map, Meta,
+        Parser, Recovery, ReductMap, SemanticMap, Span, Status, Suspended, Token,
+    };
+
+    type Tok = Token<Vec<&'static str>, &'static str>;
+
+    fn reductors() -> ReductMap<'static, Vec<&'static str>, &'static str> {
+        fn concat(toks: Vec<Tok>) -> Vec<&'static str> {
+            toks.into_iter().flat_map(|t| t.item).collect()
+        }
+        fn stmt(_: Vec<Tok>) -> Vec<&'static str> {
+            vec!["stmt"]
+        }
+        fn first(toks: Vec<Tok>) -> Vec<&'static str> {
+            toks.into_iter().next().unwrap().item
+        }
+        ReductMap::from([
+            ("S", vec![reductor(concat)]),
+            ("Stmts", vec![reductor(concat), reductor(concat)]),
+            ("Stmt", vec![reductor(stmt), reductor(first)]),
+            ("Value", vec![reductor(first), reductor(first)]),
+        ])
+    }
+
+    fn error(_: Vec<Tok>) -> Vec<&'static str> {
+        vec!["error"]
+    }
+
+    fn tokens(input: &[&'static str]) -> std::vec::IntoIter<Tok> {
+        input
+            .iter()
+            .map(|&t| Token::new(Vec::new(), t))
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    #[test]
+    fn error_productions() {
+        let lalr = Lalr::new(grammars_tests::statements());
+        let recovery = Recovery::new(error).with_error("error");
+        let expected: &[(&[_], usize)] = &[
+            (&["error"], 1),
+            (&["stmt", "error", "stmt"], 1),
+            // `error ;` isn't reduced before the second error, so both errors are merged
+            (&["error", "stmt"], 2),
+        ];
+        for (input, (nodes, errors)) in grammars_tests::BAD_STATEMENTS_INPUTS.iter().zip(expected) {
+            let mut dfa = lalr
+                .dfa(&"S", tokens(&[]), reductors())
+                .with_recovery(recovery.clone());
+            let (res, errs) = dfa.parse_all(tokens(input));
+            assert_eq!(res.as_deref(), Some(*nodes));
+            assert_eq!(errs.len(), *errors, "{errs:?}");
+        }
+
+        for input in grammars_tests::STATEMENTS_INPUTS {
+            let mut dfa = lalr
+                .dfa(&"S", tokens(&[]), reductors())
+                .with_recovery(recovery.clone());
+            let (res, errs) = dfa.parse_all(tokens(input));
+            assert!(errs.is_empty());
+            assert!(res.unwrap().iter().all(|&n| n == "stmt"));
+        }
+    }
+
+    #[test]
+    fn panic_mode() {
+        let lalr = Lalr::new(grammars_tests::statements());
+        let recovery = Recovery::new(error).with_sync([";"]);
+        let expected: &[(&[_], usize)] = &[(&["stmt", "stmt"], 1), (&["stmt", "stmt"], 2)];
+        for (input, (nodes, errors)) in grammars_tests::BAD_STATEMENTS_INPUTS[1..]
+            .iter()
+            .zip(expected)
+        {
+            let mut dfa = lalr
+                .dfa(&"S", tokens(&[]), reductors())
+                .with_recovery(recovery.clone());
+            let (res, errs) = dfa.parse_all(tokens(input));
+            assert_eq!(res.as_deref(), Some(*nodes));
+            assert_eq!(errs.len(), *errors, "{errs:?}");
+        }
+    }
+
+    #[test]
+    fn unrecoverable() {
+        let lalr = Lalr::new(grammars_tests::statements());
+        let mut dfa = lalr
+            .dfa(&"S", tokens(&[]), reductors())
+            .with_recovery(Recovery::new(error).with_sync([";"]));
+        let (res, errs) = dfa.parse_all(tokens(&["id", "=", "n"]));
+        assert_eq!(res, None);
+        assert_eq!(errs.len(), 1);
+    }
+
+    #[test]
+    fn spans() {
+        fn join(toks: Vec<Token<Meta<()>, &'static str>>) -> Meta<()> {
+            Meta::new(
+                (),
+                Span::new(toks[0].item.span.start, toks[toks.len() - 1].item.span.end),
+            )
+        }
+        let src = "id = n ;\nid = = ;";
+        let input = src.split_whitespace().map(|t| {
+            let start = t.as_ptr() as usize - src.as_ptr() as usize;
+            Token::new(Meta::new((), Span::new(start, start + t.len())), t)
+        });
+        let lalr = Lalr::new(grammars_tests::statements());
+        let reductors = || Dfa::<_, _, std::vec::IntoIter<_>>::transparent(lalr.tables(), join);
+
+        let err = lalr
+            .dfa(&"S", input.clone(), reductors())
+            .parse(input.clone());
+        let Err(err @ Error::UnexpectedToken { index: 6, .. }) = err else {
+            panic!("{err:?}")
+        };
+        assert_eq!(err.span(), None);
+
+        let mut dfa = lalr.dfa(&"S", input.clone(), reductors()).spanned();
+        let err = dfa.parse(input).unwrap_err();
+        assert_eq!(err.span(), Some(Span::new(14, 15)));
+        assert_eq!(
+            err.diagnostic(src),
+            "error: unexpected token \"=\" at 6. expected [\"Value\", \"id\", \"n\"]\n \
+             --> 2:6\n  |\n2 | id = = ;\n  |      ^"
+        );
+    }
+
+    #[test]
+    fn context() {
+        type Tok = Token<String, &'static str>;
+        // symbol table borrowed by the reductors
+        let vars = RefCell::new(Map::new());
+        let (assign, lookup) = (&vars, &vars);
+        let first = |toks: Vec<Tok>| toks.into_iter().next().unwrap().item;
+        let last = |toks: Vec<Tok>| toks.into_iter().last().unwrap().item;
+        let reductors = ReductMap::from([
+            ("S", vec![reductor(first)]),
+            ("Stmts", vec![reductor(last), reductor(first)]),
+            (
+                "Stmt",
+                vec![
+                    reductor(move |toks: Vec<Tok>| {
+                        let mut toks = toks.into_iter();
+                        let (name, value) = (toks.next().unwrap(), toks.nth(1).unwrap());
+                        assign.borrow_mut().insert(name.item, value.item.clone());
+                        value.item
+                    }),
+                    reductor(first),
+                ],
+            ),
+            (
+                "Value",
+                vec![
+                    reductor(first),
+                    reductor(move |toks: Vec<Tok>| lookup.borrow()[&toks[0].item].clone()),
+                ],
+            ),
+        ]);
+        let input = "a = 1 ; b = a ; c = b ;".split(' ').map(|word| {
+            let ty = match word {
+                "=" | ";" => word,
+                "1" => "n",
+                _ => "id",
+            };
+            Token::new(word.to_string(), ty)
+        });
+        let lalr = Lalr::new(grammars_tests::statements());
+        assert_eq!(lalr.parse(input, reductors), Ok("1".to_string()));
+        assert_eq!(vars.borrow().len(), 3);
+    }
+
+    #[test]
+    fn semantic_actions() {
+        type Tok = Token<Meta<String>, &'static str>;
+        type Vars = Map<String, String>;
+        type Res = Result<Meta<String>, (String, Vec<Tok>)>;
+        fn first(_: &mut Vars, toks: Vec<Tok>) -> Res {
+            Ok(toks.into_iter().next().unwrap().item)
+        }
+        fn last(_: &mut Vars, toks: Vec<Tok>) -> Res {
+            Ok(toks.into_iter().last().unwrap().item)
+        }
+        fn assign(vars: &mut Vars, toks: Vec<Tok>) -> Res {
+            let mut toks = toks.into_iter();
+            let (name, value) = (toks.next().unwrap().item, toks.nth(1).unwrap().item);
+            vars.insert(name.item, value.item.clone());
+            Ok(value)
+        }
+        fn lookup(vars: &mut Vars, toks: Vec<Tok>) -> Res {
+            let Meta { item, span } = &toks[0].item;
+            match vars.get(item) {
+                Some(value) => Ok(Meta::new(value.clone(), *span)),
+                None => Err((format!("undefined {item}"), toks)),
+            }
+        }
+        fn error(toks: Vec<Tok>) -> Meta<String> {
+            let span = Span::new(toks[0].item.span.start, toks[toks.len() - 1].item.span.end);
+            Meta::new("error".to_string(), span)
+        }
+        let actions = || {
+            SemanticMap::from([
+                ("S", vec![semantic(first)]),
+                ("Stmts", vec![semantic(last), semantic(first)]),
+                ("Stmt", vec![semantic(assign), semantic(first)]),
+                ("Value", vec![semantic(first), semantic(lookup)]),
+            ])
+        };
+        let src = "a = 1 ; b = c ; d = a ;";
+        let input = src.split(' ').scan(0, |start, word| {
+            let ty = match word {
+                "=" | ";" => word,
+                "1" => "n",
+                _ => "id",
+            };
+            let span = Span::new(*start, *start + word.len());
+            *start = span.end + 1;
+            Some(Token::new(Meta::new(word.to_string(), span), ty))
+        });
+        let lalr = Lalr::new(grammars_tests::statements());
+        let mut dfa = lalr.dfa(&"S", input.clone(), ReductMap::new()).spanned();
+
+        let mut vars = Vars::new();
+        let (res, errs) = dfa.parse_with(input.clone(), &mut vars, &mut actions());
+        assert_eq!(res, None);
+        assert_eq!(
+            errs,
+            [Error::Semantic {
+                rule: "Value",
+                prod: 1,
+                span: Some(Span::new(12, 13)),
+                error: "undefined c".to_string(),
+            }]
+        );
+        assert_eq!(
+            errs[0].diagnostic(src),
+            "error: undefined c reducing the production 1 of \"Value\"\n --> 1:13\n  |\n1 | \
+             a = 1 ; b = c ; d = a ;\n  |             ^"
+        );
+
+        let mut dfa = dfa.with_recovery(Recovery::new(error));
+        let mut vars = Vars::new();
+        let (res, errs) = dfa.parse_with(input, &mut vars, &mut actions());
+        assert_eq!(res.map(|m| m.item).as_deref(), Some("1"));
+        assert_eq!(errs.len(), 1);
+        assert_eq!(vars["b"], "error");
+    }
+
+    #[test]
+    fn push() {
+        let lalr = Lalr::new(grammars_tests::statements());
+        for input in grammars_tests::STATEMENTS_INPUTS {
+            let expected = lalr
+                .dfa(&"S", tokens(&[]), reductors())
+                .parse(tokens(input));
+            let mut dfa = lalr.push_dfa(reductors());
+            let (first, rest) = input.split_at(input.len() / 2);
+            for token in tokens(first) {
+                assert_eq!(dfa.feed(token), Ok(Status::Consumed));
+            }
+            // suspended and resumed with the rest of the input
+            let mut resumed = dfa;
+            for token in tokens(rest) {
+                assert_eq!(resumed.feed(token), Ok(Status::Consumed));
+            }
+            assert_eq!(resumed.finish(), expected);
+            assert!(resumed.feed(Token::new(Vec::new(), "id")).is_err());
+        }
+
+        let mut dfa = lalr.push_dfa(reductors());
+        let mut errs = tokens(grammars_tests::BAD_STATEMENTS_INPUTS[0]).map(|t| dfa.feed(t));
+        assert!(matches!(
+            errs.find(Result::is_err),
+            Some(Err(Error::UnexpectedToken { index: 2, .. }))
+        ));
+    }
+
+    #[test]
+    fn push_recovery() {
+        let lalr = Lalr::new(grammars_tests::statements());
+        let recoveries = [
+            Recovery::new(error).with_error("error"),
+            Recovery::new(error).with_sync([";"]),
+        ];
+        for recovery in recoveries {
+            for input in grammars_tests::BAD_STATEMENTS_INPUTS {
+                let (expected, mut errors) = lalr
+                    .dfa(&"S", tokens(&[]), reductors())
+                    .with_recovery(recovery.clone())
+                    .parse_all(tokens(input));
+                let mut dfa = lalr.push_dfa(reductors()).with_recovery(recovery.clone());
+                // the recovery waits for the tokens after the erroneous one
+                for token in tokens(input) {
+                    assert!(dfa.feed(token).is_ok(), "{input:?}");
+                }
+                let res = dfa.finish();
+                match &expected {
+                    Some(nodes) => assert_eq!(res.as_ref(), Ok(nodes), "{input:?}"),
+                    None => assert_eq!(res.err(), errors.pop(), "{input:?}"),
+                }
+                assert_eq!(dfa.errors, errors, "{input:?}");
+            }
+        }
+
+        let mut dfa = lalr
+            .push_dfa(reductors())
+            .with_recovery(Recovery::new(error).with_sync([";"]));
+        for token in tokens(&["id", "=", "n", ";", "id"]) {
+            assert_eq!(dfa.feed(token), Ok(Status::Consumed));
+        }
+        let mut rest = tokens(&[";", "id", "=", "n", ";"]);
+        assert_eq!(dfa.feed(rest.next().unwrap()), Ok(Status::Pending(0)));
+        assert_eq!(dfa.suspended, Some(Suspended::AfterSync));
+        for token in rest {
+            assert_eq!(dfa.feed(token), Ok(Status::Consumed));
+        }
+        assert_eq!(dfa.finish().as_deref(), Ok(&["stmt", "stmt"][..]));
+        assert_eq!(dfa.errors.len(), 1);
+    }
+
+    #[test]
+    fn push_lookahead() {
+        fn empty(_: Vec<Token<(), &'static str>>) {}
+        let clr = ClrK::new(grammars_tests::lr2(), 2);
+        let maps = Dfa::<_, _, std::iter::Empty<_>>::transparent(&clr.table, empty);
+        let mut dfa = clr.dfa(std::iter::empty(), maps);
+        // each token is kept until the next one decides its action
+        for token in ["d", "a", "b"] {
+            assert_eq!(dfa.feed(Token::empty(token)), Ok(Status::Pending(1)));
+        }
+        assert_eq!(dfa.finish(), Ok(()));
+    }
+
+    #[test]
+    fn scannerless() {
+        let grammar = grammars_tests::scannerless();
+        let mut unrestricted = grammar.clone();
+        unrestricted.restrictions.clear();
+        let unrestricted = Lalr::new(unrestricted);
+        assert_ne!(unrestricted.tables().conflicts().count(), 0);
+
+        let lalr = Lalr::new(grammar.clone());
+        assert_eq!(lalr.tables().conflicts().count(), 0);
+        assert_eq!(lalr.tables().grammar.terminals.len(), 4);
+
+        let src = "lorem ipsum  42";
+        let mut dfa = lalr.push_dfa(ReductMap::new()).with_tree(Cst::node);
+        let cst = dfa.parse_chars(src, &grammar, Cst::leaf).unwrap();
+        assert_eq!(cst.span(), Some(Span::new(0, src.len())));
+        let items: Vec<_> = cst
+            .preorder()
+            .filter(|node| node.symbol() == &"Item")
+            .map(|node| {
+                let span = node.span().unwrap();
+                (*node.children()[0].symbol(), &src[span.start..span.end])
+            })
+            .collect();
+        assert_eq!(
+            items,
+            [
+                ("Word", "lorem"),
+                ("Space", " "),
+                ("Word", "ipsum"),
+                ("Space", "  "),
+                ("Num", "42")
+            ]
+        );
+
+        dfa.reset();
+        let err = dfa.parse_chars("ab-c", &grammar, Cst::leaf).unwrap_err();
+        assert_eq!(
+            err,
+            Error::UnknownChar {
+                found: '-',
+                index: 2,
+                span: Span::new(2, 3)
+            }
+        );
+
+        // a word can't be followed by a digit, even without a conflict
+        let restricted = grammar
+            .clone()
+            .with_restriction("Word", CharClass::new(['a'..='z', '0'..='9']));
+        dfa.reset();
+        assert!(dfa.parse_chars("ab42", &grammar, Cst::leaf).is_ok());
+        let lalr = Lalr::new(restricted.clone());
+        let mut dfa = lalr.push_dfa(ReductMap::new()).with_tree(Cst::node);
+        let err = dfa.parse_chars("ab42", &restricted, Cst::leaf).unwrap_err();
+        assert!(
+            matches!(err, Error::UnexpectedToken { index: 2, .. }),
+            "{err:?}"
+        );
+    }
+}
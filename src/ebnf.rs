@@ -0,0 +1,461 @@
+use std::fmt;
+
+use crate::{
+    grammar::Rule, reductor, Fresh, Grammar, GrammarError, Map, ReductMap, RuleMap, Set, Token,
+};
+
+/// EBNF production body, desugared by `Grammar::ebnf` into plain rules
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Ebnf<T> {
+    Sym(T),
+    /// Symbols in sequence. An empty one is the empty production.
+    Seq(Vec<Self>),
+    /// Alternatives, as a group
+    Alt(Vec<Self>),
+    /// `x*`
+    Star(Box<Self>),
+    /// `x+`
+    Plus(Box<Self>),
+    /// `x?`
+    Opt(Box<Self>),
+    /// Zero or more `x`s, separated by the symbol
+    SepBy(Box<Self>, T),
+    /// One or more `x`s, separated by the symbol
+    SepBy1(Box<Self>, T),
+}
+
+impl<T> Ebnf<T> {
+    #[must_use]
+    pub fn seq(items: impl IntoIterator<Item = Self>) -> Self {
+        Self::Seq(items.into_iter().collect())
+    }
+
+    #[must_use]
+    pub fn alt(items: impl IntoIterator<Item = Self>) -> Self {
+        Self::Alt(items.into_iter().collect())
+    }
+
+    #[must_use]
+    pub fn star(self) -> Self {
+        Self::Star(Box::new(self))
+    }
+
+    #[must_use]
+    pub fn plus(self) -> Self {
+        Self::Plus(Box::new(self))
+    }
+
+    #[must_use]
+    pub fn opt(self) -> Self {
+        Self::Opt(Box::new(self))
+    }
+
+    #[must_use]
+    pub fn sep_by(self, sep: T) -> Self {
+        Self::SepBy(Box::new(self), sep)
+    }
+
+    #[must_use]
+    pub fn sep_by1(self, sep: T) -> Self {
+        Self::SepBy1(Box::new(self), sep)
+    }
+}
+
+impl<T> From<T> for Ebnf<T> {
+    fn from(sym: T) -> Self {
+        Self::Sym(sym)
+    }
+}
+
+/// How a production of a helper rule builds its value, from the popped items
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Build {
+    /// An empty list
+    Empty,
+    /// A list of the single item
+    Single,
+    /// The first item, untouched
+    Pass,
+    /// The first item, a list, with the last item appended
+    Push,
+    /// A list of every item
+    Group,
+}
+
+/// Item values able to hold the lists built by the helper rules of `Grammar::ebnf`. Repetitions
+/// and separated lists build a list of their items, an option builds an empty or single list.
+pub trait ListItem: Sized {
+    fn from_list(items: Vec<Self>) -> Self;
+
+    /// Takes back the items of a `ListItem::from_list` value
+    fn into_list(self) -> Vec<Self>;
+}
+
+/// Desugars the EBNF bodies into plain rules, with fresh helper rules for the nested operators.
+/// Repetitions are left-recursive, so the parser stack doesn't grow with them.
+struct Desugar<T>
+where
+    T: Clone + Ord + fmt::Debug,
+{
+    rules: RuleMap<T>,
+    helpers: Map<T, Vec<Build>>,
+    used: Set<T>,
+}
+
+impl<T> Desugar<T>
+where
+    T: Clone + Ord + fmt::Debug + Fresh,
+{
+    /// Symbols of a production
+    fn prod(&mut self, rule: &T, body: Ebnf<T>) -> Vec<T> {
+        match body {
+            Ebnf::Sym(sym) => vec![sym],
+            Ebnf::Seq(items) => items
+                .into_iter()
+                .flat_map(|item| self.prod(rule, item))
+                .collect(),
+            body => vec![self.symbol(rule, body)],
+        }
+    }
+
+    /// Single symbol deriving `body`
+    fn symbol(&mut self, rule: &T, body: Ebnf<T>) -> T {
+        match body {
+            Ebnf::Sym(sym) => sym,
+            Ebnf::Seq(mut items) if items.len() == 1 => self.symbol(rule, items.remove(0)),
+            Ebnf::Seq(_) => {
+                let prod = self.prod(rule, body);
+                let build = Self::group(&prod);
+                self.helper(rule, vec![(prod, build)])
+            }
+            Ebnf::Alt(alts) => {
+                let prods = alts
+                    .into_iter()
+                    .map(|alt| {
+                        let prod = self.prod(rule, alt);
+                        let build = Self::group(&prod);
+                        (prod, build)
+                    })
+                    .collect();
+                self.helper(rule, prods)
+            }
+            Ebnf::Star(item) => {
+                let item = self.symbol(rule, *item);
+                self.recursive(rule, |list| {
+                    vec![(vec![list, item], Build::Push), (vec![], Build::Empty)]
+                })
+            }
+            Ebnf::Plus(item) => {
+                let item = self.symbol(rule, *item);
+                self.recursive(rule, |list| {
+                    vec![
+                        (vec![list, item.clone()], Build::Push),
+                        (vec![item], Build::Single),
+                    ]
+                })
+            }
+            Ebnf::Opt(item) => {
+                let item = self.symbol(rule, *item);
+                self.helper(
+                    rule,
+                    vec![(vec![item], Build::Single), (vec![], Build::Empty)],
+                )
+            }
+            Ebnf::SepBy(item, sep) => {
+                let list = self.symbol(rule, Ebnf::SepBy1(item, sep));
+                self.helper(
+                    rule,
+                    vec![(vec![list], Build::Pass), (vec![], Build::Empty)],
+                )
+            }
+            Ebnf::SepBy1(item, sep) => {
+                let item = self.symbol(rule, *item);
+                self.recursive(rule, |list| {
+                    vec![
+                        (vec![list, sep, item.clone()], Build::Push),
+                        (vec![item], Build::Single),
+                    ]
+                })
+            }
+        }
+    }
+
+    const fn group(prod: &[T]) -> Build {
+        if prod.len() == 1 {
+            Build::Pass
+        } else {
+            Build::Group
+        }
+    }
+
+    fn fresh(&mut self, rule: &T) -> T {
+        let name = T::fresh(rule, &self.used);
+        self.used.insert(name.clone());
+        name
+    }
+
+    fn helper(&mut self, rule: &T, prods: Vec<(Vec<T>, Build)>) -> T {
+        self.recursive(rule, |_| prods)
+    }
+
+    /// Adds a helper rule, whose productions can use its name
+    fn recursive(&mut self, rule: &T, prods: impl FnOnce(T) -> Vec<(Vec<T>, Build)>) -> T {
+        let name = self.fresh(rule);
+        let (prods, builds): (Vec<_>, Vec<_>) = prods(name.clone()).into_iter().unzip();
+        self.rules
+            .insert(name.clone(), Rule::new(name.clone(), prods));
+        self.helpers.insert(name.clone(), builds);
+        name
+    }
+}
+
+impl<T> Grammar<T>
+where
+    T: Clone + PartialEq + PartialOrd + Ord + fmt::Debug,
+{
+    /// Builds a grammar from rules with EBNF bodies, one per production. Each nested operator is
+    /// desugared into a fresh helper rule, listed in `Grammar::helpers` with the reductors of
+    /// `Grammar::list_reductors`.
+    /// # Errors
+    /// The same of `Grammar::new`
+    pub fn ebnf(
+        start: T,
+        rules: impl IntoIterator<Item = (T, Vec<Ebnf<T>>)>,
+        eof: T,
+    ) -> Result<Self, GrammarError<T>>
+    where
+        T: Fresh,
+    {
+        let rules: Vec<_> = rules.into_iter().collect();
+        let mut used = Set::from([eof.clone()]);
+        for (name, bodies) in &rules {
+            used.insert(name.clone());
+            bodies.iter().for_each(|body| body.symbols(&mut used));
+        }
+        let mut desugar = Desugar {
+            rules: RuleMap::new(),
+            helpers: Map::new(),
+            used,
+        };
+        for (name, bodies) in rules {
+            let prods: Vec<_> = bodies
+                .into_iter()
+                .map(|body| desugar.prod(&name, body))
+                .collect();
+            desugar
+                .rules
+                .insert(name.clone(), Rule::new(name.clone(), prods));
+        }
+        let mut grammar = Self::new(start, desugar.rules, eof)?;
+        grammar.helpers = desugar.helpers;
+        Ok(grammar)
+    }
+
+    /// Reductors of the helper rules of `Grammar::ebnf`, to be extended with the ones of the
+    /// user's rules
+    #[must_use]
+    pub fn list_reductors<'a, V>(&self) -> ReductMap<'a, V, T>
+    where
+        V: ListItem + 'a,
+        T: 'a,
+    {
+        self.helpers
+            .iter()
+            .map(|(name, builds)| {
+                let prods = builds
+                    .iter()
+                    .map(|build| match build {
+                        Build::Empty => reductor(|_: Vec<Token<V, T>>| V::from_list(Vec::new())),
+                        Build::Single | Build::Group => reductor(|toks: Vec<Token<V, T>>| {
+                            V::from_list(toks.into_iter().map(|t| t.item).collect())
+                        }),
+                        Build::Pass => {
+                            reductor(|mut toks: Vec<Token<V, T>>| toks.swap_remove(0).item)
+                        }
+                        Build::Push => reductor(|mut toks: Vec<Token<V, T>>| {
+                            let last = toks.pop().expect("a push has two items at least");
+                            let mut list = toks.swap_remove(0).item.into_list();
+                            list.push(last.item);
+                            V::from_list(list)
+                        }),
+                    })
+                    .collect();
+                (name.clone(), prods)
+            })
+            .collect()
+    }
+}
+
+impl<T> Ebnf<T>
+where
+    T: Clone + Ord,
+{
+    /// Collects the symbols used by the body
+    fn symbols(&self, used: &mut Set<T>) {
+        match self {
+            Self::Sym(sym) => {
+                used.insert(sym.clone());
+            }
+            Self::Seq(items) | Self::Alt(items) => {
+                items.iter().for_each(|item| item.symbols(used));
+            }
+            Self::Star(item) | Self::Plus(item) | Self::Opt(item) => item.symbols(used),
+            Self::SepBy(item, sep) | Self::SepBy1(item, sep) => {
+                used.insert(sep.clone());
+                item.symbols(used);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{reductor, Build, Ebnf, Grammar, Lalr, ListItem, Parser, ReductMap, Token};
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    enum Value {
+        Tok(&'static str),
+        List(Vec<Value>),
+    }
+
+    impl ListItem for Value {
+        fn from_list(items: Vec<Self>) -> Self {
+            Self::List(items)
+        }
+
+        fn into_list(self) -> Vec<Self> {
+            match self {
+                Self::List(items) => items,
+                tok @ Self::Tok(_) => vec![tok],
+            }
+        }
+    }
+
+    type Item = Token<Value, &'static str>;
+
+    fn grammar() -> Grammar<&'static str> {
+        /*
+            S -> Call.
+            Call -> id ( Expr % , ).
+            Expr -> n.
+            Expr -> Call.
+            Expr -> [ n* ].
+            Expr -> -+ n.
+            Expr -> < ( n | id id ) >.
+            Expr -> ! n?.
+        */
+        Grammar::ebnf(
+            "S",
+            [
+                ("S", vec![Ebnf::from("Call")]),
+                (
+                    "Call",
+                    vec![Ebnf::seq([
+                        "id".into(),
+                        "(".into(),
+                        Ebnf::from("Expr").sep_by(","),
+                        ")".into(),
+                    ])],
+                ),
+                (
+                    "Expr",
+                    vec![
+                        Ebnf::from("n"),
+                        Ebnf::from("Call"),
+                        Ebnf::seq(["[".into(), Ebnf::from("n").star(), "]".into()]),
+                        Ebnf::seq([Ebnf::from("-").plus(), "n".into()]),
+                        Ebnf::seq([
+                            "<".into(),
+                            Ebnf::alt(["n".into(), Ebnf::seq(["id".into(), "id".into()])]),
+                            ">".into(),
+                        ]),
+                        Ebnf::seq(["!".into(), Ebnf::from("n").opt()]),
+                    ],
+                ),
+            ],
+            "$",
+        )
+        .unwrap()
+    }
+
+    fn lex(src: &'static str) -> Vec<Item> {
+        src.split(' ')
+            .map(|word| {
+                let ty = match word {
+                    _ if word.chars().all(|c| c.is_ascii_digit()) => "n",
+                    _ if word.chars().all(char::is_alphabetic) => "id",
+                    _ => word,
+                };
+                Token::new(Value::Tok(word), ty)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn desugar() {
+        let grammar = grammar();
+        assert_eq!(grammar.validate(), []);
+        assert_eq!(grammar.helpers.len(), 6);
+        let (list, _) = grammar
+            .helpers
+            .iter()
+            .find(|(name, builds)| {
+                builds == &&[Build::Push, Build::Single]
+                    && grammar.rules[*name].prods[0].0.contains(&",")
+            })
+            .unwrap();
+        // left-recursive
+        assert_eq!(grammar.rules[list].prods[0].0, [*list, ",", "Expr"]);
+        assert_eq!(grammar.rules[list].prods[1].0, ["Expr"]);
+        assert_eq!(grammar.rules["Call"].prods[0].0.len(), 4);
+    }
+
+    #[test]
+    fn lists() {
+        use Value::{List, Tok};
+        let grammar = grammar();
+        let mut reductors = grammar.list_reductors();
+        reductors.extend(ReductMap::from([
+            ("S", vec![reductor(|mut t: Vec<Item>| t.remove(0).item)]),
+            (
+                "Call",
+                vec![reductor(|mut t: Vec<Item>| {
+                    let args = t.swap_remove(2).item;
+                    List(vec![t.swap_remove(0).item, args])
+                })],
+            ),
+            (
+                "Expr",
+                vec![
+                    reductor(|mut t: Vec<Item>| t.remove(0).item),
+                    reductor(|mut t| t.remove(0).item),
+                    reductor(|mut t| t.remove(1).item),
+                    reductor(|t| List(t.into_iter().map(|t| t.item).collect())),
+                    reductor(|mut t| t.remove(1).item),
+                    reductor(|mut t| t.remove(1).item),
+                ],
+            ),
+        ]));
+        let lalr = Lalr::new(grammar);
+        assert_eq!(lalr.tables().conflicts().count(), 0);
+
+        let src = "f ( 1 , [ 2 3 ] , g ( ) , - - 4 , < a b > , < 5 > , ! , ! 6 )";
+        let value = lalr.parse(lex(src), reductors).unwrap();
+        assert_eq!(
+            value,
+            List(vec![
+                Tok("f"),
+                List(vec![
+                    Tok("1"),
+                    List(vec![Tok("2"), Tok("3")]),
+                    List(vec![Tok("g"), List(vec![])]),
+                    List(vec![List(vec![Tok("-"), Tok("-")]), Tok("4")]),
+                    List(vec![Tok("a"), Tok("b")]),
+                    Tok("5"),
+                    List(vec![]),
+                    List(vec![Tok("6")]),
+                ])
+            ])
+        );
+    }
+}
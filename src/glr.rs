@@ -0,0 +1,417 @@
+use std::{collections::VecDeque, fmt};
+
+use crate::{ActTable, Action, Error, Map, ReductMap, Set, Token};
+
+/// Alternative derivation of a `ForestNode`: the production index in its rule and the children
+/// nodes.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Packed {
+    pub prod: usize,
+    pub children: Vec<usize>,
+}
+
+/// Node of a shared packed parse forest. Derives `symbol` from the tokens in `start..end`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForestNode<M> {
+    pub symbol: M,
+    pub start: usize,
+    pub end: usize,
+    /// Index of the input token, for leaves
+    pub token: Option<usize>,
+    /// Every derivation of the node, for non-terminals
+    pub alts: Vec<Packed>,
+}
+
+/// Shared packed parse forest (SPPF): every parse of an input, with the common subtrees and the
+/// ambiguous nodes packed only once.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Forest<T, M> {
+    pub tokens: Vec<Token<T, M>>,
+    pub nodes: Vec<ForestNode<M>>,
+    /// Nodes that derive the entire input
+    pub roots: Vec<usize>,
+}
+
+impl<T, M> Forest<T, M>
+where
+    T: Clone,
+    M: Clone + Ord,
+{
+    /// Amount of parse trees in the forest. Cyclic derivations aren't counted.
+    #[must_use]
+    pub fn trees(&self) -> usize {
+        let mut memo = Map::new();
+        let mut visiting = Set::new();
+        self.roots.iter().fold(0, |acc: usize, &root| {
+            acc.saturating_add(self.count(root, &mut memo, &mut visiting))
+        })
+    }
+
+    fn count(&self, node: usize, memo: &mut Map<usize, usize>, visiting: &mut Set<usize>) -> usize {
+        if let Some(&count) = memo.get(&node) {
+            return count;
+        }
+        if self.nodes[node].token.is_some() {
+            return 1;
+        }
+        if !visiting.insert(node) {
+            return 0;
+        }
+        let count = self.nodes[node].alts.iter().fold(0, |acc: usize, alt| {
+            let trees = alt.children.iter().fold(1, |acc: usize, &child| {
+                acc.saturating_mul(self.count(child, memo, visiting))
+            });
+            acc.saturating_add(trees)
+        });
+        visiting.remove(&node);
+        memo.insert(node, count);
+        count
+    }
+
+    /// Builds the first parse tree of the forest with `reductors`, like a `Dfa` does. Cyclic
+    /// derivations are skipped.
+    #[must_use]
+    pub fn first(&self, reductors: &mut ReductMap<T, M>) -> Option<T> {
+        let mut visiting = Set::new();
+        self.roots
+            .iter()
+            .find_map(|&root| self.build(root, reductors, &mut visiting))
+            .map(|tok| tok.item)
+    }
+
+    fn build(
+        &self,
+        node: usize,
+        reductors: &mut ReductMap<T, M>,
+        visiting: &mut Set<usize>,
+    ) -> Option<Token<T, M>> {
+        let ForestNode {
+            symbol,
+            token,
+            alts,
+            ..
+        } = &self.nodes[node];
+        if let Some(token) = token {
+            return self.tokens.get(*token).cloned();
+        }
+        if !visiting.insert(node) {
+            return None;
+        }
+        let tok = alts.iter().find_map(|alt| {
+            let children: Option<Vec<_>> = alt
+                .children
+                .iter()
+                .map(|&child| self.build(child, reductors, visiting))
+                .collect();
+            let reductor = reductors.get_mut(symbol)?.get_mut(alt.prod)?;
+            Some(Token::new(reductor(children?), symbol.clone()))
+        });
+        visiting.remove(&node);
+        tok
+    }
+}
+
+/// Node of the graph-structured stack. Each edge points to a previous node, labeled by the forest
+/// node of the symbol between them.
+#[derive(Debug, Clone)]
+struct Vertex {
+    state: usize,
+    level: usize,
+    edges: Vec<(usize, usize)>,
+}
+
+/// Generalized LR runtime. Runs over the same `ActTable` of a `Dfa`, but forks the stack on
+/// conflicting actions, with a graph-structured stack (Tomita's algorithm). So, every parse of
+/// an ambiguous grammar is found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Glr<M> {
+    pub table: ActTable<M>,
+    pub eof: M,
+}
+
+/// Parsing state of a `Glr` run
+struct Run<'a, T, M> {
+    table: &'a ActTable<M>,
+    vertices: Vec<Vertex>,
+    nodes: Vec<ForestNode<M>>,
+    /// Forest nodes by symbol, start and end
+    derived: Map<(M, usize, usize), usize>,
+    tokens: Vec<Token<T, M>>,
+}
+
+impl<T, M> Run<'_, T, M>
+where
+    M: Clone + Ord,
+{
+    /// Every path of `len` edges from `vertex`, with its last vertex and the edges' labels
+    fn paths(&self, vertex: usize, len: usize) -> Vec<(usize, Vec<usize>)> {
+        if len == 0 {
+            return vec![(vertex, Vec::new())];
+        }
+        self.vertices[vertex]
+            .edges
+            .iter()
+            .flat_map(|&(prev, label)| {
+                self.paths(prev, len - 1)
+                    .into_iter()
+                    .map(move |(end, mut labels)| {
+                        labels.push(label);
+                        (end, labels)
+                    })
+            })
+            .collect()
+    }
+
+    fn derive(&mut self, symbol: &M, start: usize, end: usize, alt: Packed) -> usize {
+        let node = *self
+            .derived
+            .entry((symbol.clone(), start, end))
+            .or_insert_with(|| {
+                self.nodes.push(ForestNode {
+                    symbol: symbol.clone(),
+                    start,
+                    end,
+                    token: None,
+                    alts: Vec::new(),
+                });
+                self.nodes.len() - 1
+            });
+        if !self.nodes[node].alts.contains(&alt) {
+            self.nodes[node].alts.push(alt);
+        }
+        node
+    }
+
+    fn actions(&self, state: usize, symbol: &M) -> Vec<Action<M>> {
+        self.table
+            .get(state)
+            .and_then(|row| row.get(symbol))
+            .map_or_else(Vec::new, |act| act.flatten().into_iter().cloned().collect())
+    }
+
+    fn expected(&self, frontier: &Map<usize, usize>) -> Vec<M> {
+        let expected: Set<_> = frontier
+            .keys()
+            .filter_map(|&state| self.table.get(state))
+            .flat_map(Map::keys)
+            .cloned()
+            .collect();
+        expected.into_iter().collect()
+    }
+
+    /// Applies every reduction of the `frontier` vertices over `symbol`, at the `level`-th token.
+    /// Returns the shifts and the accepted forest nodes.
+    fn reduce_all(
+        &mut self,
+        frontier: &mut Map<usize, usize>,
+        symbol: &M,
+        level: usize,
+    ) -> (Vec<(usize, usize)>, Set<usize>) {
+        let mut queue: VecDeque<_> = frontier.values().copied().collect();
+        let mut shifts = Set::new();
+        let mut roots = Set::new();
+        while let Some(vertex) = queue.pop_front() {
+            for act in self.actions(self.vertices[vertex].state, symbol) {
+                match act {
+                    Action::Shift(to) => {
+                        shifts.insert((vertex, to));
+                    }
+                    Action::Acc => {
+                        roots.extend(self.vertices[vertex].edges.iter().map(|&(_, label)| label));
+                    }
+                    Action::Reduce(name, prod) => {
+                        for (prev, children) in self.paths(vertex, prod.0.len()) {
+                            let Some(Action::Goto(to)) = self
+                                .table
+                                .get(self.vertices[prev].state)
+                                .and_then(|row| row.get(&name))
+                            else {
+                                continue;
+                            };
+                            let start = self.vertices[prev].level;
+                            let alt = Packed {
+                                prod: prod.1,
+                                children,
+                            };
+                            let label = self.derive(&name, start, level, alt);
+                            if let Some(&next) = frontier.get(to) {
+                                let edges = &mut self.vertices[next].edges;
+                                if !edges.iter().any(|&(p, _)| p == prev) {
+                                    edges.push((prev, label));
+                                    // the new edge creates paths for the already reduced vertices
+                                    queue.extend(frontier.values().copied());
+                                }
+                            } else {
+                                self.vertices.push(Vertex {
+                                    state: *to,
+                                    level,
+                                    edges: vec![(prev, label)],
+                                });
+                                frontier.insert(*to, self.vertices.len() - 1);
+                                queue.push_back(self.vertices.len() - 1);
+                            }
+                        }
+                    }
+                    Action::Goto(_) | Action::Conflict(..) => {}
+                }
+            }
+        }
+        (shifts.into_iter().collect(), roots)
+    }
+}
+
+impl<M> Glr<M>
+where
+    M: fmt::Debug + Clone + Ord,
+{
+    #[must_use]
+    pub const fn new(table: ActTable<M>, eof: M) -> Self {
+        Self { table, eof }
+    }
+
+    /// Parses `input` into a forest with all its parses.
+    /// # Errors
+    /// When every stack fails at a token, raises an `Error::UnexpectedToken` with the tokens
+    /// expected by all of them.
+    pub fn forest<T>(
+        &self,
+        input: impl IntoIterator<Item = Token<T, M>>,
+    ) -> Result<Forest<T, M>, Error<M>> {
+        let mut run = Run {
+            table: &self.table,
+            vertices: vec![Vertex {
+                state: 0,
+                level: 0,
+                edges: Vec::new(),
+            }],
+            nodes: Vec::new(),
+            derived: Map::new(),
+            tokens: input.into_iter().collect(),
+        };
+        let mut frontier = Map::from([(0, 0)]);
+        for level in 0..=run.tokens.len() {
+            let symbol = run
+                .tokens
+                .get(level)
+                .map_or_else(|| self.eof.clone(), |t| t.ty.clone());
+            let (shifts, roots) = run.reduce_all(&mut frontier, &symbol, level);
+            if level == run.tokens.len() && !roots.is_empty() {
+                return Ok(Forest {
+                    tokens: run.tokens,
+                    nodes: run.nodes,
+                    roots: roots.into_iter().collect(),
+                });
+            }
+            if shifts.is_empty() {
+                return Err(Error::UnexpectedToken {
+                    found: symbol,
+                    expected: run.expected(&frontier),
+                    index: level,
+                    span: None,
+                });
+            }
+
+            run.nodes.push(ForestNode {
+                symbol,
+                start: level,
+                end: level + 1,
+                token: Some(level),
+                alts: Vec::new(),
+            });
+            let leaf = run.nodes.len() - 1;
+            let mut next = Map::new();
+            for (prev, to) in shifts {
+                let vertex = *next.entry(to).or_insert_with(|| {
+                    run.vertices.push(Vertex {
+                        state: to,
+                        level: level + 1,
+                        edges: Vec::new(),
+                    });
+                    run.vertices.len() - 1
+                });
+                run.vertices[vertex].edges.push((prev, leaf));
+            }
+            frontier = next;
+        }
+        Err(Error::UnexpectedEof)
+    }
+
+    /// Parses `input` and builds its first successful parse with `reductors`.
+    /// # Errors
+    /// The same of `Glr::forest`. If every parse is cyclic, raises an
+    /// `Error::MissingPreviousState`.
+    pub fn parse<T>(
+        &self,
+        input: impl IntoIterator<Item = Token<T, M>>,
+        reductors: &mut ReductMap<T, M>,
+    ) -> Result<T, Error<M>>
+    where
+        T: Clone,
+    {
+        self.forest(input)?
+            .first(reductors)
+            .ok_or(Error::MissingPreviousState)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{grammars_tests, reductor, to_tokens, Error, Lalr, Parser, ReductMap, Token};
+
+    #[test]
+    fn ambiguous() {
+        let lalr = Lalr::new(grammars_tests::ambiguous());
+        assert_ne!(0, lalr.tables().conflicts().count());
+        let glr = lalr.glr();
+        // Catalan numbers
+        for (input, trees) in grammars_tests::AMBIGUOUS_INPUTS.iter().zip([1, 1, 2, 5]) {
+            let forest = glr.forest(to_tokens(input.iter().copied())).unwrap();
+            assert_eq!(forest.trees(), trees, "{input:?}");
+        }
+
+        let err = glr.forest(to_tokens(["n", "+"])).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::UnexpectedToken {
+                found: "$",
+                index: 2,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn first() {
+        type Tok = Token<String, &'static str>;
+        fn wrap(toks: Vec<Tok>) -> String {
+            format!("({})", toks.into_iter().map(|t| t.item).collect::<String>())
+        }
+        fn leaf(toks: Vec<Tok>) -> String {
+            toks.into_iter().next().unwrap().item
+        }
+        let lalr = Lalr::new(grammars_tests::ambiguous());
+        let mut maps = ReductMap::from([
+            ("S", vec![reductor(leaf)]),
+            ("E", vec![reductor(wrap), reductor(leaf)]),
+        ]);
+        let input = ["n", "+", "n", "+", "n"].map(|t| Token::new(t.to_string(), t));
+        let res = lalr.glr().parse(input, &mut maps).unwrap();
+        assert!(["((n+n)+n)", "(n+(n+n))"].contains(&res.as_str()), "{res}");
+    }
+
+    #[test]
+    fn deterministic() {
+        for (grammar, inputs, name) in grammars_tests::GRAMMARS {
+            let lalr = Lalr::new(grammar());
+            let glr = lalr.glr();
+            for input in *inputs {
+                let forest = glr.forest(to_tokens(input.iter().copied()));
+                // the LR(1) grammar inputs with LALR conflicts are parsed too
+                assert!(forest.is_ok(), "{name}: {input:?}");
+                if lalr.validate(to_tokens(input.iter().copied())) {
+                    assert_eq!(forest.unwrap().trees(), 1, "{name}: {input:?}");
+                }
+            }
+        }
+    }
+}
@@ -1,11 +1,31 @@
-use std::{fmt, rc::Rc};
+use std::{
+    fmt,
+    rc::Rc,
+    sync::{Mutex, PoisonError},
+};
 
-use crate::{Map, Position, Set};
+use crate::{Build, CharClass, Map, Position, Set};
+
+pub mod transform;
 
 /// Production Rule + Index In Declaration
 pub type Production<T> = (Vec<T>, usize);
 pub type RuleMap<T> = Map<T, Rule<T>>;
 
+/// Precedence level + Associativity. Higher levels bind tighter.
+pub type Precedence = (usize, Assoc);
+
+/// Associativity of a precedence level, like yacc's `%left`, `%right` and `%nonassoc`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Assoc {
+    /// `a + b + c` = `(a + b) + c`. Solves shift/reduce conflicts by reducing.
+    Left,
+    /// `a ^ b ^ c` = `a ^ (b ^ c)`. Solves shift/reduce conflicts by shifting.
+    Right,
+    /// `a == b == c` is an error. Solves shift/reduce conflicts by removing the action.
+    NonAssoc,
+}
+
 #[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Rule<T>
 where
@@ -44,6 +64,102 @@ where
     }
 }
 
+/// Symbols able to make fresh internal symbols, like the augmented start rule of
+/// `Grammar::augmented`
+pub trait Fresh: Sized {
+    /// Builds a symbol from `base`, different of every `used` one
+    fn fresh(base: &Self, used: &Set<Self>) -> Self;
+}
+
+impl Fresh for String {
+    fn fresh(base: &Self, used: &Set<Self>) -> Self {
+        let mut name = format!("{base}'");
+        while used.contains(&name) {
+            name.push('\'');
+        }
+        name
+    }
+}
+
+/// Names made by the `&'static str` symbols, so each distinct one is leaked only once
+static FRESH_NAMES: Mutex<Set<&'static str>> = Mutex::new(Set::new());
+
+/// The symbol is leaked the first time it's made, and reused after it
+impl Fresh for &'static str {
+    fn fresh(base: &Self, used: &Set<Self>) -> Self {
+        let mut name = format!("{base}'");
+        while used.contains(name.as_str()) {
+            name.push('\'');
+        }
+        // the set is left consistent by every insertion, even a panicking one
+        let mut names = FRESH_NAMES.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(interned) = names.get(name.as_str()) {
+            return interned;
+        }
+        let name = Box::leak(name.into_boxed_str());
+        names.insert(name);
+        name
+    }
+}
+
+/// Grammar diagnostic, found by `Grammar::validate`
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum GrammarError<T> {
+    /// The start rule isn't defined
+    MissingStart(T),
+    /// The start rule has more than one production. Contains their amount. `Grammar::augmented`
+    /// allows it.
+    AmbiguousStart(T, usize),
+    /// `Grammar::with_entries` got no start symbol
+    NoEntries,
+    /// The `prod`-th production of `rule` uses a symbol that isn't a rule nor a declared terminal
+    /// (see `Grammar::with_terminals`)
+    Undefined { rule: T, prod: usize, symbol: T },
+    /// The `prod`-th production of `rule` uses the eof symbol
+    EofInProduction { rule: T, prod: usize },
+    /// The productions `first` and `second` of `rule` are the same
+    Duplicate {
+        rule: T,
+        first: usize,
+        second: usize,
+    },
+    /// The rule can't be derived from the start rule
+    Unreachable(T),
+    /// The rule can't derive a terminals sequence
+    Nonproductive(T),
+}
+
+impl<T: fmt::Debug> fmt::Display for GrammarError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingStart(start) => {
+                f.write_fmt(format_args!("missing the start rule {start:?}"))
+            }
+            Self::AmbiguousStart(start, prods) => f.write_fmt(format_args!(
+                "the start rule {start:?} has {prods} productions instead of one"
+            )),
+            Self::NoEntries => f.write_str("no entry point"),
+            Self::Undefined { rule, prod, symbol } => f.write_fmt(format_args!(
+                "undefined symbol {symbol:?} in the production {prod} of {rule:?}"
+            )),
+            Self::EofInProduction { rule, prod } => f.write_fmt(format_args!(
+                "eof symbol in the production {prod} of {rule:?}"
+            )),
+            Self::Duplicate {
+                rule,
+                first,
+                second,
+            } => f.write_fmt(format_args!(
+                "the productions {first} and {second} of {rule:?} are the same"
+            )),
+            Self::Unreachable(rule) => f.write_fmt(format_args!("unreachable rule {rule:?}")),
+            Self::Nonproductive(rule) => f.write_fmt(format_args!("nonproductive rule {rule:?}")),
+        }
+    }
+}
+
+impl<T: fmt::Debug> std::error::Error for GrammarError<T> {}
+
 #[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Grammar<T>
 where
@@ -53,14 +169,81 @@ where
     pub terminals: Set<T>,
     pub symbols: Set<T>,
     pub basis: Position<T>,
+    /// Entry points of `Grammar::with_entries`, in declaration order: each start symbol and the
+    /// internal terminal selecting it. Empty with a single entry point.
+    pub entries: Vec<(T, T)>,
+    /// Precedence of each declared terminal
+    pub precs: Map<T, Precedence>,
+    /// Explicit production precedences (yacc's `%prec`), indexed by rule and production index
+    pub prod_precs: Map<(T, usize), T>,
+    /// Chars matched by each terminal, for scannerless parsing (see `Dfa::parse_chars`), in
+    /// declaration order
+    pub classes: Vec<(T, CharClass)>,
+    /// Chars that can't follow each symbol (SDF's `-/-`)
+    pub restrictions: Map<T, CharClass>,
+    /// Helper rules made by `Grammar::ebnf`, with the way each production builds its value
+    pub helpers: Map<T, Vec<Build>>,
 }
 
 impl<T> Grammar<T>
 where
     T: Clone + PartialEq + PartialOrd + Ord + fmt::Debug,
 {
-    #[must_use]
-    pub fn new(start: T, rules: RuleMap<T>, eof: T) -> Self {
+    /// # Errors
+    /// A missing or ambiguous start rule, or the eof used inside a production. The other
+    /// diagnostics of `Grammar::validate` don't stop building the grammar.
+    pub fn new(start: T, rules: RuleMap<T>, eof: T) -> Result<Self, GrammarError<T>> {
+        match rules.get(&start) {
+            None => Err(GrammarError::MissingStart(start)),
+            Some(rule) if rule.prods.len() != 1 => {
+                Err(GrammarError::AmbiguousStart(start, rule.prods.len()))
+            }
+            Some(_) => Self::build(start, rules, eof),
+        }
+    }
+
+    /// Builds a grammar with a fresh start rule deriving each of `starts`, after a fresh terminal
+    /// selecting it. So, one table parses all of them, starting from the state of
+    /// `Tabler::entry`.
+    /// # Errors
+    /// The same of `Grammar::new`
+    pub fn with_entries(
+        starts: impl IntoIterator<Item = T>,
+        mut rules: RuleMap<T>,
+        eof: T,
+    ) -> Result<Self, GrammarError<T>>
+    where
+        T: Fresh,
+    {
+        let starts: Vec<_> = starts.into_iter().collect();
+        if let Some(start) = starts.iter().find(|start| !rules.contains_key(start)) {
+            return Err(GrammarError::MissingStart(start.clone()));
+        }
+        let Some(first) = starts.first().cloned() else {
+            return Err(GrammarError::NoEntries);
+        };
+        let mut used = Self::used(&rules, &eof);
+        let mut entries = Vec::new();
+        for start in starts {
+            let marker = T::fresh(&start, &used);
+            used.insert(marker.clone());
+            entries.push((start, marker));
+        }
+        let fresh = T::fresh(&first, &used);
+        let prods = entries
+            .iter()
+            .map(|(start, marker)| vec![marker.clone(), start.clone()]);
+        rules.insert(fresh.clone(), Rule::new(fresh.clone(), prods));
+        let mut grammar = Self::build(fresh, rules, eof)?;
+        grammar.entries = entries;
+        Ok(grammar)
+    }
+
+    fn build(start: T, rules: RuleMap<T>, eof: T) -> Result<Self, GrammarError<T>> {
+        let prods = rules[&start].prods[0].clone();
+        if let Some((rule, prod)) = Self::eof_uses(&rules, &eof).next() {
+            return Err(GrammarError::EofInProduction { rule, prod });
+        }
         let mut terminals = Set::new();
         for rule in rules.values() {
             for rc in rule.prods() {
@@ -74,15 +257,270 @@ where
         terminals.insert(eof.clone());
         let symbols = rules.keys().chain(terminals.iter()).cloned().collect();
 
-        let prods = &rules[&start].prods;
-        debug_assert_eq!(prods.len(), 1, "there's more than one possible entry");
-        let basis = Position::new(start, prods[0].clone(), 0, Set::from([eof]));
-        Self {
+        let basis = Position::new(start, prods, 0, Set::from([eof]));
+        Ok(Self {
             rules,
             terminals,
             symbols,
             basis,
+            entries: Vec::new(),
+            precs: Map::new(),
+            prod_precs: Map::new(),
+            classes: Vec::new(),
+            restrictions: Map::new(),
+            helpers: Map::new(),
+        })
+    }
+
+    /// Builds a grammar with a fresh start rule deriving `start`, so the user's start rule can
+    /// have many productions and its value is the parsing result.
+    /// # Errors
+    /// The same of `Grammar::new`
+    pub fn augmented(start: T, mut rules: RuleMap<T>, eof: T) -> Result<Self, GrammarError<T>>
+    where
+        T: Fresh,
+    {
+        if !rules.contains_key(&start) {
+            return Err(GrammarError::MissingStart(start));
         }
+        let fresh = T::fresh(&start, &Self::used(&rules, &eof));
+        rules.insert(fresh.clone(), Rule::single(fresh.clone(), vec![start]));
+        Self::new(fresh, rules, eof)
+    }
+
+    /// Every symbol of `rules`, and the `eof`
+    fn used(rules: &RuleMap<T>, eof: &T) -> Set<T> {
+        rules
+            .values()
+            .flat_map(|rule| rule.prods.iter().flat_map(|prod| prod.0.iter()))
+            .chain(rules.keys())
+            .chain([eof])
+            .cloned()
+            .collect()
+    }
+
+    /// Productions using `eof`, as rule and production index
+    fn eof_uses<'a>(rules: &'a RuleMap<T>, eof: &'a T) -> impl Iterator<Item = (T, usize)> + 'a {
+        rules
+            .values()
+            .flat_map(|rule| rule.prods.iter().map(move |prod| (rule, prod)))
+            .filter(move |(_, prod)| prod.0.contains(eof))
+            .map(|(rule, prod)| (rule.name.clone(), prod.1))
+    }
+
+    /// Checks the grammar, returning every diagnostic found. An empty result means a valid grammar.
+    #[must_use]
+    pub fn validate(&self) -> Vec<GrammarError<T>> {
+        let mut errors = Vec::new();
+        let start = &self.basis.rule;
+        match self.rules.get(start) {
+            None => errors.push(GrammarError::MissingStart(start.clone())),
+            Some(rule) if rule.prods.len() != 1 && self.entries.is_empty() => {
+                errors.push(GrammarError::AmbiguousStart(
+                    start.clone(),
+                    rule.prods.len(),
+                ));
+            }
+            Some(_) => {}
+        }
+        for rule in self.rules.values() {
+            for prod in &rule.prods {
+                errors.extend(
+                    prod.0
+                        .iter()
+                        .filter(|sym| {
+                            !self.rules.contains_key(sym) && !self.terminals.contains(sym)
+                        })
+                        .map(|sym| GrammarError::Undefined {
+                            rule: rule.name.clone(),
+                            prod: prod.1,
+                            symbol: sym.clone(),
+                        }),
+                );
+            }
+        }
+        for eof in &self.basis.look {
+            errors.extend(
+                Self::eof_uses(&self.rules, eof)
+                    .map(|(rule, prod)| GrammarError::EofInProduction { rule, prod }),
+            );
+        }
+        for rule in self.rules.values() {
+            for (i, first) in rule.prods.iter().enumerate() {
+                if let Some(second) = rule.prods[i + 1..].iter().find(|p| p.0 == first.0) {
+                    errors.push(GrammarError::Duplicate {
+                        rule: rule.name.clone(),
+                        first: first.1,
+                        second: second.1,
+                    });
+                }
+            }
+        }
+        let reachable = self.reachable();
+        errors.extend(
+            self.rules
+                .keys()
+                .filter(|name| !reachable.contains(name))
+                .map(|name| GrammarError::Unreachable(name.clone())),
+        );
+        let productive = self.productive();
+        errors.extend(
+            self.rules
+                .keys()
+                .filter(|name| !productive.contains(name))
+                .map(|name| GrammarError::Nonproductive(name.clone())),
+        );
+        errors
+    }
+
+    /// Rules derivable from the start rule
+    #[must_use]
+    pub fn reachable(&self) -> Set<T> {
+        let mut reachable = Set::from([self.basis.rule.clone()]);
+        let mut pending = vec![self.basis.rule.clone()];
+        while let Some(name) = pending.pop() {
+            let Some(rule) = self.rules.get(&name) else {
+                continue;
+            };
+            for sym in rule.prods.iter().flat_map(|prod| &prod.0) {
+                if self.rules.contains_key(sym) && reachable.insert(sym.clone()) {
+                    pending.push(sym.clone());
+                }
+            }
+        }
+        reachable
+    }
+
+    /// Rules deriving some terminals sequence
+    #[must_use]
+    pub fn productive(&self) -> Set<T> {
+        let mut productive = Set::new();
+        loop {
+            let found: Vec<_> = self
+                .rules
+                .values()
+                .filter(|rule| !productive.contains(&rule.name))
+                .filter(|rule| {
+                    rule.prods.iter().any(|prod| {
+                        prod.0
+                            .iter()
+                            .all(|sym| self.is_terminal(sym) || productive.contains(sym))
+                    })
+                })
+                .map(|rule| rule.name.clone())
+                .collect();
+            if found.is_empty() {
+                return productive;
+            }
+            productive.extend(found);
+        }
+    }
+
+    /// Declares a new precedence level for `terms`, binding tighter than the previously declared
+    /// ones. The same of a yacc's `%left`, `%right` or `%nonassoc` line.
+    #[must_use]
+    pub fn with_level(mut self, assoc: Assoc, terms: impl IntoIterator<Item = T>) -> Self {
+        self.add_level(assoc, terms);
+        self
+    }
+
+    pub fn add_level(&mut self, assoc: Assoc, terms: impl IntoIterator<Item = T>) {
+        let level = self
+            .precs
+            .values()
+            .map(|(l, _)| l + 1)
+            .max()
+            .unwrap_or_default();
+        self.precs
+            .extend(terms.into_iter().map(|t| (t, (level, assoc))));
+    }
+
+    /// Sets the precedence of the `prod` production in `rule` as the same of `term`. The same of
+    /// a yacc's `%prec`.
+    #[must_use]
+    pub fn with_prec(mut self, rule: T, prod: usize, term: T) -> Self {
+        self.prod_precs.insert((rule, prod), term);
+        self
+    }
+
+    /// Declares the terminals, so `Grammar::validate` reports the other symbols of the
+    /// productions that aren't rules as `GrammarError::Undefined`. Without it, every one of them
+    /// is a terminal. The eof, the entry markers and the character class terminals are always
+    /// declared.
+    #[must_use]
+    pub fn with_terminals(mut self, terms: impl IntoIterator<Item = T>) -> Self {
+        let markers = self.entries.iter().map(|(_, marker)| marker);
+        let classes = self.classes.iter().map(|(term, _)| term);
+        let implicit = self.basis.look.iter().chain(markers).chain(classes);
+        self.terminals = terms.into_iter().chain(implicit.cloned()).collect();
+        self.symbols = self.rules.keys().chain(&self.terminals).cloned().collect();
+        self
+    }
+
+    /// Declares `term` as a character class terminal, matching the chars of `class`. So, a range
+    /// like `a-z` is a single terminal instead of one per char. Declaring `term` again replaces
+    /// its class, keeping its place.
+    #[must_use]
+    pub fn with_class(mut self, term: T, class: CharClass) -> Self {
+        let used = (self.rules.values()).any(|rule| rule.prods.iter().any(|p| p.0.contains(&term)));
+        if used && !self.rules.contains_key(&term) {
+            self.terminals.insert(term.clone());
+            self.symbols.insert(term.clone());
+        }
+        match self.classes.iter_mut().find(|(t, _)| *t == term) {
+            Some((_, old)) => *old = class,
+            None => self.classes.push((term, class)),
+        }
+        self
+    }
+
+    /// Forbids `symbol` to be followed by a char of `class`, like a SDF's follow restriction: the
+    /// productions of `symbol`, or ending with it, aren't reduced over the character class
+    /// terminals inside `class` (see `Tabler::solve`). So, it also works as a longest-match rule.
+    #[must_use]
+    pub fn with_restriction(mut self, symbol: T, class: CharClass) -> Self {
+        self.restrictions.insert(symbol, class);
+        self
+    }
+
+    /// Checks if a follow restriction forbids reducing `prod` of `rule` before `term`
+    #[must_use]
+    pub fn restricts(&self, rule: &T, prod: &Production<T>, term: &T) -> bool {
+        let Some((_, class)) = self.classes.iter().find(|(t, _)| t == term) else {
+            return false;
+        };
+        std::iter::once(rule)
+            .chain(prod.0.last())
+            .filter_map(|sym| self.restrictions.get(sym))
+            .any(|restriction| class.is_subset(restriction))
+    }
+
+    /// Terminal of the first declared class containing `c` and allowed by `filter`
+    #[must_use]
+    pub fn classify(&self, c: char, filter: impl Fn(&T) -> bool) -> Option<&T> {
+        self.classes
+            .iter()
+            .find(|(term, class)| class.contains(c) && filter(term))
+            .map(|(term, _)| term)
+    }
+
+    #[must_use]
+    pub fn term_prec(&self, term: &T) -> Option<Precedence> {
+        self.precs.get(term).copied()
+    }
+
+    /// Returns the precedence of a production: the explicit one, if declared, or the same of its
+    /// rightmost terminal with a declared precedence.
+    #[must_use]
+    pub fn prod_prec(&self, rule: &T, prod: &Production<T>) -> Option<Precedence> {
+        if let Some(term) = self.prod_precs.get(&(rule.clone(), prod.1)) {
+            return self.term_prec(term);
+        }
+        prod.0
+            .iter()
+            .rev()
+            .filter(|s| self.is_terminal(s))
+            .find_map(|s| self.term_prec(s))
     }
 
     #[must_use]
@@ -103,3 +541,143 @@ where
         self.symbols.iter().cloned()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        grammar_map, grammars_tests, reductor, CharClass, Fresh, Grammar, GrammarError, Lalr,
+        Parser, ReductMap, Set, Token,
+    };
+
+    #[test]
+    fn valid() {
+        for (grammar, _, name) in grammars_tests::GRAMMARS {
+            assert_eq!(grammar().validate(), [], "{name}");
+        }
+        assert_eq!(grammars_tests::scannerless().validate(), []);
+        assert_eq!(grammars_tests::entries().validate(), []);
+    }
+
+    #[test]
+    fn diagnostics() {
+        let rules = grammar_map! {
+            "S" -> "A",
+            "A" -> "a" "B" | "a" | "a",
+            "B" -> "b" "B",
+            "C" -> "c",
+            "D" -> "A" "x"
+        };
+        let grammar = Grammar::new("S", rules, "$")
+            .unwrap()
+            .with_terminals(["a", "b", "c"]);
+        assert_eq!(
+            grammar.validate(),
+            [
+                GrammarError::Undefined {
+                    rule: "D",
+                    prod: 0,
+                    symbol: "x"
+                },
+                GrammarError::Duplicate {
+                    rule: "A",
+                    first: 1,
+                    second: 2
+                },
+                GrammarError::Unreachable("C"),
+                GrammarError::Unreachable("D"),
+                GrammarError::Nonproductive("B"),
+            ]
+        );
+        // declaring no terminals leaves every use of `a`, `b`, `c` and `x` undefined
+        assert_eq!(grammar.clone().with_terminals([]).validate().len(), 10);
+        let x = grammar.with_class("x", CharClass::new(['x'..='x']));
+        assert_eq!(x.validate().len(), 4);
+    }
+
+    #[test]
+    fn construction() {
+        let rules = || grammar_map! { "S" -> "a" | "b" };
+        assert_eq!(
+            Grammar::new("T", rules(), "$"),
+            Err(GrammarError::MissingStart("T"))
+        );
+        assert_eq!(
+            Grammar::new("S", rules(), "$"),
+            Err(GrammarError::AmbiguousStart("S", 2))
+        );
+        let eof_rules = grammar_map! { "S" -> "A", "A" -> "a" "$" };
+        assert_eq!(
+            Grammar::new("S", eof_rules, "$"),
+            Err(GrammarError::EofInProduction { rule: "A", prod: 0 })
+        );
+        assert_eq!(
+            Grammar::with_entries(["S", "T"], rules(), "$"),
+            Err(GrammarError::MissingStart("T"))
+        );
+        assert_eq!(
+            Grammar::with_entries([], rules(), "$"),
+            Err(GrammarError::NoEntries)
+        );
+    }
+
+    #[test]
+    fn fresh() {
+        let used = Set::from(["E", "E'"]);
+        let (a, b) = (<&str>::fresh(&"E", &used), <&str>::fresh(&"E", &used));
+        assert_eq!(a, "E''");
+        // the same name is interned instead of leaked again
+        assert!(std::ptr::eq(a, b));
+    }
+
+    #[test]
+    fn classify() {
+        let rules = grammar_map! { "S" -> "T", "T" -> "word" | "hex" };
+        let grammar = Grammar::new("S", rules, "$")
+            .unwrap()
+            .with_class("word", CharClass::new(['a'..='z']))
+            .with_class("hex", CharClass::new(['0'..='9', 'a'..='f']));
+        // overlapping classes match in declaration order
+        assert_eq!(grammar.classify('a', |_| true), Some(&"word"));
+        assert_eq!(grammar.classify('a', |term| *term == "hex"), Some(&"hex"));
+        assert_eq!(grammar.classify('7', |_| true), Some(&"hex"));
+
+        let grammar = grammar.with_class("word", CharClass::new(['g'..='z']));
+        assert_eq!(grammar.classify('a', |_| true), Some(&"hex"));
+        assert_eq!(grammar.classes[0].0, "word");
+    }
+
+    #[test]
+    fn augmented() {
+        type Tok = Token<i64, &'static str>;
+        // the start rule has many productions and a `E'` symbol is taken
+        let rules = grammar_map! {
+            "E" -> "E" "+" "n" | "E" "-" "n" | "n" "E'",
+            "E'" -> "!" | "?"
+        };
+        let grammar = Grammar::augmented("E", rules, "$").unwrap();
+        assert_eq!(grammar.basis.rule, "E''");
+        assert_eq!(grammar.validate(), []);
+
+        let lalr = Lalr::new(grammar);
+        assert_eq!(lalr.tables().conflicts().count(), 0);
+        let reductors = ReductMap::from([
+            (
+                "E",
+                vec![
+                    reductor(|t: Vec<Tok>| t[0].item + t[2].item),
+                    reductor(|t: Vec<Tok>| t[0].item - t[2].item),
+                    reductor(|t: Vec<Tok>| t[0].item),
+                ],
+            ),
+            ("E'", vec![reductor(|_: Vec<Tok>| 0), reductor(|_| 0)]),
+        ]);
+        let input = [("n", 8), ("!", 0), ("-", 0), ("n", 3), ("+", 0), ("n", 1)];
+        let tokens = input.into_iter().map(|(ty, n)| Token::new(n, ty));
+        assert_eq!(lalr.parse(tokens, reductors), Ok(6));
+
+        assert_eq!(
+            Grammar::augmented("S", grammar_map! { "E" -> "n" }, "$"),
+            Err(GrammarError::MissingStart("S"))
+        );
+    }
+}
@@ -0,0 +1,1094 @@
+//! Rewrites of a grammar into an equivalent one. Each rewrite returns the new grammar along with
+//! a `Remap`, telling the old productions behind each new one, so the reductors of the old
+//! grammar can be adapted by `Remap::adapt`. The rewrites adding helper rules return a
+//! `HelperRemap`, whose values are lists.
+
+use std::{cell::RefCell, collections::VecDeque, fmt, iter, rc::Rc};
+
+use crate::{
+    grammar::Rule, reductor, Fresh, Grammar, ListItem, Map, Position, ReductMap, RuleMap, Set,
+    Token,
+};
+
+/// Old productions deriving the items of a new production
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Derivation<T> {
+    pub rule: T,
+    pub prod: usize,
+    /// Symbols of the production replaced by the items of their own derivation, by index
+    pub inlined: Map<usize, Derivation<T>>,
+    /// Left recursion tail rule (see `Origin::Tail`), whose item follows the production ones and
+    /// folds the value of the production
+    pub tail: Option<T>,
+}
+
+impl<T> Derivation<T>
+where
+    T: Clone + Ord + fmt::Debug,
+{
+    /// The old production deriving only itself
+    #[must_use]
+    pub fn new(rule: T, prod: usize) -> Self {
+        Self {
+            rule,
+            prod,
+            inlined: Map::new(),
+            tail: None,
+        }
+    }
+
+    /// Symbols of the derived items, in order
+    /// # Panics
+    /// The derivation uses a production missing in `old`
+    #[must_use]
+    pub fn leaves(&self, old: &RuleMap<T>) -> Vec<T> {
+        let prod = &old[&self.rule].prods[self.prod].0;
+        let leaves = prod.iter().enumerate().flat_map(|(i, sym)| {
+            self.inlined
+                .get(&i)
+                .map_or_else(|| vec![sym.clone()], |inner| inner.leaves(old))
+        });
+        leaves.chain(self.tail.clone()).collect()
+    }
+
+    /// Replaces the `leaf`-th derived item by the items derived by `with`
+    fn inline(&mut self, leaf: usize, with: Self, old: &RuleMap<T>) {
+        self.substitute(&mut { leaf }, &mut Some(with), old);
+    }
+
+    fn substitute(&mut self, leaf: &mut usize, with: &mut Option<Self>, old: &RuleMap<T>) {
+        for i in 0..old[&self.rule].prods[self.prod].0.len() {
+            if with.is_none() {
+                return;
+            }
+            if let Some(inner) = self.inlined.get_mut(&i) {
+                inner.substitute(leaf, with, old);
+            } else if *leaf == 0 {
+                self.inlined.insert(i, with.take().expect("checked above"));
+            } else {
+                *leaf -= 1;
+            }
+        }
+        if self.tail.is_some() {
+            *leaf = leaf.saturating_sub(1);
+        }
+    }
+}
+
+/// Way the value of a new production is built from the old reductors
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Origin<T> {
+    /// Built by the reductors of the derivation, over the items
+    Derived(Derivation<T>),
+    /// Left factored prefix `A -> α A'`. Its derivation is the one of the suffix of `A'`.
+    Prefix,
+    /// Common part of some left factored suffixes, followed by the rest of them
+    Infix,
+    /// Rest of a left factored production, with the derivation of the whole production
+    Suffix(Derivation<T>),
+    /// Left recursion tail `A' -> α A'`, with the derivation of the recursive production. Its
+    /// first item is the value built so far.
+    Tail(Derivation<T>),
+    /// Empty end of a left recursion tail
+    End,
+}
+
+/// Remapping of the productions made by a rewrite
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Remap<T>
+where
+    T: Clone + Ord + fmt::Debug,
+{
+    /// Rules of the grammar before the rewrite
+    pub old: RuleMap<T>,
+    /// Origin of each production of the new grammar, by rule and production index
+    pub origins: Map<T, Vec<Origin<T>>>,
+}
+
+impl<T> Remap<T>
+where
+    T: Clone + Ord + fmt::Debug,
+{
+    /// Helper rules made by the rewrite, whose values are lists of deferred items
+    #[must_use]
+    pub fn helpers(&self) -> Set<T> {
+        self.origins
+            .iter()
+            .filter(|(_, origins)| {
+                origins.iter().any(|origin| {
+                    !matches!(origin, Origin::Derived(_) | Origin::Prefix | Origin::Infix)
+                })
+            })
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+
+    /// Reductors of the new grammar, made of the `reductors` of the old one
+    /// # Panics
+    /// A reductor of a helper rule is called: the rewrites adding them return a `HelperRemap`
+    #[must_use]
+    pub fn adapt<'a, V>(&self, reductors: ReductMap<'a, V, T>) -> ReductMap<'a, V, T>
+    where
+        V: 'a,
+        T: 'a,
+    {
+        Adapter::reductors(self, reductors, None)
+    }
+}
+
+/// Remapping of a rewrite adding helper rules, as `left_factor` and `remove_left_recursion`
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct HelperRemap<T>(pub Remap<T>)
+where
+    T: Clone + Ord + fmt::Debug;
+
+impl<T> HelperRemap<T>
+where
+    T: Clone + Ord + fmt::Debug,
+{
+    /// Reductors of the new grammar, made of the `reductors` of the old one. Values of the helper
+    /// rules are the lists of their items, built by `ListItem`.
+    #[must_use]
+    pub fn adapt<'a, V>(&self, reductors: ReductMap<'a, V, T>) -> ReductMap<'a, V, T>
+    where
+        V: ListItem + 'a,
+        T: 'a,
+    {
+        let lists = Lists {
+            from: V::from_list,
+            into: V::into_list,
+        };
+        Adapter::reductors(&self.0, reductors, Some(lists))
+    }
+}
+
+/// Derivations waiting inside a value until the value before them is known, by index in
+/// `Adapter::deferred`, along with the ones of the items of its list
+#[derive(Debug, Clone, Default)]
+struct Deferred {
+    parts: Vec<usize>,
+    items: Vec<Deferred>,
+}
+
+thread_local! {
+    /// Deferred parts of the items given by an adapter to the reductors of the one it adapts
+    static PASSED: RefCell<Option<Vec<Deferred>>> = const { RefCell::new(None) };
+    /// Deferred parts of the value built by an adapted reductor called by another adapter
+    static RETURNED: RefCell<Option<Deferred>> = const { RefCell::new(None) };
+}
+
+/// Builds and takes apart the values of the helper rules
+struct Lists<V> {
+    from: fn(Vec<V>) -> V,
+    into: fn(V) -> Vec<V>,
+}
+
+/// Reduces through the old reductors. The deferred parts of the values are kept beside them: in
+/// `stack` for the items on the parser stack, or passed along the items when the old reductors
+/// are adapted ones, so adapted reductors can be adapted again by a later rewrite.
+struct Adapter<'a, V, T>
+where
+    T: Clone + Ord + fmt::Debug,
+{
+    old: RuleMap<T>,
+    reductors: RefCell<ReductMap<'a, V, T>>,
+    /// Derivations of the left factored suffixes and the left recursion steps
+    deferred: Vec<Derivation<T>>,
+    /// Rules of the new grammar, whose items have their deferred parts in `stack`
+    rules: Set<T>,
+    stack: RefCell<Vec<Deferred>>,
+    lists: Option<Lists<V>>,
+}
+
+impl<'a, V, T> Adapter<'a, V, T>
+where
+    T: Clone + Ord + fmt::Debug + 'a,
+    V: 'a,
+{
+    fn reductors(
+        remap: &Remap<T>,
+        reductors: ReductMap<'a, V, T>,
+        lists: Option<Lists<V>>,
+    ) -> ReductMap<'a, V, T> {
+        let mut deferred = Vec::new();
+        let origins: Map<_, Vec<_>> = (remap.origins.iter())
+            .map(|(name, origins)| {
+                let origins = origins.iter().map(|origin| {
+                    if let Origin::Suffix(derivation) | Origin::Tail(derivation) = origin {
+                        deferred.push(derivation.clone());
+                    }
+                    (origin.clone(), deferred.len().saturating_sub(1))
+                });
+                (name.clone(), origins.collect())
+            })
+            .collect();
+        let adapter = Rc::new(Adapter {
+            old: remap.old.clone(),
+            reductors: RefCell::new(reductors),
+            deferred,
+            rules: origins.keys().cloned().collect(),
+            stack: RefCell::new(Vec::new()),
+            lists,
+        });
+        origins
+            .into_iter()
+            .map(|(name, origins)| {
+                let prods = origins
+                    .into_iter()
+                    .map(|(origin, part)| {
+                        let adapter = adapter.clone();
+                        reductor(move |items| adapter.adapted(&origin, part, items))
+                    })
+                    .collect();
+                (name, prods)
+            })
+            .collect()
+    }
+
+    /// Reduces the `items` of a new production, taking their deferred parts from the adapter
+    /// calling it or from `stack`, and leaving the ones of the value at the same place
+    fn adapted(&self, origin: &Origin<T>, part: usize, items: Vec<Token<V, T>>) -> V {
+        let passed = PASSED.with(|passed| passed.borrow_mut().take());
+        let outer = passed.is_some();
+        let deferred = passed.unwrap_or_else(|| {
+            let mut stack = self.stack.borrow_mut();
+            let count = items.iter().filter(|i| self.rules.contains(&i.ty)).count();
+            let low = stack.len().saturating_sub(count);
+            let mut popped = stack.split_off(low).into_iter();
+            (items.iter())
+                .map(|item| {
+                    (self.rules.contains(&item.ty))
+                        .then(|| popped.next())
+                        .flatten()
+                        .unwrap_or_default()
+                })
+                .collect()
+        });
+        let items = items.into_iter().zip(deferred).collect();
+        let (value, deferred) = self.reduce(origin, part, items);
+        if outer {
+            RETURNED.with(|returned| *returned.borrow_mut() = Some(deferred));
+        } else {
+            self.stack.borrow_mut().push(deferred);
+        }
+        value
+    }
+
+    /// Builds the value of a new production. `part` is the index of the derivation of a suffix or
+    /// a tail origin.
+    fn reduce(
+        &self,
+        origin: &Origin<T>,
+        part: usize,
+        mut items: Vec<(Token<V, T>, Deferred)>,
+    ) -> (V, Deferred) {
+        let mut last = || {
+            let (item, deferred) = items.pop().expect("a helper item ends the production");
+            self.split(item.item, deferred)
+        };
+        let values = |items: Vec<(Token<V, T>, Deferred)>| {
+            items
+                .into_iter()
+                .map(|(item, deferred)| (item.item, deferred))
+        };
+        match origin {
+            Origin::Derived(derivation) => self.eval(derivation, &mut items.into_iter()),
+            Origin::Prefix => {
+                let (rest, mut parts) = last();
+                let suffix = &self.deferred[parts.pop().expect("a suffix is deferred")];
+                let mut items = (values(items).chain(rest))
+                    .zip(suffix.leaves(&self.old))
+                    .map(|((value, deferred), sym)| (Token::new(value, sym), deferred));
+                self.eval(suffix, &mut items)
+            }
+            Origin::Infix => {
+                let (rest, parts) = last();
+                self.join(values(items).chain(rest).collect(), parts)
+            }
+            Origin::Suffix(_) => self.join(values(items).collect(), vec![part]),
+            Origin::Tail(_) => {
+                let (rest, mut parts) = last();
+                parts.insert(0, part);
+                let step = self.join(values(items).collect(), Vec::new());
+                self.join(iter::once(step).chain(rest).collect(), parts)
+            }
+            Origin::End => self.join(Vec::new(), Vec::new()),
+        }
+    }
+
+    /// Reduces the `derivation` over the derived `items`, folding the steps of its left recursion
+    /// tail
+    fn eval(
+        &self,
+        derivation: &Derivation<T>,
+        items: &mut dyn Iterator<Item = (Token<V, T>, Deferred)>,
+    ) -> (V, Deferred) {
+        let prod = &self.old[&derivation.rule].prods[derivation.prod].0;
+        let mut toks = Vec::with_capacity(prod.len());
+        let mut passed = Vec::with_capacity(prod.len());
+        for (i, sym) in prod.iter().enumerate() {
+            let (item, deferred) = match derivation.inlined.get(&i) {
+                Some(inner) => {
+                    let (value, deferred) = self.eval(inner, items);
+                    (Token::new(value, sym.clone()), deferred)
+                }
+                None => items.next().expect("an item by leaf"),
+            };
+            toks.push(item);
+            passed.push(deferred);
+        }
+        let mut value = {
+            let mut reductors = self.reductors.borrow_mut();
+            let reductor = reductors
+                .get_mut(&derivation.rule)
+                .and_then(|prods| prods.get_mut(derivation.prod))
+                .expect("a reductor by old production");
+            PASSED.with(|cell| *cell.borrow_mut() = Some(passed));
+            let value = reductor(toks);
+            PASSED.with(|cell| cell.borrow_mut().take());
+            let deferred = RETURNED.with(|cell| cell.borrow_mut().take());
+            (value, deferred.unwrap_or_default())
+        };
+        if derivation.tail.is_some() {
+            let (tail, deferred) = items.next().expect("a tail item");
+            let (steps, parts) = self.split(tail.item, deferred);
+            for ((step, deferred), part) in steps.into_iter().zip(parts) {
+                let step_derivation = &self.deferred[part];
+                let leaves = step_derivation.leaves(&self.old);
+                let first = (Token::new(value.0, derivation.rule.clone()), value.1);
+                let (step, _) = self.split(step, deferred);
+                let rest = (step.into_iter())
+                    .zip(leaves.into_iter().skip(1))
+                    .map(|((value, deferred), sym)| (Token::new(value, sym), deferred));
+                value = self.eval(step_derivation, &mut iter::once(first).chain(rest));
+            }
+        }
+        value
+    }
+
+    fn lists(&self) -> &Lists<V> {
+        (self.lists.as_ref()).expect("helper rules are adapted by `HelperRemap::adapt`")
+    }
+
+    /// Helper value of `values`, deferring the derivations of `parts`
+    fn join(&self, values: Vec<(V, Deferred)>, parts: Vec<usize>) -> (V, Deferred) {
+        let (values, items) = values.into_iter().unzip();
+        ((self.lists().from)(values), Deferred { parts, items })
+    }
+
+    /// Takes back the values and the deferred parts of a `Adapter::join` value
+    fn split(&self, value: V, deferred: Deferred) -> (Vec<(V, Deferred)>, Vec<usize>) {
+        let items = deferred
+            .items
+            .into_iter()
+            .chain(iter::repeat_with(Deferred::default));
+        let values = (self.lists().into)(value).into_iter().zip(items).collect();
+        (values, deferred.parts)
+    }
+}
+
+/// Productions of each rule being rewritten, with their origin
+type Work<T> = Map<T, Vec<(Vec<T>, Origin<T>)>>;
+
+fn work<T>(grammar: &Grammar<T>) -> Work<T>
+where
+    T: Clone + Ord + fmt::Debug,
+{
+    grammar
+        .rules
+        .iter()
+        .map(|(name, rule)| {
+            let prods = rule.prods.iter().map(|prod| {
+                let derivation = Derivation::new(name.clone(), prod.1);
+                (prod.0.clone(), Origin::Derived(derivation))
+            });
+            (name.clone(), prods.collect())
+        })
+        .collect()
+}
+
+fn derivation<T>(origin: &Origin<T>) -> &Derivation<T> {
+    match origin {
+        Origin::Derived(derivation) => derivation,
+        _ => unreachable!("only derived productions are rewritten"),
+    }
+}
+
+/// Builds the rewritten grammar, keeping the start, precedences, chars classes and restrictions
+/// of `grammar`
+fn finish<T>(grammar: &Grammar<T>, work: Work<T>) -> (Grammar<T>, Remap<T>)
+where
+    T: Clone + Ord + fmt::Debug,
+{
+    let mut rules = RuleMap::new();
+    let mut origins = Map::new();
+    for (name, prods) in work {
+        let (prods, prod_origins): (Vec<_>, Vec<_>) = prods.into_iter().unzip();
+        rules.insert(name.clone(), Rule::new(name.clone(), prods));
+        origins.insert(name, prod_origins);
+    }
+    let look = grammar.basis.look.clone();
+    let start = &grammar.basis.rule;
+    let mut new = grammar.clone();
+    new.terminals = (rules.values())
+        .flat_map(|rule| rule.prods.iter().flat_map(|prod| &prod.0))
+        .filter(|sym| !rules.contains_key(sym))
+        .chain(&look)
+        .cloned()
+        .collect();
+    new.symbols = rules.keys().chain(&new.terminals).cloned().collect();
+    new.basis = Position::new(start.clone(), rules[start].prods[0].clone(), 0, look);
+    new.prod_precs = (grammar.prod_precs.iter())
+        .flat_map(|((rule, prod), term)| {
+            let prods = origins.get(rule).into_iter().flatten().enumerate();
+            prods
+                .filter(move |(_, origin)| {
+                    matches!(origin, Origin::Derived(d) if &d.rule == rule && d.prod == *prod)
+                })
+                .map(move |(i, _)| ((rule.clone(), i), term.clone()))
+        })
+        .collect();
+    new.helpers = Map::new();
+    new.rules = rules;
+    let old = grammar.rules.clone();
+    (new, Remap { old, origins })
+}
+
+/// Removes the nonproductive rules, the productions using them, and then the unreachable rules.
+/// The grammar is kept if its start rule is nonproductive.
+#[must_use]
+pub fn remove_useless<T>(grammar: &Grammar<T>) -> (Grammar<T>, Remap<T>)
+where
+    T: Clone + Ord + fmt::Debug,
+{
+    let start = &grammar.basis.rule;
+    let productive = grammar.productive();
+    let mut work = work(grammar);
+    if productive.contains(start) {
+        work.retain(|name, _| productive.contains(name));
+        for prods in work.values_mut() {
+            prods.retain(|(prod, _)| {
+                (prod.iter()).all(|sym| grammar.is_terminal(sym) || productive.contains(sym))
+            });
+        }
+        let mut reachable = Set::from([start.clone()]);
+        let mut pending = vec![start.clone()];
+        while let Some(name) = pending.pop() {
+            for sym in work[&name].iter().flat_map(|(prod, _)| prod) {
+                if work.contains_key(sym) && reachable.insert(sym.clone()) {
+                    pending.push(sym.clone());
+                }
+            }
+        }
+        work.retain(|name, _| reachable.contains(name));
+    }
+    finish(grammar, work)
+}
+
+/// Replaces the rules used only once, outside of themselves, by their productions. The start rule
+/// and the entry points are kept.
+#[must_use]
+pub fn inline_single_use<T>(grammar: &Grammar<T>) -> (Grammar<T>, Remap<T>)
+where
+    T: Clone + Ord + fmt::Debug,
+{
+    let start = &grammar.basis.rule;
+    let entries: Set<_> = grammar.entries.iter().map(|(entry, _)| entry).collect();
+    let mut work = work(grammar);
+    loop {
+        let mut uses: Map<&T, Vec<(&T, usize, usize)>> = Map::new();
+        for (name, prods) in &work {
+            for (p, (prod, _)) in prods.iter().enumerate() {
+                for (i, sym) in prod.iter().enumerate() {
+                    if work.contains_key(sym) {
+                        uses.entry(sym).or_default().push((name, p, i));
+                    }
+                }
+            }
+        }
+        let found = uses.into_iter().find(|(sym, used)| {
+            let [(user, _, _)] = used[..] else {
+                return false;
+            };
+            // the start rule can't get more productions
+            *sym != start
+                && !entries.contains(sym)
+                && user != *sym
+                && (user != start || work[*sym].len() == 1)
+        });
+        let Some((name, used)) = found else {
+            break;
+        };
+        let (user, p, i) = used[0];
+        let (name, user) = (name.clone(), user.clone());
+        let inlined = work.remove(&name).expect("a rule of the grammar");
+        let prods = work.get_mut(&user).expect("a rule of the grammar");
+        let (prod, origin) = prods.remove(p);
+        for (j, (inner, inner_origin)) in inlined.into_iter().enumerate() {
+            let mut outer = derivation(&origin).clone();
+            outer.inline(i, derivation(&inner_origin).clone(), &grammar.rules);
+            let syms = prod[..i].iter().chain(&inner).chain(&prod[i + 1..]);
+            prods.insert(p + j, (syms.cloned().collect(), Origin::Derived(outer)));
+        }
+    }
+    finish(grammar, work)
+}
+
+/// Replaces each unit production `A -> B` by the productions of `B`, leaving out the cycles. The
+/// start rule is kept.
+#[must_use]
+pub fn remove_units<T>(grammar: &Grammar<T>) -> (Grammar<T>, Remap<T>)
+where
+    T: Clone + Ord + fmt::Debug,
+{
+    let old = work(grammar);
+    let mut work = Work::new();
+    for (name, prods) in &old {
+        let mut new = Vec::new();
+        for prod in prods {
+            if name == &grammar.basis.rule {
+                new.push(prod.clone());
+            } else {
+                let mut seen = Set::from([name.clone()]);
+                expand(&old, prod.clone(), &mut seen, &mut new, &grammar.rules);
+            }
+        }
+        work.insert(name.clone(), new);
+    }
+    finish(grammar, work)
+}
+
+fn expand<T>(
+    old: &Work<T>,
+    (prod, origin): (Vec<T>, Origin<T>),
+    seen: &mut Set<T>,
+    new: &mut Vec<(Vec<T>, Origin<T>)>,
+    rules: &RuleMap<T>,
+) where
+    T: Clone + Ord + fmt::Debug,
+{
+    match &prod[..] {
+        [unit] if old.contains_key(unit) => {
+            if !seen.insert(unit.clone()) {
+                return;
+            }
+            for (inner, inner_origin) in &old[unit] {
+                let mut outer = derivation(&origin).clone();
+                outer.inline(0, derivation(inner_origin).clone(), rules);
+                let prod = (inner.clone(), Origin::Derived(outer));
+                expand(old, prod, seen, new, rules);
+            }
+            seen.remove(unit);
+        }
+        _ if new.iter().any(|(other, _)| other == &prod) => {}
+        _ => new.push((prod, origin)),
+    }
+}
+
+/// Factors the longest common prefix of the productions of a rule starting with the same symbol,
+/// `A -> α β | α γ` into `A -> α A'` and `A' -> β | γ`
+#[must_use]
+pub fn left_factor<T>(grammar: &Grammar<T>) -> (Grammar<T>, HelperRemap<T>)
+where
+    T: Clone + Ord + fmt::Debug + Fresh,
+{
+    let mut work = work(grammar);
+    let mut used = grammar.symbols.clone();
+    let mut helpers = Set::new();
+    let mut pending: VecDeque<_> = work.keys().cloned().collect();
+    while let Some(name) = pending.pop_front() {
+        let prods = &work[&name];
+        let group = prods.iter().find_map(|(prod, _)| {
+            let first = prod.first()?;
+            let group: Vec<_> = (0..prods.len())
+                .filter(|&i| prods[i].0.first() == Some(first))
+                .collect();
+            (group.len() > 1).then_some(group)
+        });
+        let Some(group) = group else {
+            continue;
+        };
+        let len = (1..)
+            .take_while(|&len| {
+                let prefix = prods[group[0]].0.get(..len);
+                prefix.is_some() && group.iter().all(|&i| prods[i].0.get(..len) == prefix)
+            })
+            .last()
+            .unwrap_or(1);
+        let helper = T::fresh(&name, &used);
+        used.insert(helper.clone());
+
+        let prods = work.get_mut(&name).expect("a rule being factored");
+        let mut members: Vec<_> = group.iter().rev().map(|&i| prods.remove(i)).collect();
+        members.reverse();
+        let mut prefix = members[0].0[..len].to_vec();
+        prefix.push(helper.clone());
+        let origin = if helpers.contains(&name) {
+            Origin::Infix
+        } else {
+            Origin::Prefix
+        };
+        prods.insert(group[0], (prefix, origin));
+        let suffixes = members.into_iter().map(|(prod, origin)| {
+            let origin = match origin {
+                Origin::Derived(derivation) => Origin::Suffix(derivation),
+                suffix => suffix,
+            };
+            (prod[len..].to_vec(), origin)
+        });
+        work.insert(helper.clone(), suffixes.collect());
+        helpers.insert(helper.clone());
+        pending.extend([name, helper]);
+    }
+    let (new, remap) = finish(grammar, work);
+    (new, HelperRemap(remap))
+}
+
+/// Rules appearing first in the derivations of `name`
+fn left_corners<T>(work: &Work<T>, name: &T) -> Set<T>
+where
+    T: Clone + Ord,
+{
+    let mut corners = Set::new();
+    let mut pending = vec![name];
+    while let Some(name) = pending.pop() {
+        for (prod, _) in &work[name] {
+            if let Some(first) = prod.first().filter(|first| work.contains_key(*first)) {
+                if corners.insert(first.clone()) {
+                    pending.push(first);
+                }
+            }
+        }
+    }
+    corners
+}
+
+/// Eliminates the left recursion, making `A -> A α | β` into `A -> β A'` and `A' -> α A' | ε`.
+/// The indirect one is made direct first, by replacing the rule starting a production with its
+/// productions. Cycles and empty productions may leave some left recursion.
+#[must_use]
+pub fn remove_left_recursion<T>(grammar: &Grammar<T>) -> (Grammar<T>, HelperRemap<T>)
+where
+    T: Clone + Ord + fmt::Debug + Fresh,
+{
+    let mut work = work(grammar);
+    let mut used = grammar.symbols.clone();
+    let order: Vec<_> = (work.keys())
+        .filter(|name| *name != &grammar.basis.rule)
+        .cloned()
+        .collect();
+    for (i, name) in order.iter().enumerate() {
+        for prev in &order[..i] {
+            if !left_corners(&work, prev).contains(name) {
+                continue;
+            }
+            let prods = std::mem::take(work.get_mut(name).expect("a rule of the grammar"));
+            let mut new = Vec::new();
+            for (prod, origin) in prods {
+                if prod.first() != Some(prev) {
+                    new.push((prod, origin));
+                    continue;
+                }
+                for (inner, inner_origin) in &work[prev] {
+                    let mut outer = derivation(&origin).clone();
+                    outer.inline(0, derivation(inner_origin).clone(), &grammar.rules);
+                    let syms = inner.iter().chain(&prod[1..]).cloned().collect();
+                    new.push((syms, Origin::Derived(outer)));
+                }
+            }
+            work.insert(name.clone(), new);
+        }
+
+        let (recursive, base): (Vec<_>, Vec<_>) =
+            (work[name].iter().cloned()).partition(|(prod, _)| prod.first() == Some(name));
+        if recursive.is_empty() || base.is_empty() {
+            continue;
+        }
+        let tail = T::fresh(name, &used);
+        used.insert(tail.clone());
+        let base = base.into_iter().map(|(mut prod, origin)| {
+            let mut derivation = derivation(&origin).clone();
+            derivation.tail = Some(tail.clone());
+            prod.push(tail.clone());
+            (prod, Origin::Derived(derivation))
+        });
+        work.insert(name.clone(), base.collect());
+        let steps = recursive.into_iter().map(|(mut prod, origin)| {
+            prod.remove(0);
+            prod.push(tail.clone());
+            (prod, Origin::Tail(derivation(&origin).clone()))
+        });
+        let steps = steps.chain(iter::once((Vec::new(), Origin::End))).collect();
+        work.insert(tail, steps);
+    }
+    let (new, remap) = finish(grammar, work);
+    (new, HelperRemap(remap))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        inline_single_use, left_factor, remove_left_recursion, remove_units, remove_useless,
+    };
+    use crate::{grammar_map, reductor, Grammar, Lalr, ListItem, Parser, ReductMap, Token};
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    enum Value {
+        N(i64),
+        List(Vec<Value>),
+    }
+
+    impl Value {
+        fn n(&self) -> i64 {
+            match self {
+                Self::N(n) => *n,
+                Self::List(_) => panic!("not a number"),
+            }
+        }
+    }
+
+    impl ListItem for Value {
+        fn from_list(items: Vec<Self>) -> Self {
+            Self::List(items)
+        }
+
+        fn into_list(self) -> Vec<Self> {
+            match self {
+                Self::List(items) => items,
+                n @ Self::N(_) => vec![n],
+            }
+        }
+    }
+
+    type Item = Token<Value, &'static str>;
+
+    fn n(f: fn(&[Item]) -> i64) -> crate::ReductFn<'static, Value, &'static str> {
+        reductor(move |t: Vec<Item>| Value::N(f(&t)))
+    }
+
+    /// Parses every input with the old and the rewritten grammar, getting the same values
+    fn check(
+        old: Grammar<&'static str>,
+        new: Grammar<&'static str>,
+        adapt: impl Fn(
+            ReductMap<'static, Value, &'static str>,
+        ) -> ReductMap<'static, Value, &'static str>,
+        reductors: fn() -> ReductMap<'static, Value, &'static str>,
+        inputs: &[&'static str],
+    ) {
+        assert_eq!(new.validate(), []);
+        let (old, new) = (Lalr::new(old), Lalr::new(new));
+        assert_eq!(new.tables().conflicts().count(), 0);
+        for input in inputs {
+            let tokens = || {
+                input.split(' ').map(|word| match word.parse() {
+                    Ok(n) => Token::new(Value::N(n), "n"),
+                    Err(_) => Token::new(Value::N(0), word),
+                })
+            };
+            let expected = old.parse(tokens(), reductors()).unwrap();
+            assert_eq!(new.parse(tokens(), adapt(reductors())), Ok(expected));
+        }
+    }
+
+    #[test]
+    fn useless() {
+        let grammar = || {
+            let rules = grammar_map! {
+                "S" -> "E",
+                "E" -> "E" "+" "n" | "X" | "n",
+                "X" -> "x" "X",
+                "U" -> "u"
+            };
+            Grammar::new("S", rules, "$").unwrap()
+        };
+        let (new, remap) = remove_useless(&grammar());
+        assert_eq!(new.rules.keys().collect::<Vec<_>>(), [&"E", &"S"]);
+        assert_eq!(new.rules["E"].prods[1].0, ["n"]);
+        assert!(!new.terminals.contains("u"));
+        let reductors = || {
+            ReductMap::from([
+                ("S", vec![n(|t| t[0].item.n())]),
+                (
+                    "E",
+                    vec![
+                        n(|t| t[0].item.n() + t[2].item.n()),
+                        n(|_| unreachable!()),
+                        n(|t| t[0].item.n()),
+                    ],
+                ),
+                ("X", vec![n(|_| unreachable!())]),
+                ("U", vec![n(|_| unreachable!())]),
+            ])
+        };
+        check(
+            grammar(),
+            new,
+            |r| remap.adapt(r),
+            reductors,
+            &["1 + 2 + 3", "4"],
+        );
+    }
+
+    #[test]
+    fn inline() {
+        let grammar = || {
+            let rules = grammar_map! {
+                "S" -> "E",
+                "E" -> "E" "+" "Pair" | "n",
+                "Pair" -> "(" "n" "," "n" ")" | "[" "n" "]"
+            };
+            Grammar::new("S", rules, "$").unwrap()
+        };
+        let (new, remap) = inline_single_use(&grammar());
+        assert!(!new.rules.contains_key("Pair"));
+        assert_eq!(new.rules["E"].prods.len(), 3);
+        assert_eq!(new.rules["E"].prods[1].0, ["E", "+", "[", "n", "]"]);
+        let reductors = || {
+            ReductMap::from([
+                ("S", vec![n(|t| t[0].item.n())]),
+                (
+                    "E",
+                    vec![n(|t| t[0].item.n() + t[2].item.n()), n(|t| t[0].item.n())],
+                ),
+                (
+                    "Pair",
+                    vec![n(|t| t[1].item.n() * t[3].item.n()), n(|t| -t[1].item.n())],
+                ),
+            ])
+        };
+        check(
+            grammar(),
+            new,
+            |r| remap.adapt(r),
+            reductors,
+            &["1 + ( 2 , 3 ) + [ 4 ]"],
+        );
+    }
+
+    #[test]
+    fn units() {
+        let grammar = || {
+            let rules = grammar_map! {
+                "S" -> "E",
+                "E" -> "E" "+" "T" | "T",
+                "T" -> "T" "*" "F" | "F",
+                "F" -> "n" | "(" "E" ")"
+            };
+            Grammar::new("S", rules, "$").unwrap()
+        };
+        let (new, remap) = remove_units(&grammar());
+        for rule in new.rules.values().filter(|rule| rule.name != "S") {
+            assert!(rule
+                .prods
+                .iter()
+                .all(|prod| prod.0.len() > 1 || prod.0 == ["n"]));
+        }
+        assert_eq!(new.rules["E"].prods.len(), 4);
+        let reductors = || {
+            ReductMap::from([
+                ("S", vec![n(|t| t[0].item.n())]),
+                (
+                    "E",
+                    vec![n(|t| t[0].item.n() + t[2].item.n()), n(|t| t[0].item.n())],
+                ),
+                (
+                    "T",
+                    vec![n(|t| t[0].item.n() * t[2].item.n()), n(|t| t[0].item.n())],
+                ),
+                ("F", vec![n(|t| t[0].item.n()), n(|t| t[1].item.n())]),
+            ])
+        };
+        let inputs = ["1 + 2 * 3", "( 1 + 2 ) * 3", "7"];
+        check(grammar(), new, |r| remap.adapt(r), reductors, &inputs);
+    }
+
+    #[test]
+    fn plain_values() {
+        let grammar = Grammar::new(
+            "S",
+            grammar_map! {
+                "S" -> "E",
+                "E" -> "E" "+" "T" | "T",
+                "T" -> "n"
+            },
+            "$",
+        )
+        .unwrap();
+        let (new, remap) = remove_units(&grammar);
+        let sum = |t: Vec<Token<i64, &str>>| t[0].item + t[2].item;
+        let first = |t: Vec<Token<i64, &str>>| t[0].item;
+        let reductors = ReductMap::from([
+            ("S", vec![reductor(first)]),
+            ("E", vec![reductor(sum), reductor(first)]),
+            ("T", vec![reductor(first)]),
+        ]);
+        let tokens = "1 + 2 + 3".split(' ').map(|word| match word.parse() {
+            Ok(n) => Token::new(n, "n"),
+            Err(_) => Token::new(0, word),
+        });
+        let value = Lalr::new(new).parse(tokens, remap.adapt(reductors));
+        assert_eq!(value, Ok(6));
+    }
+
+    #[test]
+    fn factoring() {
+        let grammar = || {
+            let rules = grammar_map! {
+                "S" -> "A",
+                "A" -> "x" "y" "z" | "x" "y" "w" "A" | "k" | "x" "q"
+            };
+            Grammar::new("S", rules, "$").unwrap()
+        };
+        let (new, remap) = left_factor(&grammar());
+        assert_eq!(new.rules["A"].prods.len(), 2);
+        assert_eq!(new.rules["A"].prods[0].0, ["x", "A'"]);
+        assert_eq!(new.rules["A'"].prods[0].0, ["y", "A''"]);
+        assert_eq!(new.rules["A''"].prods[1].0, ["w", "A"]);
+        let reductors = || {
+            ReductMap::from([
+                ("S", vec![n(|t| t[0].item.n())]),
+                (
+                    "A",
+                    vec![n(|_| 1), n(|t| t[3].item.n() * 10), n(|_| 2), n(|_| 3)],
+                ),
+            ])
+        };
+        let inputs = ["x y z", "x y w x y w k", "x y w x q", "k"];
+        check(grammar(), new, |r| remap.adapt(r), reductors, &inputs);
+    }
+
+    #[test]
+    fn left_recursion() {
+        let grammar = || {
+            let rules = grammar_map! {
+                "S" -> "E",
+                "E" -> "E" "+" "T" | "E" "-" "T" | "T",
+                "T" -> "T" "*" "n" | "n"
+            };
+            Grammar::new("S", rules, "$").unwrap()
+        };
+        let (new, remap) = remove_left_recursion(&grammar());
+        for rule in new.rules.values() {
+            assert!(rule
+                .prods
+                .iter()
+                .all(|prod| prod.0.first() != Some(&rule.name)));
+        }
+        let reductors = || {
+            ReductMap::from([
+                ("S", vec![n(|t| t[0].item.n())]),
+                (
+                    "E",
+                    vec![
+                        n(|t| t[0].item.n() + t[2].item.n()),
+                        n(|t| t[0].item.n() - t[2].item.n()),
+                        n(|t| t[0].item.n()),
+                    ],
+                ),
+                (
+                    "T",
+                    vec![n(|t| t[0].item.n() * t[2].item.n()), n(|t| t[0].item.n())],
+                ),
+            ])
+        };
+        let inputs = ["1 - 2 - 3", "1 - 2 * 3 * 2 + 4", "5"];
+        check(grammar(), new, |r| remap.adapt(r), reductors, &inputs);
+    }
+
+    #[test]
+    fn indirect_left_recursion() {
+        let grammar = || {
+            let rules = grammar_map! {
+                "S" -> "B",
+                "A" -> "B" "a" | "c",
+                "B" -> "A" "b" | "B" "d" | "e"
+            };
+            Grammar::new("S", rules, "$").unwrap()
+        };
+        let (new, remap) = remove_left_recursion(&grammar());
+        assert_eq!(new.rules["B"].prods[0].0, ["c", "b", "B'"]);
+        assert_eq!(new.rules["B'"].prods[0].0, ["a", "b", "B'"]);
+        // `A` isn't used anymore
+        let (new, useless) = remove_useless(&new);
+        assert!(!new.rules.contains_key("A"));
+        let reductors = || {
+            ReductMap::from([
+                ("S", vec![n(|t| t[0].item.n())]),
+                ("A", vec![n(|t| t[0].item.n() * 2), n(|_| 1)]),
+                (
+                    "B",
+                    vec![n(|t| t[0].item.n() + 3), n(|t| t[0].item.n() - 1), n(|_| 5)],
+                ),
+            ])
+        };
+        let inputs = ["e a b", "c b d a b", "e d a b d d a b", "e"];
+        let adapt = |r| useless.adapt(remap.adapt(r));
+        check(grammar(), new, adapt, reductors, &inputs);
+    }
+
+    #[test]
+    fn nested_left_recursion() {
+        let grammar = || {
+            let rules = grammar_map! {
+                "S" -> "B",
+                "A" -> "A" "x" | "B" "y" | "z",
+                "B" -> "A" "w" | "v"
+            };
+            Grammar::new("S", rules, "$").unwrap()
+        };
+        let (new, remap) = remove_left_recursion(&grammar());
+        // the tail of `A` stays inside the productions of `B`
+        assert_eq!(new.rules["B"].prods[0].0, ["z", "A'", "w", "B'"]);
+        assert_eq!(new.rules["B'"].prods[0].0, ["y", "A'", "w", "B'"]);
+        let (new, useless) = remove_useless(&new);
+        let reductors = || {
+            ReductMap::from([
+                ("S", vec![n(|t| t[0].item.n())]),
+                (
+                    "A",
+                    vec![n(|t| t[0].item.n() * 2), n(|t| t[0].item.n() + 1), n(|_| 3)],
+                ),
+                ("B", vec![n(|t| t[0].item.n() - 10), n(|_| 7)]),
+            ])
+        };
+        let inputs = ["v y x w y w", "z x x w", "z w y x w", "v"];
+        let adapt = |r| useless.adapt(remap.adapt(r));
+        check(grammar(), new, adapt, reductors, &inputs);
+    }
+
+    #[test]
+    fn chained() {
+        let grammar = || {
+            let rules = grammar_map! {
+                "S" -> "E",
+                "E" -> "E" "+" "n" | "E" "-" "n" | "n"
+            };
+            Grammar::new("S", rules, "$").unwrap()
+        };
+        let reductors = || {
+            ReductMap::from([
+                ("S", vec![n(|t| t[0].item.n())]),
+                (
+                    "E",
+                    vec![
+                        n(|t| t[0].item.n() + t[2].item.n()),
+                        n(|t| t[0].item.n() - t[2].item.n()),
+                        n(|t| t[0].item.n()),
+                    ],
+                ),
+            ])
+        };
+        let inputs = ["1 + 2 - 3", "1 - 2 - 3 + 4 - 5", "7"];
+        let (factored, r1) = left_factor(&grammar());
+        let (new, r2) = remove_left_recursion(&factored);
+        assert_eq!(new.rules["E"].prods[0].0, ["n", "E''"]);
+        let adapt = |r| r2.adapt(r1.adapt(r));
+        check(grammar(), new, adapt, reductors, &inputs);
+
+        let (recursive, r1) = remove_left_recursion(&grammar());
+        let (new, r2) = left_factor(&recursive);
+        let adapt = |r| r2.adapt(r1.adapt(r));
+        check(grammar(), new, adapt, reductors, &inputs);
+    }
+}
@@ -0,0 +1,440 @@
+use std::{fmt, iter, rc::Rc, vec};
+
+use crate::{Actions, Dfa, Error, Meta, Span, Token};
+
+/// A text edit: the `span` of the old text was replaced by `len` bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Edit {
+    pub span: Span,
+    pub len: usize,
+}
+
+impl Edit {
+    #[must_use]
+    pub const fn new(span: Span, len: usize) -> Self {
+        Self { span, len }
+    }
+
+    /// Moves a span after the edit by the text length change. Spans before it are kept.
+    #[must_use]
+    pub const fn relocate(&self, span: Span) -> Span {
+        if span.start < self.span.end {
+            return span;
+        }
+        let (start, end) = (span.start - self.span.end, span.end - self.span.end);
+        let base = self.span.start + self.len;
+        Span::new(base + start, base + end)
+    }
+}
+
+/// Moves the spans inside an item reused after an `Edit`
+pub type RelocateFn<T> = fn(Meta<T>, &Edit) -> Meta<T>;
+
+pub type Tokens<T, M> = vec::IntoIter<Token<Meta<T>, M>>;
+
+/// An entry of a persistent `Dfa` stack, shared by every checkpoint that kept it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame<T, M> {
+    pub state: usize,
+    /// Item pushed with the state, missing at the bottom of the stack
+    pub item: Option<Token<T, M>>,
+    /// Amount of states from the bottom of the stack up to this one
+    pub height: usize,
+    pub below: Option<Rc<Frame<T, M>>>,
+}
+
+impl<T, M> Frame<T, M> {
+    /// Frames from this one down to the bottom of the stack
+    pub fn iter(&self) -> impl Iterator<Item = &Self> {
+        iter::successors(Some(self), |frame| frame.below.as_deref())
+    }
+}
+
+impl<T, M> Drop for Frame<T, M> {
+    // unlinks the frames no longer shared one by one, instead of recursing over the whole stack
+    fn drop(&mut self) {
+        let mut below = self.below.take();
+        while let Some(mut frame) = below.and_then(|rc| Rc::try_unwrap(rc).ok()) {
+            below = frame.below.take();
+        }
+    }
+}
+
+/// `Dfa` configuration before consuming a token
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Checkpoint<T, M> {
+    pub stack: Rc<Frame<T, M>>,
+    /// Amount of bottom states kept untouched since the previous checkpoint
+    pub low: usize,
+}
+
+impl<T, M> Checkpoint<T, M> {
+    fn matches(&self, states: &[usize]) -> bool {
+        self.stack.height == states.len()
+            && self
+                .stack
+                .iter()
+                .map(|f| f.state)
+                .eq(states.iter().rev().copied())
+    }
+}
+
+/// An input parsed by a `Dfa`, that can be reparsed after edits. Only the tokens damaged by the
+/// edit are run again: the parsing resumes from the saved stacks before them and, once the stack
+/// after them matches the old one, the old subtrees over the next unchanged tokens are reused.
+#[derive(Debug, Clone)]
+pub struct Session<T, M> {
+    pub tokens: Vec<Token<Meta<T>, M>>,
+    /// Configuration before each token, and before the input end
+    pub checkpoints: Vec<Checkpoint<Meta<T>, M>>,
+    pub relocate: RelocateFn<T>,
+    /// Amount of tokens whose subtrees were reused by the last parsing
+    pub reused: usize,
+}
+
+impl<T, M> Default for Session<T, M> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, M> Session<T, M> {
+    /// A session that relocates only the outer span of the reused items
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_relocate(|meta, edit| Meta::new(meta.item, edit.relocate(meta.span)))
+    }
+
+    #[must_use]
+    pub const fn with_relocate(relocate: RelocateFn<T>) -> Self {
+        Self {
+            tokens: Vec::new(),
+            checkpoints: Vec::new(),
+            relocate,
+            reused: 0,
+        }
+    }
+}
+
+/// Tokens of a reparse matching the old ones after the edit
+struct Reuse<T, M> {
+    edit: Edit,
+    /// Old checkpoints after the damaged token
+    old: Vec<Checkpoint<Meta<T>, M>>,
+    /// Old token index of `old[0]`
+    base: usize,
+    /// First new token of the matching suffix
+    sync: usize,
+    /// Amount of old tokens minus amount of new tokens
+    offset: isize,
+}
+
+impl<T, M> Session<T, M>
+where
+    T: Clone,
+    M: fmt::Debug + Clone + Ord,
+{
+    /// Parses `tokens` from scratch
+    /// # Errors
+    /// The same of `Dfa::trace`
+    pub fn parse<A: Actions<M>>(
+        &mut self,
+        dfa: &mut Dfa<Meta<T>, M, Tokens<T, M>, A>,
+        tokens: Vec<Token<Meta<T>, M>>,
+    ) -> Result<Meta<T>, Error<M>> {
+        self.checkpoints.clear();
+        self.run(dfa, tokens, 0, None)
+    }
+
+    /// Parses `tokens`, the tokens of the old text after the `edit`, reusing the old parsing.
+    /// # Errors
+    /// The same of `Dfa::trace`
+    pub fn reparse<A: Actions<M>>(
+        &mut self,
+        dfa: &mut Dfa<Meta<T>, M, Tokens<T, M>, A>,
+        edit: Edit,
+        tokens: Vec<Token<Meta<T>, M>>,
+    ) -> Result<Meta<T>, Error<M>> {
+        if self.checkpoints.is_empty() {
+            return self.parse(dfa, tokens);
+        }
+        let same = |a: &Token<Meta<T>, M>, b: &Token<Meta<T>, M>, span: Span| {
+            a.ty == b.ty && a.item.span == span
+        };
+        let prefix = self
+            .tokens
+            .iter()
+            .zip(&tokens)
+            .take_while(|(old, new)| {
+                old.item.span.end < edit.span.start && same(new, old, old.item.span)
+            })
+            .count()
+            .min(self.checkpoints.len().saturating_sub(1));
+        let suffix = self
+            .tokens
+            .iter()
+            .rev()
+            .zip(tokens.iter().rev())
+            .take_while(|(old, new)| {
+                old.item.span.start >= edit.span.end && same(new, old, edit.relocate(old.item.span))
+            })
+            .count()
+            .min(tokens.len() - prefix)
+            .min(self.tokens.len() - prefix);
+
+        let reuse = Reuse {
+            edit,
+            old: self.checkpoints.split_off(prefix + 1),
+            base: prefix + 1,
+            sync: tokens.len() - suffix,
+            offset: self.tokens.len() as isize - tokens.len() as isize,
+        };
+        self.run(dfa, tokens, prefix, Some(reuse))
+    }
+
+    fn run<A: Actions<M>>(
+        &mut self,
+        dfa: &mut Dfa<Meta<T>, M, Tokens<T, M>, A>,
+        tokens: Vec<Token<Meta<T>, M>>,
+        start: usize,
+        reuse: Option<Reuse<T, M>>,
+    ) -> Result<Meta<T>, Error<M>> {
+        dfa.reset();
+        // frames of the last checkpoint, from the bottom of the stack
+        let mut frames = Vec::new();
+        if let Some(checkpoint) = self.checkpoints.last() {
+            frames.extend(iter::successors(Some(checkpoint.stack.clone()), |f| {
+                f.below.clone()
+            }));
+            frames.reverse();
+            dfa.states = frames.iter().map(|f| f.state).collect();
+            dfa.items = frames.iter().filter_map(|f| f.item.clone()).collect();
+            dfa.top = *dfa.states.last().ok_or(Error::MissingPreviousState)?;
+        } else {
+            let bottom = Rc::new(Frame {
+                state: dfa.top,
+                item: None,
+                height: 1,
+                below: None,
+            });
+            frames.push(bottom.clone());
+            self.checkpoints.push(Checkpoint {
+                stack: bottom,
+                low: 0,
+            });
+        }
+        dfa.index = start;
+        let rest = tokens[start..].to_vec();
+        dfa.buffer = rest.into_iter().peekable();
+        self.tokens = tokens;
+        self.reused = 0;
+
+        let mut low = usize::MAX;
+        let res = dfa.trace(|dfa| {
+            low = low.min(dfa.states.len() - 1);
+            if dfa.index < self.checkpoints.len() {
+                return;
+            }
+            // only the frames above the untouched ones are new, and the entry state is never popped
+            frames.truncate(low.max(1));
+            for height in frames.len() + 1..=dfa.states.len() {
+                let frame = Frame {
+                    state: dfa.states[height - 1],
+                    item: height.checked_sub(2).map(|i| dfa.items[i].clone()),
+                    height,
+                    below: frames.last().cloned(),
+                };
+                frames.push(Rc::new(frame));
+            }
+            self.checkpoints.push(Checkpoint {
+                stack: frames[frames.len() - 1].clone(),
+                low,
+            });
+            low = usize::MAX;
+            if let Some(reuse) = &reuse {
+                self.reuse(dfa, &mut frames, reuse);
+            }
+        });
+        let res = res.and_then(|()| dfa.items.pop().ok_or(Error::MissingPreviousState));
+        dfa.reset();
+        res.map(|tok| tok.item)
+    }
+
+    /// Once the stack at a synchronized token matches the old one, pushes the old subtrees over the
+    /// next tokens that didn't touch the stack below it. `frames` are the ones of the last
+    /// checkpoint, from the bottom of the stack.
+    fn reuse<A: Actions<M>>(
+        &mut self,
+        dfa: &mut Dfa<Meta<T>, M, Tokens<T, M>, A>,
+        frames: &mut Vec<Rc<Frame<Meta<T>, M>>>,
+        reuse: &Reuse<T, M>,
+    ) {
+        if dfa.index < reuse.sync {
+            return;
+        }
+        let Some(first) = dfa
+            .index
+            .checked_add_signed(reuse.offset)
+            .and_then(|old| old.checked_sub(reuse.base))
+        else {
+            return;
+        };
+        if !reuse.old.get(first).is_some_and(|c| c.matches(&dfa.states)) {
+            return;
+        }
+        let base = dfa.states.len();
+        let last = reuse.old[first + 1..]
+            .iter()
+            .take_while(|c| c.low >= base)
+            .count();
+        if last == 0 {
+            return;
+        }
+        // old frames above `base` already relocated into `frames`, so each one is moved only once
+        let mut moved: Vec<Rc<Frame<Meta<T>, M>>> = Vec::new();
+        for checkpoint in &reuse.old[first + 1..=first + last] {
+            let fresh: Vec<_> =
+                iter::successors(Some(checkpoint.stack.clone()), |f| f.below.clone())
+                    .take_while(|f| {
+                        f.height > base
+                            && !moved
+                                .get(f.height - base - 1)
+                                .is_some_and(|old| Rc::ptr_eq(old, f))
+                    })
+                    .collect();
+            let kept = fresh
+                .last()
+                .map_or(checkpoint.stack.height, |f| f.height - 1);
+            moved.truncate(kept - base);
+            frames.truncate(kept);
+            for old in fresh.into_iter().rev() {
+                let item = old.item.as_ref().map(|tok| {
+                    Token::new(
+                        (self.relocate)(tok.item.clone(), &reuse.edit),
+                        tok.ty.clone(),
+                    )
+                });
+                let frame = Frame {
+                    state: old.state,
+                    item,
+                    height: old.height,
+                    below: frames.last().cloned(),
+                };
+                frames.push(Rc::new(frame));
+                moved.push(old);
+            }
+            self.checkpoints.push(Checkpoint {
+                stack: frames[frames.len() - 1].clone(),
+                low: checkpoint.low,
+            });
+        }
+        dfa.states.truncate(base);
+        dfa.items.truncate(base - 1);
+        for frame in &frames[base..] {
+            dfa.states.push(frame.state);
+            dfa.items.extend(frame.item.clone());
+        }
+        dfa.top = *dfa.states.last().unwrap_or(&0);
+        for _ in 0..last {
+            dfa.skip();
+        }
+        self.reused += last;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{grammars_tests, Dfa, Edit, Lalr, Meta, Parser, Session, Span, Token, Tokens};
+
+    type Tok = Token<Meta<String>, &'static str>;
+
+    fn lex(src: &str) -> Vec<Tok> {
+        let mut tokens = Vec::new();
+        let mut start = 0;
+        for word in src.split(' ') {
+            let ty = match word {
+                "=" => "=",
+                ";" => ";",
+                _ if word.chars().all(|c| c.is_ascii_digit()) => "n",
+                _ => "id",
+            };
+            let span = Span::new(start, start + word.len());
+            tokens.push(Token::new(Meta::new(word.to_string(), span), ty));
+            start += word.len() + 1;
+        }
+        tokens
+    }
+
+    fn join(toks: Vec<Tok>) -> Meta<String> {
+        let span = Span::new(toks[0].item.span.start, toks[toks.len() - 1].item.span.end);
+        let words: Vec<_> = toks.into_iter().map(|t| t.item.item).collect();
+        Meta::new(format!("({})", words.join(" ")), span)
+    }
+
+    fn dfa(
+        lalr: &Lalr<&'static str>,
+    ) -> Dfa<'static, Meta<String>, &'static str, Tokens<String, &'static str>> {
+        let maps = Dfa::<_, _, Tokens<_, _>>::transparent(lalr.tables(), join);
+        lalr.dfa(&"S", Vec::new().into_iter(), maps)
+    }
+
+    /// Replaces `old` (the first occurrence after `from`) by `new`
+    fn edit(src: &str, from: usize, old: &str, new: &str) -> (String, Edit) {
+        let start = from + src[from..].find(old).unwrap();
+        let edit = Edit::new(Span::new(start, start + old.len()), new.len());
+        (
+            format!("{}{new}{}", &src[..start], &src[start + old.len()..]),
+            edit,
+        )
+    }
+
+    #[test]
+    fn reparse() {
+        let lalr = Lalr::new(grammars_tests::statements());
+        let mut fresh_dfa = dfa(&lalr);
+        let mut dfa = dfa(&lalr);
+        let mut fresh = |src: &str| Session::new().parse(&mut fresh_dfa, lex(src)).unwrap();
+
+        let mut src = "a = 1 ; b = c ; d = 2 ; e = f ; g = 3 ;".to_string();
+        let mut session = Session::new();
+        assert_eq!(session.parse(&mut dfa, lex(&src)).unwrap(), fresh(&src));
+        assert_eq!(session.reused, 0);
+
+        let edits = [
+            (0, "c", "42"),
+            (0, "a", "x = 1 ; a"),
+            (10, "2 ;", "y ; h = 2 ;"),
+        ];
+        for (from, old, new) in edits {
+            let (new_src, change) = edit(&src, from, old, new);
+            src = new_src;
+            let tree = session.reparse(&mut dfa, change, lex(&src)).unwrap();
+            assert_eq!(tree, fresh(&src), "{src}");
+            assert!(session.reused > 0, "{src}");
+        }
+
+        // the checkpoints share their stacks instead of copying them
+        let bottom = session.checkpoints[0].stack.iter().last().unwrap();
+        for checkpoint in &session.checkpoints {
+            assert!(std::ptr::eq(
+                checkpoint.stack.iter().last().unwrap(),
+                bottom
+            ));
+        }
+    }
+
+    #[test]
+    fn broken() {
+        let lalr = Lalr::new(grammars_tests::statements());
+        let mut dfa = dfa(&lalr);
+        let src = "a = 1 ; b = c ; d = 2 ;";
+        let mut session = Session::new();
+        session.parse(&mut dfa, lex(src)).unwrap();
+
+        let (src, change) = edit(src, 0, "c", "= c");
+        assert!(session.reparse(&mut dfa, change, lex(&src)).is_err());
+        let (src, change) = edit(&src, 0, "= c", "c");
+        let tree = session.reparse(&mut dfa, change, lex(&src)).unwrap();
+        assert_eq!(tree, Session::new().parse(&mut dfa, lex(&src)).unwrap());
+    }
+}
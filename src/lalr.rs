@@ -1,4 +1,4 @@
-use crate::{transitive, Action, Map, Parser, Position, Set, State, Tabler};
+use crate::{transitive, Action, Map, Parser, Position, State, Tabler};
 use std::fmt::Debug;
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
@@ -55,7 +55,7 @@ where
     T: PartialEq + Ord + Clone + Debug,
 {
     pub fn proc_closures_first_row(&mut self) {
-        let start = self.prop_closure(State::from([self.table.basis_pos()]));
+        let start = self.prop_closure(self.table.basis_state());
         self.table.kernels.insert(State::new(), 0);
         self.raws.insert(State::new(), start.clone());
         self.table.states.push(start);
@@ -91,6 +91,7 @@ where
             }
             self.table.actions.push(map);
         }
+        self.table.solve_conflicts();
     }
 
     #[must_use]
@@ -101,10 +102,7 @@ where
                 if self.table.grammar.is_terminal(&top) {
                     continue;
                 }
-                let look = pos.next().map_or_else(
-                    || pos.look.clone(),
-                    |locus| self.table.first_of(&Set::from([locus])),
-                );
+                let look = self.table.first_seq(pos.rest(), &pos.look);
                 for prod in self.table.grammar.rules[&top].prods() {
                     new_state.insert(Position::new(top.clone(), prod.clone(), 0, look.clone()));
                 }
@@ -266,7 +264,10 @@ where
 
 #[cfg(test)]
 mod tests {
-    use crate::{grammars_tests, to_tokens, Lalr, Parser};
+    use crate::{
+        grammars_tests, reductor, to_tokens, Clr, Dfa, Lalr, LalrDp, Pager, Parser, ReductMap, Slr,
+        Token,
+    };
 
     #[test]
     pub fn dragon_book() {
@@ -331,4 +332,136 @@ mod tests {
             assert!(lalr.validate(to_tokens(input.iter().cloned())));
         }
     }
+
+    #[test]
+    pub fn declarations() {
+        let lalr = Lalr::new(grammars_tests::declarations());
+        assert_eq!(0, lalr.tables().conflicts().count());
+
+        for input in grammars_tests::DECLARATIONS_INPUTS {
+            assert!(lalr.validate(to_tokens(input.iter().cloned())));
+        }
+    }
+
+    #[test]
+    pub fn empty_reductions() {
+        fn sum(toks: Vec<Token<usize, &'static str>>) -> usize {
+            toks.iter().map(|t| t.item).sum()
+        }
+        let lalr = Lalr::new(grammars_tests::declarations());
+        for input in grammars_tests::DECLARATIONS_INPUTS {
+            let reductors = Dfa::<_, _, std::vec::IntoIter<_>>::transparent(lalr.tables(), sum);
+            let tokens = input.iter().map(|&t| Token::new(1, t));
+            assert_eq!(lalr.parse(tokens, reductors), Ok(input.len()));
+        }
+    }
+
+    #[test]
+    pub fn arithmetic() {
+        let lalr = Lalr::new(grammars_tests::arithmetic());
+        assert_eq!(0, lalr.tables().conflicts().count());
+
+        for input in grammars_tests::ARITHMETIC_INPUTS {
+            assert!(lalr.validate(to_tokens(input.iter().cloned())));
+        }
+
+        for input in grammars_tests::NON_ASSOC_ARITHMETIC_INPUTS {
+            assert!(!lalr.validate(to_tokens(input.iter().cloned())));
+        }
+    }
+
+    #[test]
+    pub fn precedence() {
+        type Tok = Token<i64, &'static str>;
+        let lalr = Lalr::new(grammars_tests::arithmetic());
+        let reductors = || {
+            let e: [fn(Vec<Tok>) -> i64; 9] = [
+                |t| i64::from(t[0].item == t[2].item),
+                |t| t[0].item + t[2].item,
+                |t| t[0].item - t[2].item,
+                |t| t[0].item * t[2].item,
+                |t| t[0].item / t[2].item,
+                |t| t[0].item.pow(t[2].item.try_into().unwrap()),
+                |t| -t[1].item,
+                |t| t[1].item,
+                |t| t[0].item,
+            ];
+            ReductMap::from([
+                ("S", vec![reductor(|t: Vec<Tok>| t[0].item)]),
+                ("E", e.map(reductor).into()),
+            ])
+        };
+        let eval = |input: &[(&'static str, i64)]| {
+            let tokens = input.iter().map(|&(ty, n)| Token::new(n, ty));
+            lalr.parse(tokens, reductors())
+        };
+        // 1 - 2 - 3
+        assert_eq!(
+            eval(&[("n", 1), ("-", 0), ("n", 2), ("-", 0), ("n", 3)]),
+            Ok(-4)
+        );
+        // 1 + 2 * 3
+        assert_eq!(
+            eval(&[("n", 1), ("+", 0), ("n", 2), ("*", 0), ("n", 3)]),
+            Ok(7)
+        );
+        // 2 ^ 3 ^ 2
+        assert_eq!(
+            eval(&[("n", 2), ("^", 0), ("n", 3), ("^", 0), ("n", 2)]),
+            Ok(512)
+        );
+        // - 2 ^ 2
+        assert_eq!(eval(&[("-", 0), ("n", 2), ("^", 0), ("n", 2)]), Ok(4));
+        // 1 + 1 == 2
+        assert_eq!(
+            eval(&[("n", 1), ("+", 0), ("n", 1), ("==", 0), ("n", 2)]),
+            Ok(1)
+        );
+    }
+
+    fn entries<P: Parser<&'static str>>() {
+        let parser = P::new(grammars_tests::entries());
+        assert_eq!(parser.tables().conflicts().count(), 0);
+        assert_eq!(parser.tables().start(), "Module");
+        let inputs = [
+            ("Module", &["let", "id", ":", "id", "=", "n", ";"][..]),
+            ("Expr", &["n", "+", "n"]),
+            ("Type", &["id", "[", "]", "[", "]"]),
+        ];
+        for (entry, input) in inputs {
+            for (other, _) in inputs {
+                let tokens = to_tokens(input.iter().copied());
+                let mut dfa = parser.simple_dfa(tokens).with_entry(parser.entry(&other));
+                assert_eq!(dfa.start().is_ok(), entry == other, "{entry} from {other}");
+            }
+        }
+        assert!(parser.tables().entry(&"Decl").is_none());
+        assert!(parser.validate(to_tokens(inputs[0].1.iter().copied())));
+    }
+
+    #[test]
+    fn entry_points() {
+        entries::<Lalr<_>>();
+        entries::<Slr<_>>();
+        entries::<Clr<_>>();
+        entries::<Pager<_>>();
+        entries::<LalrDp<_>>();
+
+        // the entry point value is the parsing result
+        let lalr = Lalr::new(grammars_tests::entries());
+        let reductors = || {
+            ReductMap::from([(
+                "Expr",
+                vec![
+                    reductor(|t: Vec<Token<i64, &str>>| t[0].item + t[2].item),
+                    reductor(|t| t[0].item),
+                ],
+            )])
+        };
+        let input = [("n", 1), ("+", 0), ("n", 2), ("+", 0), ("n", 3)];
+        let tokens = input.into_iter().map(|(ty, n)| Token::new(n, ty));
+        let mut dfa = lalr.dfa(&"Expr", tokens.clone(), reductors());
+        assert_eq!(dfa.start().map(|()| dfa.items.pop().unwrap().item), Ok(6));
+        assert!(lalr.dfa(&"Module", tokens, reductors()).start().is_err());
+    }
 }
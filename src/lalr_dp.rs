@@ -0,0 +1,415 @@
+use crate::{transitive, Action, Map, Parser, Position, Set, State, Tabler};
+use std::fmt::Debug;
+
+/// LALR(1) parser built with the DeRemer–Pennello lookahead computation: the LR(0) automaton is
+/// generated first, and the lookaheads come from the `reads`, `includes` and `lookback` relations.
+/// Generates the same tables of `Lalr`, but faster.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct LalrDp<T>
+where
+    T: PartialEq + Ord + Clone + Debug,
+{
+    pub table: Tabler<T>,
+    /// Describes the "raw states" (states without lookahead symbol) in `table.kernels`, using it
+    /// raw kernel as key and value being its final kernel
+    pub raws: Map<State<T>, State<T>>,
+    /// LR(0) transitions of each state
+    pub gotos: Vec<Map<T, usize>>,
+}
+
+impl<T> Parser<T> for LalrDp<T>
+where
+    T: PartialEq + Ord + Clone + Debug,
+{
+    fn with_table(table: Tabler<T>) -> Self {
+        let mut parser = Self::uninit(table);
+        parser.proc_actions();
+        parser
+    }
+
+    fn uninit(table: Tabler<T>) -> Self {
+        Self {
+            table,
+            raws: Map::new(),
+            gotos: Vec::new(),
+        }
+    }
+
+    fn tables(&self) -> &Tabler<T> {
+        &self.table
+    }
+
+    fn tables_mut(&mut self) -> &mut Tabler<T> {
+        &mut self.table
+    }
+
+    fn final_kernel<'a>(&'a self, kernel: &'a State<T>) -> Option<&'a State<T>> {
+        let raw = Self::without_look(kernel);
+        self.raws.get(&raw)
+    }
+}
+
+/// Nonterminal transitions, in the `(state, symbol)` form
+type Transitions<T> = Map<(usize, T), usize>;
+
+impl<T> LalrDp<T>
+where
+    T: PartialEq + Ord + Clone + Debug,
+{
+    #[must_use]
+    pub fn without_look(state: &State<T>) -> State<T> {
+        state.iter().map(Position::no_look).collect()
+    }
+
+    #[must_use]
+    pub fn closure(&self, state: State<T>) -> State<T> {
+        let mut new_state = State::new();
+        for pos in &state {
+            if let Some(top) = pos.top() {
+                if self.table.grammar.is_terminal(&top) {
+                    continue;
+                }
+                for prod in self.table.grammar.rules[&top].prods() {
+                    new_state.insert(Position::new(top.clone(), prod.clone(), 0, Set::new()));
+                }
+            }
+        }
+        new_state.extend(state);
+        new_state
+    }
+
+    #[must_use]
+    pub fn prop_closure(&self, seed: State<T>) -> State<T> {
+        transitive(seed, |s| self.closure(s))
+    }
+
+    /// Generates the LR(0) automaton in `table.states` and `gotos`
+    pub fn proc_lr0(&mut self) {
+        let basis = Self::without_look(&self.table.basis_state());
+        let mut kernels = Map::from([(basis.clone(), 0)]);
+        self.table.states = vec![self.prop_closure(basis)];
+        self.gotos.clear();
+        let syms: Vec<_> = self.table.grammar.symbols().collect();
+        let mut idx = 0;
+        while idx < self.table.states.len() {
+            let mut gotos = Map::new();
+            for sym in &syms {
+                let kernel = Tabler::sym_filter(&self.table.states[idx], sym);
+                if kernel.is_empty() {
+                    continue;
+                }
+                let next = kernels.get(&kernel).copied().unwrap_or_else(|| {
+                    let closure = self.prop_closure(kernel.clone());
+                    self.table.states.push(closure);
+                    kernels.insert(kernel, self.table.states.len() - 1);
+                    self.table.states.len() - 1
+                });
+                gotos.insert(sym.clone(), next);
+            }
+            self.gotos.push(gotos);
+            idx += 1;
+        }
+    }
+
+    /// Indexes the nonterminal transitions. The start rule has a virtual transition from the
+    /// initial state, reading the eof.
+    fn transitions(&self) -> Transitions<T> {
+        let mut trans = Transitions::new();
+        let start = (0, self.table.basis_pos().rule);
+        for (state, gotos) in self.gotos.iter().enumerate() {
+            for sym in gotos.keys() {
+                if !self.table.grammar.is_terminal(sym) {
+                    let id = trans.len();
+                    trans.insert((state, sym.clone()), id);
+                }
+            }
+        }
+        let id = trans.len();
+        trans.entry(start).or_insert(id);
+        trans
+    }
+
+    /// Computes the lookaheads of each position, by state. Follows DeRemer and
+    /// Pennello's "Efficient Computation of LALR(1) Look-Ahead Sets".
+    #[must_use]
+    pub fn lookaheads(&self) -> Map<(usize, Position<T>), Set<T>> {
+        let trans = self.transitions();
+        let mut ordered: Vec<_> = trans.iter().collect();
+        ordered.sort_by_key(|(_, id)| **id);
+        let start = (0, self.table.basis_pos().rule);
+        let eof = self.table.eof();
+
+        // DR(p, A): terminals shifted after the transition
+        // (p, A) reads (r, C): r = goto(p, A) and C is nullable
+        let mut direct = Vec::with_capacity(trans.len());
+        let mut reads = Vec::with_capacity(trans.len());
+        for ((state, sym), _) in &ordered {
+            let mut dr = Set::new();
+            let mut rd = Vec::new();
+            if (*state, (*sym).clone()) == start {
+                dr.insert(eof.clone());
+            }
+            if let Some(&to) = self.gotos[*state].get(sym) {
+                for next in self.gotos[to].keys() {
+                    if self.table.grammar.is_terminal(next) {
+                        dr.insert(next.clone());
+                    } else if self.table.is_nullable(next) {
+                        rd.push(trans[&(to, next.clone())]);
+                    }
+                }
+            }
+            direct.push(dr);
+            reads.push(rd);
+        }
+        let read = digraph(&reads, direct);
+
+        // (p, B) includes (p', A): A = β B γ, γ is nullable and p' reads β reaching p
+        // lookback: A = β . γ in goto(p', β) takes Follow(p', A)
+        let mut includes = vec![Vec::new(); trans.len()];
+        let mut lookback: Vec<(usize, Position<T>, usize)> = Vec::new();
+        for ((from, rule), &id) in &ordered {
+            for prod in self.table.grammar.rules[rule].prods() {
+                let mut state = *from;
+                for (i, sym) in prod.0.iter().enumerate() {
+                    lookback.push((
+                        state,
+                        Position::new(rule.clone(), prod.clone(), i, Set::new()),
+                        id,
+                    ));
+                    if !self.table.grammar.is_terminal(sym)
+                        && prod.0[i + 1..].iter().all(|s| self.table.is_nullable(s))
+                    {
+                        includes[trans[&(state, sym.clone())]].push(id);
+                    }
+                    state = self.gotos[state][sym];
+                }
+                let pos = Position::new(rule.clone(), prod.clone(), prod.0.len(), Set::new());
+                lookback.push((state, pos, id));
+            }
+        }
+        let follow = digraph(&includes, read);
+
+        let mut looks: Map<_, Set<T>> = Map::new();
+        for (state, pos, id) in lookback {
+            looks
+                .entry((state, pos))
+                .or_default()
+                .extend(follow[id].iter().cloned());
+        }
+        looks
+    }
+
+    fn proc_actions(&mut self) {
+        self.proc_lr0();
+        let looks = self.lookaheads();
+
+        let states: Vec<State<T>> = self
+            .table
+            .states
+            .iter()
+            .enumerate()
+            .map(|(idx, state)| {
+                state
+                    .iter()
+                    .map(|pos| {
+                        let look = looks.get(&(idx, pos.clone())).cloned();
+                        pos.clone().with_look(look.unwrap_or_default())
+                    })
+                    .collect()
+            })
+            .collect();
+
+        self.table.kernels.clear();
+        self.raws.clear();
+        self.table.kernels.insert(State::new(), 0);
+        self.raws.insert(State::new(), State::new());
+        for (idx, state) in states.iter().enumerate().skip(1) {
+            let kernel: State<T> = state.iter().filter(|p| p.point > 0).cloned().collect();
+            self.raws
+                .insert(Self::without_look(&kernel), kernel.clone());
+            self.table.kernels.insert(kernel, idx);
+        }
+        self.table.states = states;
+
+        let start = self.table.basis_pos().rule;
+        for (idx, row) in self.table.states.iter().enumerate() {
+            let mut map: Map<T, Action<T>> = Map::new();
+            for item in row {
+                for (term, act) in self.decision(&start, idx, item) {
+                    if map.contains_key(&term) && map[&term] != act {
+                        *map.get_mut(&term).unwrap() =
+                            Action::Conflict(Box::new(map.get(&term).unwrap().clone()), act.into());
+                    } else {
+                        map.insert(term, act);
+                    }
+                }
+            }
+            self.table.actions.push(map);
+        }
+        self.table.solve_conflicts();
+    }
+
+    #[must_use]
+    fn decision(&self, start: &T, state: usize, pos: &Position<T>) -> Map<T, Action<T>> {
+        pos.top().map_or_else(
+            || {
+                pos.look
+                    .iter()
+                    .map(|l| {
+                        (
+                            l.clone(),
+                            if &pos.rule == start {
+                                Action::Acc
+                            } else {
+                                Action::Reduce(pos.rule.clone(), pos.seq.clone())
+                            },
+                        )
+                    })
+                    .collect()
+            },
+            |locus| {
+                let to = self.gotos[state][&locus];
+                if self.table.grammar.is_terminal(&locus) {
+                    Map::from([(locus, Action::Shift(to))])
+                } else {
+                    Map::from([(locus, Action::Goto(to))])
+                }
+            },
+        )
+    }
+}
+
+/// Computes `F(x) = init(x) ∪ ⋃{ F(y) | x R y }` for every `x`, where `rel[x]` lists the `y`s.
+/// Strongly connected components share the same result.
+fn digraph<T: Ord + Clone>(rel: &[Vec<usize>], init: Vec<Set<T>>) -> Vec<Set<T>> {
+    struct Walk<'a, T> {
+        rel: &'a [Vec<usize>],
+        sets: Vec<Set<T>>,
+        depth: Vec<usize>,
+        stack: Vec<usize>,
+    }
+
+    impl<T: Ord + Clone> Walk<'_, T> {
+        fn traverse(&mut self, x: usize) {
+            self.stack.push(x);
+            let d = self.stack.len();
+            self.depth[x] = d;
+            for &y in &self.rel[x] {
+                if self.depth[y] == 0 {
+                    self.traverse(y);
+                }
+                self.depth[x] = self.depth[x].min(self.depth[y]);
+                let ys = self.sets[y].clone();
+                self.sets[x].extend(ys);
+            }
+            if self.depth[x] == d {
+                while let Some(top) = self.stack.pop() {
+                    self.depth[top] = usize::MAX;
+                    if top == x {
+                        break;
+                    }
+                    self.sets[top] = self.sets[x].clone();
+                }
+            }
+        }
+    }
+
+    let mut walk = Walk {
+        rel,
+        depth: vec![0; init.len()],
+        sets: init,
+        stack: Vec::new(),
+    };
+    for x in 0..rel.len() {
+        if walk.depth[x] == 0 {
+            walk.traverse(x);
+        }
+    }
+    walk.sets
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{grammars_tests, ActTable, Action, Lalr, LalrDp, Map, Parser, Set};
+
+    /// Checks if both tables are the same, except for the states numbering
+    fn equivalent(a: &ActTable<&str>, b: &ActTable<&str>) -> bool {
+        let mut pairs = Map::from([(0, 0)]);
+        let mut queue = vec![0];
+        while let Some(x) = queue.pop() {
+            let y = pairs[&x];
+            let (row_a, row_b) = (&a[x], &b[y]);
+            if row_a.keys().ne(row_b.keys()) {
+                return false;
+            }
+            for (sym, act_a) in row_a {
+                let acts_a: Set<_> = act_a.flatten().into_iter().collect();
+                let acts_b: Set<_> = row_b[sym].flatten().into_iter().collect();
+                if acts_a.len() != acts_b.len() {
+                    return false;
+                }
+                for (act_a, act_b) in acts_a.into_iter().zip(acts_b) {
+                    match (act_a, act_b) {
+                        (Action::Shift(to_a), Action::Shift(to_b))
+                        | (Action::Goto(to_a), Action::Goto(to_b)) => {
+                            if let Some(&paired) = pairs.get(to_a) {
+                                if paired != *to_b {
+                                    return false;
+                                }
+                            } else {
+                                pairs.insert(*to_a, *to_b);
+                                queue.push(*to_a);
+                            }
+                        }
+                        (act_a, act_b) if act_a == act_b => {}
+                        _ => return false,
+                    }
+                }
+            }
+        }
+        pairs.len() == a.len() && a.len() == b.len()
+    }
+
+    #[test]
+    fn lalr_equivalence() {
+        for (grammar, inputs, name) in grammars_tests::GRAMMARS {
+            let lalr = Lalr::new(grammar());
+            let dp = LalrDp::new(grammar());
+            assert!(
+                equivalent(&lalr.tables().actions, &dp.tables().actions),
+                "{name}"
+            );
+            assert_eq!(
+                lalr.tables().conflicts().count(),
+                dp.tables().conflicts().count(),
+                "{name}"
+            );
+            for input in *inputs {
+                let tokens = input.iter().map(|&t| crate::Token::new((), t));
+                assert_eq!(
+                    lalr.validate(tokens.clone()),
+                    dp.validate(tokens),
+                    "{name}: {input:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn states() {
+        for (grammar, _, name) in grammars_tests::GRAMMARS {
+            let lalr = Lalr::new(grammar());
+            let dp = LalrDp::new(grammar());
+            // `Lalr` doesn't merge the positions of the first state
+            let lalr_states: Set<_> = lalr
+                .tables()
+                .states
+                .iter()
+                .cloned()
+                .map(Lalr::merged)
+                .collect();
+            let dp_states: Set<_> = dp.tables().states.iter().cloned().collect();
+            assert_eq!(lalr_states, dp_states, "{name}");
+        }
+    }
+}
@@ -0,0 +1,363 @@
+use std::fmt;
+
+use crate::{
+    Actions, Automaton, Dfa, Error, Grammar, Meta, Regex, RegexError, Span, Status, Token,
+};
+
+/// Pattern of a `Lexer`. Matches without terminal are skipped, like whitespaces and comments.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LexRule<T> {
+    pub terminal: Option<T>,
+    pub regex: Regex,
+}
+
+impl<T> LexRule<T> {
+    #[must_use]
+    pub const fn new(terminal: Option<T>, regex: Regex) -> Self {
+        Self { terminal, regex }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LexError<T> {
+    /// Malformed pattern of a terminal, or of a skipped pattern
+    Pattern(Option<T>, RegexError),
+    /// Pattern of a symbol that isn't a terminal of the grammar
+    UnknownTerminal(T),
+    /// No pattern matches the input. Contains the span of the unmatched char.
+    UnexpectedChar(Span),
+}
+
+/// Failure of `Lexer::parse`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScanError<T>
+where
+    T: fmt::Debug,
+{
+    Lex(LexError<T>),
+    Parse(Error<T>),
+}
+
+impl<T: fmt::Debug> From<LexError<T>> for ScanError<T> {
+    fn from(value: LexError<T>) -> Self {
+        Self::Lex(value)
+    }
+}
+
+impl<T: fmt::Debug> From<Error<T>> for ScanError<T> {
+    fn from(value: Error<T>) -> Self {
+        Self::Parse(value)
+    }
+}
+
+impl<T> fmt::Display for ScanError<T>
+where
+    T: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Lex(e) => e.fmt(f),
+            Self::Parse(e) => e.fmt(f),
+        }
+    }
+}
+
+impl<T> fmt::Display for LexError<T>
+where
+    T: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Pattern(Some(term), e) => f.write_fmt(format_args!("pattern of {term:?}: {e}")),
+            Self::Pattern(None, e) => f.write_fmt(format_args!("skipped pattern: {e}")),
+            Self::UnknownTerminal(term) => f.write_fmt(format_args!("unknown terminal {term:?}")),
+            Self::UnexpectedChar(span) => {
+                f.write_fmt(format_args!("unexpected char at {}", span.start))
+            }
+        }
+    }
+}
+
+/// Regex based tokenizer. At each position, the longest match wins and, between matches of the
+/// same length, the first declared rule. So, keywords must be declared before identifiers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lexer<T> {
+    pub rules: Vec<LexRule<T>>,
+    pub automaton: Automaton,
+}
+
+impl<T> Lexer<T>
+where
+    T: Clone + Ord + fmt::Debug,
+{
+    /// Builds the lexer of `rules`, in priority order
+    #[must_use]
+    pub fn new(rules: Vec<LexRule<T>>) -> Self {
+        let automaton = Automaton::new(rules.iter().map(|r| &r.regex));
+        Self { rules, automaton }
+    }
+
+    /// Builds a lexer for the terminals of `grammar` from their `patterns`, in priority order, and
+    /// skipping the `skip` patterns, like whitespaces and comments.
+    /// # Errors
+    /// If a pattern is malformed or isn't of a terminal of `grammar`
+    pub fn for_grammar<'p>(
+        grammar: &Grammar<T>,
+        patterns: impl IntoIterator<Item = (T, &'p str)>,
+        skip: &[&str],
+    ) -> Result<Self, LexError<T>> {
+        let mut rules = Vec::new();
+        for (term, pattern) in patterns {
+            if !grammar.terminals.contains(&term) {
+                return Err(LexError::UnknownTerminal(term));
+            }
+            let regex =
+                Regex::parse(pattern).map_err(|e| LexError::Pattern(Some(term.clone()), e))?;
+            rules.push(LexRule::new(Some(term), regex));
+        }
+        for pattern in skip {
+            let regex = Regex::parse(pattern).map_err(|e| LexError::Pattern(None, e))?;
+            rules.push(LexRule::new(None, regex));
+        }
+        Ok(Self::new(rules))
+    }
+
+    /// Longest match at the start of `text`. Returns its rule index and length, in bytes.
+    #[must_use]
+    pub fn longest(&self, text: &str) -> Option<(usize, usize)> {
+        self.longest_in(text, |_| true)
+    }
+
+    /// The same of `Lexer::longest`, but only matching the terminals allowed by `filter` and the
+    /// skipped patterns
+    #[must_use]
+    pub fn longest_in(&self, text: &str, filter: impl Fn(&T) -> bool) -> Option<(usize, usize)> {
+        let allowed = |&rule: &usize| self.rules[rule].terminal.iter().all(&filter);
+        self.automaton
+            .prefixes(text)
+            .filter_map(|(len, accepts)| Some((*accepts.iter().find(|r| allowed(r))?, len)))
+            .last()
+    }
+
+    /// Iterates the tokens of `src`
+    #[must_use]
+    pub const fn scan<'a>(&self, src: &'a str) -> Scanner<'_, 'a, T> {
+        Scanner {
+            lexer: self,
+            src,
+            pos: 0,
+        }
+    }
+
+    /// Splits `src` into tokens, with their text and span
+    /// # Errors
+    /// If no pattern matches a part of `src`, raises a `LexError::UnexpectedChar`
+    pub fn tokenize<'a>(&self, src: &'a str) -> Result<Vec<Token<Meta<&'a str>, T>>, LexError<T>> {
+        self.scan(src).collect()
+    }
+
+    /// Parses `src` by feeding a `dfa` (see `Dfa::feed`) with its tokens, converted by `leaf`. Each
+    /// token is lexed with the terminals acceptable in the current state (see `Scanner::next_in`),
+    /// so a keyword can be an identifier where keywords aren't expected. Tables with more than one
+    /// lookahead symbol decide the state after the pending tokens, so they're lexed without context.
+    /// # Errors
+    /// Raises a `ScanError::Lex` if no pattern matches a part of `src`, or a `ScanError::Parse`
+    /// with the errors of `Dfa::feed` and `Dfa::finish`.
+    pub fn parse<'a, R, I, A>(
+        &self,
+        src: &'a str,
+        dfa: &mut Dfa<R, T, I, A>,
+        mut leaf: impl FnMut(Token<Meta<&'a str>, T>) -> Token<R, T>,
+    ) -> Result<R, ScanError<T>>
+    where
+        R: Clone,
+        I: Iterator<Item = Token<R, T>>,
+        A: Actions<T>,
+    {
+        let mut scanner = self.scan(src);
+        let mut pending = false;
+        loop {
+            let token = if pending {
+                scanner.next()
+            } else {
+                scanner.next_in(&dfa.acceptable())
+            };
+            let Some(token) = token.transpose()? else {
+                break;
+            };
+            pending = matches!(dfa.feed(leaf(token))?, Status::Pending(_));
+        }
+        Ok(dfa.finish()?)
+    }
+}
+
+/// Iterator over the tokens of a source. After a unexpected char, the scanning goes on from the
+/// next one.
+#[derive(Debug, Clone)]
+pub struct Scanner<'l, 'a, T> {
+    pub lexer: &'l Lexer<T>,
+    pub src: &'a str,
+    /// Byte offset of the next token
+    pub pos: usize,
+}
+
+impl<'a, T> Scanner<'_, 'a, T>
+where
+    T: Clone + Ord + fmt::Debug,
+{
+    /// The same of `Iterator::next`, but only matching the `acceptable` terminals, like the ones of
+    /// `Dfa::acceptable`. When none of them matches, falls back to every terminal, so the parser
+    /// can report the unexpected one.
+    pub fn next_in(&mut self, acceptable: &[T]) -> Option<<Self as Iterator>::Item> {
+        self.next_by(|term| acceptable.contains(term))
+    }
+
+    fn next_by(&mut self, filter: impl Fn(&T) -> bool) -> Option<<Self as Iterator>::Item> {
+        loop {
+            let rest = &self.src[self.pos..];
+            let start = self.pos;
+            let found = self
+                .lexer
+                .longest_in(rest, &filter)
+                .or_else(|| self.lexer.longest(rest));
+            let Some((rule, len)) = found else {
+                let c = rest.chars().next()?;
+                self.pos += c.len_utf8();
+                return Some(Err(LexError::UnexpectedChar(Span::new(start, self.pos))));
+            };
+            self.pos += len;
+            if let Some(term) = &self.lexer.rules[rule].terminal {
+                let span = Span::new(start, self.pos);
+                let text = &self.src[start..self.pos];
+                return Some(Ok(Token::new(Meta::new(text, span), term.clone())));
+            }
+        }
+    }
+}
+
+impl<'a, T> Iterator for Scanner<'_, 'a, T>
+where
+    T: Clone + Ord + fmt::Debug,
+{
+    type Item = Result<Token<Meta<&'a str>, T>, LexError<T>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_by(|_| true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        grammars_tests, Cst, Error, Lalr, LexError, Lexer, Parser, ReductMap, RegexError,
+        ScanError, Span,
+    };
+
+    fn statements() -> Lexer<&'static str> {
+        Lexer::for_grammar(
+            &grammars_tests::statements(),
+            [("id", r"[a-z_]\w*"), ("n", r"\d+"), ("=", "="), (";", ";")],
+            &[r"\s+", "#[^\n]*"],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn end_to_end() {
+        let lexer = statements();
+        let src = "a = 10; # comment\nb_2=a ;";
+        let tokens = lexer.tokenize(src).unwrap();
+        let words: Vec<_> = tokens.iter().map(|t| (t.ty, t.item.item)).collect();
+        assert_eq!(
+            words,
+            [
+                ("id", "a"),
+                ("=", "="),
+                ("n", "10"),
+                (";", ";"),
+                ("id", "b_2"),
+                ("=", "="),
+                ("id", "a"),
+                (";", ";"),
+            ]
+        );
+        assert_eq!(tokens[4].item.span, Span::new(18, 21));
+
+        let cst = Lalr::new(grammars_tests::statements()).cst(tokens).unwrap();
+        assert_eq!(
+            cst.sexp(),
+            r#"("S" ("Stmts" ("Stmts" ("Stmt" "id" "=" ("Value" "n") ";")) ("Stmt" "id" "=" ("Value" "id") ";")))"#
+        );
+    }
+
+    #[test]
+    fn priority() {
+        let grammar = grammars_tests::statements();
+        // `id` also matches `if`, but it's declared after
+        let lexer = Lexer::for_grammar(&grammar, [("=", "if"), ("id", "[a-z]+")], &[" "]).unwrap();
+        let types = |src| {
+            let tokens = lexer.tokenize(src).unwrap();
+            tokens.into_iter().map(|t| t.ty).collect::<Vec<_>>()
+        };
+        assert_eq!(types("if iff i f"), ["=", "id", "id", "id"]);
+        let lexer = Lexer::for_grammar(&grammar, [("id", "[a-z]+"), ("=", "if")], &[" "]).unwrap();
+        assert_eq!(lexer.tokenize("if").unwrap()[0].ty, "id");
+    }
+
+    #[test]
+    fn errors() {
+        let grammar = grammars_tests::statements();
+        assert_eq!(
+            Lexer::for_grammar(&grammar, [("Stmt", "a")], &[]),
+            Err(LexError::UnknownTerminal("Stmt"))
+        );
+        assert_eq!(
+            Lexer::for_grammar(&grammar, [("id", "(a")], &[]),
+            Err(LexError::Pattern(Some("id"), RegexError::UnexpectedEnd))
+        );
+
+        let lexer = statements();
+        let results: Vec<_> = lexer.scan("a = $1;").collect();
+        assert_eq!(results[2], Err(LexError::UnexpectedChar(Span::new(4, 5))));
+        assert_eq!(results.len(), 5);
+        assert!(lexer.tokenize("a = é").is_err());
+    }
+
+    #[test]
+    fn contextual() {
+        let grammar = grammars_tests::statements();
+        // every word is a `n` without context
+        let patterns = [("n", "[a-z]+"), ("id", "[a-z]+"), ("=", "="), (";", ";")];
+        let lexer = Lexer::for_grammar(&grammar, patterns, &[" "]).unwrap();
+        let lalr = Lalr::new(grammar);
+        let src = "a = b ; c = d ;";
+        assert!(lalr.cst(lexer.tokenize(src).unwrap()).is_err());
+
+        let mut dfa = lalr.push_dfa(ReductMap::new()).with_tree(Cst::node);
+        let mut acceptable = dfa.acceptable();
+        acceptable.sort_unstable();
+        assert_eq!(acceptable, ["error", "id"]);
+        let cst = lexer.parse(src, &mut dfa, Cst::leaf).unwrap();
+        assert_eq!(
+            cst.sexp(),
+            r#"("Stmts" ("Stmts" ("Stmt" "id" "=" ("Value" "n") ";")) ("Stmt" "id" "=" ("Value" "n") ";"))"#
+        );
+
+        dfa.reset();
+        let err = lexer.parse("a = ; b", &mut dfa, Cst::leaf).unwrap_err();
+        assert!(matches!(
+            err,
+            ScanError::Parse(Error::UnexpectedToken {
+                found: ";",
+                index: 2,
+                ..
+            })
+        ));
+        dfa.reset();
+        let err = lexer.parse("a = 1 ;", &mut dfa, Cst::leaf).unwrap_err();
+        assert_eq!(
+            err,
+            ScanError::Lex(LexError::UnexpectedChar(Span::new(4, 5)))
+        );
+    }
+}
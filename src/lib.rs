@@ -12,12 +12,24 @@ pub use parser::*;
 pub mod clr;
 pub use clr::Clr;
 
+pub mod clr_k;
+pub use clr_k::{ClrK, KPosition, KState, LookTable, SeqTable};
+
 pub mod lalr;
 pub use lalr::Lalr;
 
+pub mod lalr_dp;
+pub use lalr_dp::LalrDp;
+
 pub mod slr;
 pub use slr::Slr;
 
+pub mod lr0;
+pub use lr0::Lr0;
+
+pub mod pager;
+pub use pager::Pager;
+
 pub type Map<K, V> = BTreeMap<K, V>;
 pub type Set<T> = BTreeSet<T>;
 
@@ -65,6 +77,36 @@ pub use dfa::*;
 pub mod tabler;
 pub use tabler::*;
 
+pub mod report;
+pub use report::*;
+
+pub mod serial;
+pub use serial::*;
+
+pub mod codegen;
+pub use codegen::*;
+
+pub mod dense;
+pub use dense::*;
+
+pub mod glr;
+pub use glr::*;
+
+pub mod incremental;
+pub use incremental::*;
+
+pub mod cst;
+pub use cst::*;
+
+pub mod regex;
+pub use regex::*;
+
+pub mod lexer;
+pub use lexer::*;
+
+pub mod ebnf;
+pub use ebnf::*;
+
 pub mod pos;
 pub use pos::*;
 
@@ -128,6 +170,70 @@ impl Span {
     ) -> &'a T::Output {
         slice.index(self.start..self.end)
     }
+
+    /// The span moved inside `src`, back to the nearest char boundaries
+    /// ```
+    /// use lrp::Span;
+    /// assert_eq!(Span::new(2, 9).within("aé"), Span::new(1, 3));
+    /// ```
+    #[must_use]
+    pub fn within(&self, src: &str) -> Self {
+        let floor = |mut i: usize| {
+            i = i.min(src.len());
+            while !src.is_char_boundary(i) {
+                i -= 1;
+            }
+            i
+        };
+        let start = floor(self.start);
+        Self::new(start, floor(self.end).max(start))
+    }
+
+    /// Line and column, both starting from 1, where the span begins in `src`. The span is clamped
+    /// by `Span::within`.
+    #[must_use]
+    pub fn line_col(&self, src: &str) -> (usize, usize) {
+        let before = &src[..self.within(src).start];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        let line = before.matches('\n').count() + 1;
+        let col = before[line_start..].chars().count() + 1;
+        (line, col)
+    }
+
+    /// Renders the source line containing the span, pointing it with carets. The span is clamped
+    /// by `Span::within`, so a span past the end points after the last char.
+    /// ```
+    /// use lrp::Span;
+    /// let src = "let a = 1;\nlet b = = 2;";
+    /// assert_eq!(
+    ///     Span::new(19, 20).caret(src),
+    ///     " --> 2:9\n  |\n2 | let b = = 2;\n  |         ^"
+    /// );
+    /// assert_eq!(
+    ///     Span::new(40, 41).caret(src),
+    ///     " --> 2:13\n  |\n2 | let b = = 2;\n  |             ^"
+    /// );
+    /// ```
+    #[must_use]
+    pub fn caret(&self, src: &str) -> String {
+        let span = self.within(src);
+        let (line, col) = span.line_col(src);
+        let line_start = src[..span.start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = src[span.start..]
+            .find('\n')
+            .map_or(src.len(), |i| span.start + i);
+        let text = &src[line_start..line_end];
+        let width = src[span.start..span.end.min(line_end)]
+            .chars()
+            .count()
+            .max(1);
+        let gutter = " ".repeat(line.to_string().len());
+        format!(
+            "{gutter}--> {line}:{col}\n{gutter} |\n{line} | {text}\n{gutter} | {}{}",
+            " ".repeat(col - 1),
+            "^".repeat(width)
+        )
+    }
 }
 
 impl From<(usize, usize)> for Span {
@@ -172,6 +278,6 @@ macro_rules! grammar_map {
 
 #[cfg(test)]
 pub mod grammars_tests {
-    use crate::Grammar;
+    use crate::{Assoc, CharClass, Grammar};
     include!("../grammars_tests.rs");
 }
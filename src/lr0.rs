@@ -0,0 +1,191 @@
+use crate::{transitive, Action, ConflictReport, Map, Parser, Position, Set, State, Tabler};
+use std::fmt::Debug;
+
+/// LR(0) parser: the states are the same of `Slr`, but completed positions are reduced over every
+/// terminal, without looking ahead. So, a grammar is LR(0) when its table has no conflicts.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Lr0<T>
+where
+    T: PartialEq + Ord + Clone + Debug,
+{
+    pub table: Tabler<T>,
+}
+
+impl<T> Parser<T> for Lr0<T>
+where
+    T: PartialEq + Ord + Clone + Debug,
+{
+    fn with_table(table: Tabler<T>) -> Self {
+        let mut parser = Self::uninit(table);
+        parser.proc_actions();
+        parser
+    }
+
+    fn uninit(table: Tabler<T>) -> Self {
+        Self { table }
+    }
+
+    fn tables(&self) -> &Tabler<T> {
+        &self.table
+    }
+
+    fn tables_mut(&mut self) -> &mut Tabler<T> {
+        &mut self.table
+    }
+}
+
+impl<T> Lr0<T>
+where
+    T: PartialEq + Ord + Clone + Debug,
+{
+    pub fn proc_closures_first_row(&mut self) {
+        let start = self.prop_closure(self.table.basis_state());
+        self.table.kernels.insert(State::new(), 0);
+        self.table.states.push(start);
+    }
+
+    #[must_use]
+    pub fn closure(&self, state: State<T>) -> State<T> {
+        let mut new_state = State::new();
+        for pos in &state {
+            if let Some(top) = pos.top() {
+                if self.table.grammar.is_terminal(&top) {
+                    continue;
+                }
+                for prod in self.table.grammar.rules[&top].prods() {
+                    new_state.insert(Position::new(top.clone(), prod.clone(), 0, Set::new()));
+                }
+            }
+        }
+        new_state.extend(state);
+        new_state
+    }
+
+    #[must_use]
+    pub fn prop_closure(&self, seed: State<T>) -> State<T> {
+        transitive(seed, |s| self.closure(s))
+    }
+
+    pub fn proc_closures(&mut self) {
+        self.proc_closures_first_row();
+        let mut idx = 0;
+        while idx < self.table.states.len() {
+            for s in self.table.grammar.symbols() {
+                let kernel = Tabler::sym_filter(&self.table.states[idx], &s);
+                if kernel.is_empty() || self.table.kernels.contains_key(&kernel) {
+                    continue;
+                }
+                let closures = self.prop_closure(kernel.clone());
+                self.table.kernels.insert(kernel, self.table.states.len());
+                self.table.states.push(closures);
+            }
+            idx += 1;
+        }
+    }
+
+    /// Maps the decisions for the passed position. Completed positions are reduced over every
+    /// terminal, but the start rule, that's accepted at the input end.
+    /// # Panics
+    /// Never.
+    #[must_use]
+    pub fn decision(&self, start: &T, pos: &Position<T>, row: &State<T>) -> Map<T, Action<T>> {
+        pos.top().map_or_else(
+            || {
+                if &pos.rule == start {
+                    return Map::from([(self.table.eof(), Action::Acc)]);
+                }
+                self.table
+                    .grammar
+                    .terminals
+                    .iter()
+                    .map(|l| (l.clone(), Action::Reduce(pos.rule.clone(), pos.seq.clone())))
+                    .collect()
+            },
+            |locus| {
+                let filter = Tabler::sym_filter(row, &locus);
+                let state = self
+                    .state_from_kernel(&filter)
+                    .expect("`kernels` is incomplete");
+                if self.table.grammar.is_terminal(&locus) {
+                    Map::from([(locus, Action::Shift(state))])
+                } else {
+                    Map::from([(locus, Action::Goto(state))])
+                }
+            },
+        )
+    }
+
+    /// # Panics
+    /// Never.
+    pub fn proc_actions(&mut self) {
+        self.proc_closures();
+        let start = self.table.basis_pos().rule;
+        for row in &self.table.states {
+            let mut map: Map<T, Action<T>> = Map::new();
+            for item in row {
+                for (term, act) in self.decision(&start, item, row) {
+                    if map.contains_key(&term) && map[&term] != act {
+                        *map.get_mut(&term).unwrap() =
+                            Action::Conflict(Box::new(map.get(&term).unwrap().clone()), act.into());
+                    } else {
+                        map.insert(term, act);
+                    }
+                }
+            }
+            self.table.actions.push(map);
+        }
+        self.table.solve_conflicts();
+    }
+
+    /// Checks if the grammar is LR(0). I.e, if there isn't any conflict unsolved by the grammar's
+    /// precedences.
+    /// # Errors
+    /// Reports every conflict, if the grammar isn't LR(0)
+    pub fn check(&self) -> Result<(), Vec<ConflictReport<T>>> {
+        let reports = self.table.conflict_report();
+        if reports.is_empty() {
+            Ok(())
+        } else {
+            Err(reports)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{grammars_tests, to_tokens, Action, Lr0, Parser, Slr};
+
+    #[test]
+    pub fn dragon_book() {
+        let lr0 = Lr0::new(grammars_tests::dragon_book());
+        assert!(lr0.check().is_ok());
+
+        for input in grammars_tests::DRAGON_BOOK_INPUTS {
+            assert!(lr0.validate(to_tokens(input.iter().cloned())));
+        }
+    }
+
+    #[test]
+    pub fn non_lr0() {
+        // `Add -> Factor .` and `Factor -> Factor . * Term` need lookahead to be decided
+        let lr0 = Lr0::new(grammars_tests::serokell());
+        let reports = lr0.check().unwrap_err();
+        assert!(!reports.is_empty());
+        assert!(reports
+            .iter()
+            .all(|r| r.actions.iter().any(|a| matches!(a, Action::Reduce(..)))));
+    }
+
+    #[test]
+    fn states() {
+        for (grammar, _, name) in grammars_tests::GRAMMARS {
+            let lr0 = Lr0::new(grammar());
+            let slr = Slr::new(grammar());
+            assert_eq!(lr0.tables().states, slr.tables().states, "{name}");
+            assert!(
+                lr0.tables().conflicts().count() >= slr.tables().conflicts().count(),
+                "{name}"
+            );
+        }
+    }
+}
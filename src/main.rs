@@ -9,7 +9,6 @@ use prettytable::{row, Cell, Row, Table};
 
 fn main() {
     let grammar = lrp::grammar_map! {
-        "'S" -> "S",
         "S" -> "(" ")"
             | "(" "S" ")"
             | "[" "]"
@@ -33,7 +32,7 @@ fn main() {
     // );
     // grammar.insert("S", rule);
     let terminals = Set::from(["[", "]", "(", ")", "{", "}", "d", "c"]);
-    let grammar = Grammar::new("'S", grammar, "$");
+    let grammar = Grammar::augmented("S", grammar, "$").unwrap();
 
     let parser = Lalr::new(grammar);
 
@@ -43,6 +42,9 @@ fn main() {
 
     print_states_table(tables, &parser);
     print_actions_table(tables);
+    for report in tables.conflict_report() {
+        print!("{report}");
+    }
 
     loop {
         print!("input: ");
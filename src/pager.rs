@@ -0,0 +1,295 @@
+use crate::{transitive, Action, Map, Parser, Position, Set, State, Tabler};
+use std::{collections::VecDeque, fmt::Debug};
+
+/// Minimal LR(1) parser, built with Pager's practical general method (PGM): LR(1) states are
+/// generated as in `Clr`, but a new state is merged into an existing one with the same core
+/// (positions without lookahead) when they're weakly compatible, so merging them can't introduce
+/// a reduce/reduce conflict. It has the power of `Clr`, with about the size of `Lalr`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Pager<T>
+where
+    T: PartialEq + Ord + Clone + Debug,
+{
+    pub table: Tabler<T>,
+    /// Transitions of each state
+    pub gotos: Vec<Map<T, usize>>,
+}
+
+impl<T> Parser<T> for Pager<T>
+where
+    T: PartialEq + Ord + Clone + Debug,
+{
+    fn with_table(table: Tabler<T>) -> Self {
+        let mut parser = Self::uninit(table);
+        parser.proc_actions();
+        parser
+    }
+
+    fn uninit(table: Tabler<T>) -> Self {
+        Self {
+            table,
+            gotos: Vec::new(),
+        }
+    }
+
+    fn tables(&self) -> &Tabler<T> {
+        &self.table
+    }
+
+    fn tables_mut(&mut self) -> &mut Tabler<T> {
+        &mut self.table
+    }
+}
+
+impl<T> Pager<T>
+where
+    T: PartialEq + Ord + Clone + Debug,
+{
+    #[must_use]
+    pub fn closure(&self, state: State<T>) -> State<T> {
+        let mut new_state = State::new();
+        for pos in &state {
+            if let Some(top) = pos.top() {
+                if self.table.grammar.is_terminal(&top) {
+                    continue;
+                }
+                let look = self.table.first_seq(pos.rest(), &pos.look);
+                for prod in self.table.grammar.rules[&top].prods() {
+                    new_state.insert(Position::new(top.clone(), prod.clone(), 0, look.clone()));
+                }
+            }
+        }
+        new_state.extend(state);
+        new_state
+    }
+
+    #[must_use]
+    pub fn prop_closure(&self, seed: State<T>) -> State<T> {
+        Self::merged(transitive(seed, |s| self.closure(s)))
+    }
+
+    /// Checks if two kernels with the same core are weakly compatible. I.e, for every pair of
+    /// positions `i` and `j`, with lookaheads `A` and `B` in each kernel:
+    /// `(A[i] ∩ B[j] = ∅ and A[j] ∩ B[i] = ∅) or A[i] ∩ A[j] ≠ ∅ or B[i] ∩ B[j] ≠ ∅`
+    #[must_use]
+    pub fn weakly_compatible(a: &State<T>, b: &State<T>) -> bool {
+        let (a, b): (Vec<_>, Vec<_>) = (a.iter().collect(), b.iter().collect());
+        let meets = |x: &Set<T>, y: &Set<T>| !x.is_disjoint(y);
+        (0..a.len()).all(|i| {
+            (i + 1..a.len()).all(|j| {
+                !(meets(&a[i].look, &b[j].look) || meets(&a[j].look, &b[i].look))
+                    || meets(&a[i].look, &a[j].look)
+                    || meets(&b[i].look, &b[j].look)
+            })
+        })
+    }
+
+    /// Merges the lookaheads of `inc` into the `state`-th kernel. Returns if it has changed.
+    fn merge(&mut self, kernels: &mut [State<T>], state: usize, inc: State<T>) -> bool {
+        let merged = Self::merged(kernels[state].iter().cloned().chain(inc).collect());
+        if merged == kernels[state] {
+            return false;
+        }
+        kernels[state] = merged;
+        self.table.states[state] = self.prop_closure(kernels[state].clone());
+        true
+    }
+
+    /// Generates the states, starting from the basis kernel. A state is revisited each time its
+    /// kernel receives new lookaheads.
+    pub fn proc_closures(&mut self) {
+        let basis = self.table.basis_state();
+        let mut kernels = vec![basis.clone()];
+        let mut cores: Map<State<T>, Vec<usize>> = Map::from([(Self::core(&basis), vec![0])]);
+        self.table.states = vec![self.prop_closure(basis)];
+        self.gotos = vec![Map::new()];
+        let syms: Vec<_> = self.table.grammar.symbols().collect();
+
+        let mut queue = VecDeque::from([0]);
+        while let Some(idx) = queue.pop_front() {
+            for sym in &syms {
+                let kernel = Tabler::sym_filter(&self.table.states[idx], sym);
+                if kernel.is_empty() {
+                    continue;
+                }
+                let core = Self::core(&kernel);
+                let candidates = cores.get(&core).cloned().unwrap_or_default();
+                let to = if let Some(&to) = candidates
+                    .iter()
+                    .find(|&&c| Self::weakly_compatible(&kernels[c], &kernel))
+                {
+                    if self.merge(&mut kernels, to, kernel) {
+                        queue.push_back(to);
+                    }
+                    to
+                } else {
+                    let to = kernels.len();
+                    self.table.states.push(self.prop_closure(kernel.clone()));
+                    self.gotos.push(Map::new());
+                    kernels.push(kernel);
+                    cores.entry(core).or_default().push(to);
+                    queue.push_back(to);
+                    to
+                };
+                self.gotos[idx].insert(sym.clone(), to);
+            }
+        }
+        self.prune(&kernels);
+    }
+
+    /// Removes the states that became unreachable after merges, and indexes the kernels
+    fn prune(&mut self, kernels: &[State<T>]) {
+        let mut travel = Map::from([(0, 0)]);
+        let mut queue = VecDeque::from([0]);
+        while let Some(idx) = queue.pop_front() {
+            for &to in self.gotos[idx].values() {
+                if !travel.contains_key(&to) {
+                    travel.insert(to, travel.len());
+                    queue.push_back(to);
+                }
+            }
+        }
+        let mut order: Vec<_> = travel.iter().map(|(&old, &new)| (new, old)).collect();
+        order.sort_unstable();
+
+        let states = std::mem::take(&mut self.table.states);
+        let gotos = std::mem::take(&mut self.gotos);
+        self.table.kernels.clear();
+        self.table.kernels.insert(State::new(), 0);
+        for (new, old) in order {
+            self.table.states.push(states[old].clone());
+            self.gotos.push(
+                gotos[old]
+                    .iter()
+                    .map(|(s, to)| (s.clone(), travel[to]))
+                    .collect(),
+            );
+            if new != 0 {
+                self.table.kernels.insert(kernels[old].clone(), new);
+            }
+        }
+    }
+
+    #[must_use]
+    fn core(kernel: &State<T>) -> State<T> {
+        kernel.iter().map(Position::no_look).collect()
+    }
+
+    /// # Panics
+    /// Never.
+    pub fn proc_actions(&mut self) {
+        self.proc_closures();
+        let start = self.table.basis_pos().rule;
+        for (idx, row) in self.table.states.iter().enumerate() {
+            let mut map: Map<T, Action<T>> = Map::new();
+            for item in row {
+                for (term, act) in self.decision(&start, idx, item) {
+                    if map.contains_key(&term) && map[&term] != act {
+                        *map.get_mut(&term).unwrap() =
+                            Action::Conflict(Box::new(map.get(&term).unwrap().clone()), act.into());
+                    } else {
+                        map.insert(term, act);
+                    }
+                }
+            }
+            self.table.actions.push(map);
+        }
+        self.table.solve_conflicts();
+    }
+
+    #[must_use]
+    fn decision(&self, start: &T, state: usize, pos: &Position<T>) -> Map<T, Action<T>> {
+        pos.top().map_or_else(
+            || {
+                pos.look
+                    .iter()
+                    .map(|l| {
+                        (
+                            l.clone(),
+                            if &pos.rule == start {
+                                Action::Acc
+                            } else {
+                                Action::Reduce(pos.rule.clone(), pos.seq.clone())
+                            },
+                        )
+                    })
+                    .collect()
+            },
+            |locus| {
+                let to = self.gotos[state][&locus];
+                if self.table.grammar.is_terminal(&locus) {
+                    Map::from([(locus, Action::Shift(to))])
+                } else {
+                    Map::from([(locus, Action::Goto(to))])
+                }
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{grammars_tests, to_tokens, Clr, Lalr, Pager, Parser, Position, Set, State};
+
+    #[test]
+    fn sizes() {
+        for (grammar, inputs, name) in grammars_tests::GRAMMARS {
+            let pager = Pager::new(grammar());
+            let lalr = Lalr::new(grammar());
+            let clr = Clr::new(grammar());
+            let states = pager.tables().states.len();
+            assert!(lalr.tables().states.len() <= states, "{name}");
+            assert!(states <= clr.tables().states.len(), "{name}");
+            assert_eq!(
+                pager.tables().conflicts().count(),
+                clr.tables().conflicts().count(),
+                "{name}"
+            );
+
+            for input in *inputs {
+                assert_eq!(
+                    pager.validate(to_tokens(input.iter().copied())),
+                    clr.validate(to_tokens(input.iter().copied())),
+                    "{name}: {input:?}"
+                );
+            }
+        }
+    }
+
+    // https://smlweb.cpsc.ucalgary.ca/
+    #[test]
+    fn ucalgary_uni_oth_lr1() {
+        let pager = Pager::new(grammars_tests::ucalgary_uni_oth_lr1());
+        assert_eq!(0, pager.tables().conflicts().count());
+        let lalr = Lalr::new(grammars_tests::ucalgary_uni_oth_lr1());
+        assert_eq!(pager.tables().states.len(), lalr.tables().states.len() + 1);
+
+        for input in grammars_tests::UCALGARY_UNI_OTH_LR1_INPUTS {
+            assert!(pager.validate(to_tokens(input.iter().copied())));
+        }
+    }
+
+    #[test]
+    fn weakly_compatible() {
+        let grammar = grammars_tests::ucalgary_uni_oth_lr1();
+        let (a, b) = (&grammar.rules["A"].prods[0], &grammar.rules["B"].prods[0]);
+        let kernel = |look_a, look_b| {
+            State::from([
+                Position::new("A", a.clone(), 1, Set::from([look_a])),
+                Position::new("B", b.clone(), 1, Set::from([look_b])),
+            ])
+        };
+        assert!(Pager::weakly_compatible(
+            &kernel("b", "c"),
+            &kernel("b", "c")
+        ));
+        assert!(Pager::weakly_compatible(
+            &kernel("b", "c"),
+            &kernel("c", "c")
+        ));
+        assert!(!Pager::weakly_compatible(
+            &kernel("b", "c"),
+            &kernel("c", "b")
+        ));
+    }
+}
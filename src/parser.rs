@@ -1,5 +1,5 @@
 use crate::{BaseResult, Error, Grammar, State, Tabler, Token};
-use crate::{Dfa, ReductMap};
+use crate::{Cst, DenseTable, Dfa, Glr, Layout, Meta, ReductMap};
 use std::fmt::Debug;
 
 pub trait Parser<T>
@@ -22,12 +22,18 @@ where
     #[must_use]
     fn uninit(table: Tabler<T>) -> Self;
 
+    /// Builds a `Dfa` parsing from the `entry` point: a start symbol of `Grammar::with_entries`
+    /// or, without entry points, the start rule. Spans are opt-in: the errors only have them after
+    /// `Dfa::spanned`, for `Meta` tokens.
+    /// # Panics
+    /// If `entry` isn't an entry point (see `Tabler::entry`)
     #[must_use]
-    fn dfa<M, I: Iterator<Item = Token<M, T>>>(
+    fn dfa<'a, M, I: Iterator<Item = Token<M, T>>>(
         &self,
+        entry: &T,
         buffer: I,
-        maps: ReductMap<M, T>,
-    ) -> Dfa<M, T, I>
+        maps: ReductMap<'a, M, T>,
+    ) -> Dfa<'a, M, T, I>
     where
         M: Clone,
     {
@@ -35,36 +41,89 @@ where
             buffer,
             self.tables().actions.clone(),
             maps,
-            self.tables()
-                .grammar
-                .basis()
-                .look
-                .iter()
-                .take(1)
-                .collect::<Vec<_>>()[0]
-                .clone(),
+            self.tables().eof(),
         )
+        .with_entry(self.entry(entry))
     }
 
+    /// The same of `Parser::dfa`, but running on a `DenseTable` from the main entry point
     #[must_use]
-    fn simple_dfa<I: IntoIterator<Item = Token<(), T>>>(
+    fn dense_dfa<'a, M, I: Iterator<Item = Token<M, T>>>(
         &self,
         buffer: I,
-    ) -> Dfa<(), T, I::IntoIter> {
-        self.dfa(buffer.into_iter(), self.empty::<I::IntoIter>())
+        maps: ReductMap<'a, M, T>,
+        layout: Layout,
+    ) -> Dfa<'a, M, T, I, DenseTable<T>>
+    where
+        M: Clone,
+    {
+        Dfa::new(
+            buffer,
+            self.tables().dense(layout),
+            maps,
+            self.tables().eof(),
+        )
+        .with_entry(self.entry(&self.tables().start()))
     }
 
+    /// Initial state of the `entry` point
+    /// # Panics
+    /// If `entry` isn't an entry point (see `Tabler::entry`)
     #[must_use]
-    fn cloned<I: Iterator<Item = Token<T, T>>>(&self) -> ReductMap<T, T> {
-        fn clone<A: Clone>(toks: &[Token<A, A>]) -> A {
+    fn entry(&self, entry: &T) -> usize {
+        self.tables()
+            .entry(entry)
+            .unwrap_or_else(|| panic!("{entry:?} isn't an entry point"))
+    }
+
+    /// Builds a `Glr` runtime over the actions table, that forks on its conflicts
+    #[must_use]
+    fn glr(&self) -> Glr<T> {
+        Glr::new(self.tables().actions.clone(), self.tables().eof())
+    }
+
+    /// The same of `Parser::dfa` from the main entry point (see `Tabler::start`), but without a
+    /// buffer: its input is pushed by `Dfa::feed`
+    #[must_use]
+    fn push_dfa<'a, M>(
+        &self,
+        maps: ReductMap<'a, M, T>,
+    ) -> Dfa<'a, M, T, std::iter::Empty<Token<M, T>>>
+    where
+        M: Clone,
+    {
+        self.dfa(&self.tables().start(), std::iter::empty(), maps)
+    }
+
+    #[must_use]
+    fn simple_dfa<'a, I: IntoIterator<Item = Token<(), T>>>(
+        &self,
+        buffer: I,
+    ) -> Dfa<'a, (), T, I::IntoIter>
+    where
+        T: 'a,
+    {
+        let start = self.tables().start();
+        self.dfa(&start, buffer.into_iter(), self.empty::<I::IntoIter>())
+    }
+
+    #[must_use]
+    fn cloned<'a, I: Iterator<Item = Token<T, T>>>(&self) -> ReductMap<'a, T, T>
+    where
+        T: 'a,
+    {
+        fn clone<A: Clone>(toks: Vec<Token<A, A>>) -> A {
             toks[0].ty.clone()
         }
         Dfa::<T, T, I>::transparent(self.tables(), clone::<T>)
     }
 
     #[must_use]
-    fn empty<I: Iterator<Item = Token<(), T>>>(&self) -> ReductMap<(), T> {
-        const fn empty<A>(_: &[Token<(), A>]) {}
+    fn empty<'a, I: Iterator<Item = Token<(), T>>>(&self) -> ReductMap<'a, (), T>
+    where
+        T: 'a,
+    {
+        fn empty<A>(_: Vec<Token<(), A>>) {}
         Dfa::<(), T, I>::transparent(self.tables(), empty::<T>)
     }
 
@@ -78,7 +137,7 @@ where
     where
         M: Clone,
     {
-        let mut dfa = self.dfa(buffer.into_iter(), maps);
+        let mut dfa = self.dfa(&self.tables().start(), buffer.into_iter(), maps);
         dfa.start()?;
         let item = dfa
             .items
@@ -87,6 +146,26 @@ where
         Ok(item.item)
     }
 
+    /// Parses `input` into a `Cst`, without reductors. Unlike `Parser::parse`, the result is the
+    /// start rule node, containing every input token.
+    /// # Errors
+    /// The same of `dfa::travel`, spanning the unexpected token
+    fn cst<L>(
+        &self,
+        input: impl IntoIterator<Item = Token<Meta<L>, T>>,
+    ) -> BaseResult<Cst<L, T>, Error<T>>
+    where
+        L: Clone,
+    {
+        let mut dfa = self
+            .dfa(&self.tables().start(), Cst::leaves(input), ReductMap::new())
+            .with_tree(Cst::node)
+            .spanned();
+        dfa.start()?;
+        let start = self.tables().basis_pos().rule;
+        Ok(Cst::node(&start, 0, std::mem::take(&mut dfa.items)))
+    }
+
     /// Runs `Parser::parse` and checks by errors
     #[must_use]
     fn validate<I: IntoIterator<Item = Token<(), T>>>(&self, buffer: I) -> bool {
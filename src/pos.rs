@@ -5,23 +5,27 @@ use std::{
 
 use crate::{grammar::Production, Set};
 
+/// A production with a point. Each lookahead is a terminal or, in LR(k) parsers, a sequence of
+/// them (`L = Vec<T>`).
 #[derive(Default, Clone, PartialEq, Eq, PartialOrd, Ord)]
-pub struct Position<T>
+pub struct Position<T, L = T>
 where
     T: Clone + PartialEq + PartialOrd + Ord + Debug,
+    L: Clone + Ord + Debug,
 {
     pub rule: T,
     pub seq: Rc<Production<T>>,
     pub point: usize,
-    pub look: Set<T>,
+    pub look: Set<L>,
 }
 
-impl<T> Position<T>
+impl<T, L> Position<T, L>
 where
     T: Clone + PartialEq + PartialOrd + Ord + Debug,
+    L: Clone + Ord + Debug,
 {
     #[must_use]
-    pub fn new(rule: T, seq: Rc<Production<T>>, point: usize, look: Set<T>) -> Self {
+    pub fn new(rule: T, seq: Rc<Production<T>>, point: usize, look: Set<L>) -> Self {
         Self {
             rule,
             seq,
@@ -32,7 +36,7 @@ where
 
     #[allow(clippy::missing_const_for_fn)]
     #[must_use]
-    pub fn with_look(self, look: Set<T>) -> Self {
+    pub fn with_look(self, look: Set<L>) -> Self {
         Self { look, ..self }
     }
 
@@ -55,6 +59,13 @@ where
         self.peek(0)
     }
 
+    /// Returns the items after the current position item. I.e:
+    /// rest([S -> C. D f; $]) = [f]
+    #[must_use]
+    pub fn rest(&self) -> &[T] {
+        self.seq.0.get(self.point + 1..).unwrap_or_default()
+    }
+
     #[must_use]
     pub fn finished(&self) -> bool {
         self.point >= self.seq.0.len()
@@ -91,9 +102,10 @@ where
     }
 }
 
-impl<T> Display for Position<T>
+impl<T, L> Display for Position<T, L>
 where
     T: Clone + PartialEq + PartialOrd + Ord + Debug,
+    L: Clone + Ord + Debug,
 {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.write_fmt(format_args!("{:?} =", self.rule))?;
@@ -110,9 +122,10 @@ where
     }
 }
 
-impl<T> Debug for Position<T>
+impl<T, L> Debug for Position<T, L>
 where
     T: Clone + PartialEq + PartialOrd + Ord + Debug,
+    L: Clone + Ord + Debug,
 {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.write_fmt(format_args!("{self}"))
@@ -0,0 +1,623 @@
+use std::{fmt, ops::RangeInclusive, str::FromStr};
+
+use crate::{Map, Set};
+
+/// Set of chars, as sorted, disjoint and non-adjacent inclusive ranges
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CharClass {
+    pub ranges: Vec<(char, char)>,
+}
+
+const fn succ(c: char) -> Option<char> {
+    match c {
+        '\u{D7FF}' => Some('\u{E000}'),
+        char::MAX => None,
+        _ => char::from_u32(c as u32 + 1),
+    }
+}
+
+const fn pred(c: char) -> Option<char> {
+    match c {
+        '\u{E000}' => Some('\u{D7FF}'),
+        '\0' => None,
+        _ => char::from_u32(c as u32 - 1),
+    }
+}
+
+impl CharClass {
+    #[must_use]
+    pub fn new(ranges: impl IntoIterator<Item = RangeInclusive<char>>) -> Self {
+        let mut sorted: Vec<_> = ranges
+            .into_iter()
+            .filter(|r| !r.is_empty())
+            .map(RangeInclusive::into_inner)
+            .collect();
+        sorted.sort_unstable();
+        let mut ranges: Vec<(char, char)> = Vec::with_capacity(sorted.len());
+        for (lo, hi) in sorted {
+            match ranges.last_mut() {
+                Some(last) if succ(last.1).into_iter().all(|next| lo <= next) => {
+                    last.1 = last.1.max(hi)
+                }
+                _ => ranges.push((lo, hi)),
+            }
+        }
+        Self { ranges }
+    }
+
+    #[must_use]
+    pub fn single(c: char) -> Self {
+        Self::new([c..=c])
+    }
+
+    /// Every char
+    #[must_use]
+    pub fn any() -> Self {
+        Self::new(['\0'..=char::MAX])
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    #[must_use]
+    pub fn contains(&self, c: char) -> bool {
+        let idx = self.ranges.partition_point(|&(_, hi)| hi < c);
+        self.ranges.get(idx).is_some_and(|&(lo, _)| lo <= c)
+    }
+
+    #[must_use]
+    pub fn union(&self, other: &Self) -> Self {
+        Self::new(
+            self.ranges
+                .iter()
+                .chain(&other.ranges)
+                .map(|&(lo, hi)| lo..=hi),
+        )
+    }
+
+    #[must_use]
+    pub fn is_subset(&self, other: &Self) -> bool {
+        self.union(other) == *other
+    }
+
+    /// Every char outside the class
+    #[must_use]
+    pub fn negate(&self) -> Self {
+        let mut ranges = Vec::new();
+        let mut next = Some('\0');
+        for &(lo, hi) in &self.ranges {
+            if let (Some(start), Some(end)) = (next, pred(lo)) {
+                ranges.push(start..=end);
+            }
+            next = succ(hi);
+        }
+        if let Some(start) = next {
+            ranges.push(start..=char::MAX);
+        }
+        Self::new(ranges)
+    }
+}
+
+impl fmt::Display for CharClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("[")?;
+        for &(lo, hi) in &self.ranges {
+            if lo == hi {
+                f.write_fmt(format_args!("{}", lo.escape_debug()))?;
+            } else {
+                f.write_fmt(format_args!("{}-{}", lo.escape_debug(), hi.escape_debug()))?;
+            }
+        }
+        f.write_str("]")
+    }
+}
+
+/// Regular expression over chars
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Regex {
+    Class(CharClass),
+    /// Sequence of expressions. The empty sequence matches the empty string.
+    Concat(Vec<Self>),
+    Alt(Vec<Self>),
+    /// Repeats an expression between a minimum and an optional maximum amount of times
+    Repeat(Box<Self>, usize, Option<usize>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegexError {
+    /// The pattern ended inside a group, a class or an escape
+    UnexpectedEnd,
+    /// A char out of place, like a unmatched `)` or a quantifier without expression. Contains its
+    /// byte offset in the pattern.
+    UnexpectedChar(usize, char),
+    /// A class range or repetition bound in decreasing order, at the byte offset
+    BadRange(usize),
+}
+
+impl fmt::Display for RegexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnexpectedEnd => f.write_str("unexpected end of pattern"),
+            Self::UnexpectedChar(at, c) => {
+                f.write_fmt(format_args!("unexpected char {c:?} at {at}"))
+            }
+            Self::BadRange(at) => f.write_fmt(format_args!("bad range at {at}")),
+        }
+    }
+}
+
+impl Regex {
+    /// Matches exactly `text`
+    #[must_use]
+    pub fn literal(text: &str) -> Self {
+        Self::Concat(
+            text.chars()
+                .map(|c| Self::Class(CharClass::single(c)))
+                .collect(),
+        )
+    }
+
+    /// Parses a pattern. Supports alternations (`a|b`), groups (`(a)`), the quantifiers `*`, `+`,
+    /// `?`, `{n}`, `{n,}` and `{n,m}`, classes (`[a-z_]`, `[^"]`), any char but a line feed (`.`)
+    /// and escapes: `\n`, `\r`, `\t`, `\d`, `\w`, `\s` (and their negations `\D`, `\W`, `\S`) or
+    /// any escaped symbol.
+    /// # Errors
+    /// If the pattern is malformed
+    pub fn parse(pattern: &str) -> Result<Self, RegexError> {
+        let mut parser = RegexParser {
+            chars: pattern.char_indices().collect(),
+            idx: 0,
+        };
+        let regex = parser.alt()?;
+        match parser.peek() {
+            Some((at, c)) => Err(RegexError::UnexpectedChar(at, c)),
+            None => Ok(regex),
+        }
+    }
+
+    /// If matches the empty string
+    #[must_use]
+    pub fn nullable(&self) -> bool {
+        match self {
+            Self::Class(_) => false,
+            Self::Concat(seq) => seq.iter().all(Self::nullable),
+            Self::Alt(alts) => alts.iter().any(Self::nullable),
+            Self::Repeat(regex, min, _) => *min == 0 || regex.nullable(),
+        }
+    }
+}
+
+impl FromStr for Regex {
+    type Err = RegexError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+struct RegexParser {
+    chars: Vec<(usize, char)>,
+    idx: usize,
+}
+
+impl RegexParser {
+    fn peek(&self) -> Option<(usize, char)> {
+        self.chars.get(self.idx).copied()
+    }
+
+    fn next(&mut self) -> Result<(usize, char), RegexError> {
+        let next = self.peek().ok_or(RegexError::UnexpectedEnd)?;
+        self.idx += 1;
+        Ok(next)
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        let found = self.peek().is_some_and(|(_, p)| p == c);
+        self.idx += usize::from(found);
+        found
+    }
+
+    fn alt(&mut self) -> Result<Regex, RegexError> {
+        let mut alts = vec![self.concat()?];
+        while self.eat('|') {
+            alts.push(self.concat()?);
+        }
+        Ok(if alts.len() == 1 {
+            alts.remove(0)
+        } else {
+            Regex::Alt(alts)
+        })
+    }
+
+    fn concat(&mut self) -> Result<Regex, RegexError> {
+        let mut seq = Vec::new();
+        while let Some((_, c)) = self.peek() {
+            if c == '|' || c == ')' {
+                break;
+            }
+            seq.push(self.repeat()?);
+        }
+        Ok(if seq.len() == 1 {
+            seq.remove(0)
+        } else {
+            Regex::Concat(seq)
+        })
+    }
+
+    fn repeat(&mut self) -> Result<Regex, RegexError> {
+        let mut regex = self.atom()?;
+        while let Some((at, c)) = self.peek() {
+            let (min, max) = match c {
+                '*' => (0, None),
+                '+' => (1, None),
+                '?' => (0, Some(1)),
+                '{' => {
+                    self.idx += 1;
+                    let min = self.number()?;
+                    let max = if !self.eat(',') {
+                        Some(min)
+                    } else if self.peek().is_some_and(|(_, c)| c == '}') {
+                        None
+                    } else {
+                        Some(self.number()?)
+                    };
+                    match self.peek() {
+                        Some((_, '}')) if max.is_some_and(|max| max < min) => {
+                            return Err(RegexError::BadRange(at))
+                        }
+                        Some((_, '}')) => (min, max),
+                        Some((at, c)) => return Err(RegexError::UnexpectedChar(at, c)),
+                        None => return Err(RegexError::UnexpectedEnd),
+                    }
+                }
+                _ => break,
+            };
+            self.idx += 1;
+            regex = Regex::Repeat(Box::new(regex), min, max);
+        }
+        Ok(regex)
+    }
+
+    fn number(&mut self) -> Result<usize, RegexError> {
+        let mut number = None;
+        while let Some(digit) = self.peek().and_then(|(_, c)| c.to_digit(10)) {
+            self.idx += 1;
+            number = Some(number.unwrap_or(0) * 10 + digit as usize);
+        }
+        match number {
+            Some(number) => Ok(number),
+            None => {
+                let (at, c) = self.next()?;
+                Err(RegexError::UnexpectedChar(at, c))
+            }
+        }
+    }
+
+    fn atom(&mut self) -> Result<Regex, RegexError> {
+        let (at, c) = self.next()?;
+        let class = match c {
+            '(' => {
+                let regex = self.alt()?;
+                return match self.next()? {
+                    (_, ')') => Ok(regex),
+                    (at, c) => Err(RegexError::UnexpectedChar(at, c)),
+                };
+            }
+            '[' => self.class()?,
+            '.' => CharClass::single('\n').negate(),
+            '\\' => self.escape()?,
+            '*' | '+' | '?' | '{' | ')' => return Err(RegexError::UnexpectedChar(at, c)),
+            _ => CharClass::single(c),
+        };
+        Ok(Regex::Class(class))
+    }
+
+    fn escape(&mut self) -> Result<CharClass, RegexError> {
+        let digit = CharClass::new(['0'..='9']);
+        let word = CharClass::new(['a'..='z', 'A'..='Z', '0'..='9', '_'..='_']);
+        let space = CharClass::new(['\t'..='\r', ' '..=' ']);
+        Ok(match self.next()?.1 {
+            'n' => CharClass::single('\n'),
+            'r' => CharClass::single('\r'),
+            't' => CharClass::single('\t'),
+            'd' => digit,
+            'D' => digit.negate(),
+            'w' => word,
+            'W' => word.negate(),
+            's' => space,
+            'S' => space.negate(),
+            c => CharClass::single(c),
+        })
+    }
+
+    fn class(&mut self) -> Result<CharClass, RegexError> {
+        let negated = self.eat('^');
+        let mut class = CharClass::default();
+        let mut first = true;
+        loop {
+            let (at, c) = self.next()?;
+            let item = match c {
+                ']' if !first => break,
+                '\\' => self.escape()?,
+                _ => {
+                    let ranged = self.peek().is_some_and(|(_, c)| c == '-')
+                        && self.chars.get(self.idx + 1).is_some_and(|&(_, c)| c != ']');
+                    if ranged {
+                        self.idx += 1;
+                        let (_, hi) = self.next()?;
+                        if hi < c {
+                            return Err(RegexError::BadRange(at));
+                        }
+                        CharClass::new([c..=hi])
+                    } else {
+                        CharClass::single(c)
+                    }
+                }
+            };
+            class = class.union(&item);
+            first = false;
+        }
+        Ok(if negated { class.negate() } else { class })
+    }
+}
+
+/// State of an `Automaton`
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct AutoState {
+    /// Transitions over disjoint char ranges, sorted
+    pub moves: Vec<(char, char, usize)>,
+    /// Patterns matched when the input ends in this state, in increasing order
+    pub accepts: Vec<usize>,
+}
+
+/// Deterministic automaton recognizing a list of patterns at the same time. The start state is 0.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Automaton {
+    pub states: Vec<AutoState>,
+}
+
+/// Thompson's construction state
+#[derive(Default)]
+struct NfaState {
+    eps: Vec<usize>,
+    moves: Vec<(CharClass, usize)>,
+    accept: Option<usize>,
+}
+
+#[derive(Default)]
+struct Nfa {
+    states: Vec<NfaState>,
+}
+
+impl Nfa {
+    fn push(&mut self) -> usize {
+        self.states.push(NfaState::default());
+        self.states.len() - 1
+    }
+
+    fn eps(&mut self, from: usize, to: usize) {
+        self.states[from].eps.push(to);
+    }
+
+    /// Builds the fragment of `regex` starting at `from`. Returns its end state.
+    fn build(&mut self, regex: &Regex, from: usize) -> usize {
+        match regex {
+            Regex::Class(class) => {
+                let to = self.push();
+                self.states[from].moves.push((class.clone(), to));
+                to
+            }
+            Regex::Concat(seq) => seq.iter().fold(from, |at, regex| self.build(regex, at)),
+            Regex::Alt(alts) => {
+                let end = self.push();
+                for regex in alts {
+                    let start = self.push();
+                    self.eps(from, start);
+                    let last = self.build(regex, start);
+                    self.eps(last, end);
+                }
+                end
+            }
+            Regex::Repeat(regex, min, max) => {
+                let mut at = (0..*min).fold(from, |at, _| self.build(regex, at));
+                if let Some(max) = max {
+                    for _ in *min..*max {
+                        let end = self.push();
+                        self.eps(at, end);
+                        let start = self.push();
+                        self.eps(at, start);
+                        let last = self.build(regex, start);
+                        self.eps(last, end);
+                        at = end;
+                    }
+                } else {
+                    let looped = self.push();
+                    self.eps(at, looped);
+                    let last = self.build(regex, looped);
+                    self.eps(last, looped);
+                    at = looped;
+                }
+                at
+            }
+        }
+    }
+
+    fn closure(&self, seed: impl IntoIterator<Item = usize>) -> Vec<usize> {
+        let mut set = Set::new();
+        let mut stack: Vec<_> = seed.into_iter().collect();
+        while let Some(state) = stack.pop() {
+            if set.insert(state) {
+                stack.extend(&self.states[state].eps);
+            }
+        }
+        set.into_iter().collect()
+    }
+}
+
+impl Automaton {
+    /// Builds the automaton of `patterns`, by the subset construction over their NFA
+    #[must_use]
+    pub fn new<'a>(patterns: impl IntoIterator<Item = &'a Regex>) -> Self {
+        let mut nfa = Nfa::default();
+        let start = nfa.push();
+        for (idx, regex) in patterns.into_iter().enumerate() {
+            let first = nfa.push();
+            nfa.eps(start, first);
+            let last = nfa.build(regex, first);
+            nfa.states[last].accept = Some(idx);
+        }
+
+        let mut sets = vec![nfa.closure([start])];
+        let mut ids = Map::from([(sets[0].clone(), 0)]);
+        let mut states = Vec::new();
+        while states.len() < sets.len() {
+            let set = &sets[states.len()];
+            let mut accepts: Vec<_> = set.iter().filter_map(|&s| nfa.states[s].accept).collect();
+            accepts.sort_unstable();
+            let moves: Vec<_> = set.iter().flat_map(|&s| &nfa.states[s].moves).collect();
+
+            // elementary intervals between the bounds of every class
+            let mut bounds = Set::new();
+            for (class, _) in &moves {
+                for &(lo, hi) in &class.ranges {
+                    bounds.insert(u32::from(lo));
+                    bounds.insert(u32::from(hi) + 1);
+                }
+            }
+            let bounds: Vec<_> = bounds.into_iter().collect();
+            let mut state = AutoState {
+                moves: Vec::new(),
+                accepts,
+            };
+            for pair in bounds.windows(2) {
+                let (Some(lo), Some(hi)) = (
+                    char::from_u32(pair[0]).or_else(|| char::from_u32(0xE000)),
+                    char::from_u32(pair[1] - 1).or_else(|| char::from_u32(0xD7FF)),
+                ) else {
+                    continue;
+                };
+                if hi < lo {
+                    continue;
+                }
+                let targets = moves
+                    .iter()
+                    .filter(|(class, _)| class.contains(lo))
+                    .map(|&&(_, to)| to);
+                let target = nfa.closure(targets);
+                if target.is_empty() {
+                    continue;
+                }
+                let next = *ids.entry(target.clone()).or_insert_with(|| {
+                    sets.push(target);
+                    sets.len() - 1
+                });
+                match state.moves.last_mut() {
+                    Some(last) if last.2 == next && succ(last.1) == Some(lo) => last.1 = hi,
+                    _ => state.moves.push((lo, hi, next)),
+                }
+            }
+            states.push(state);
+        }
+        Self { states }
+    }
+
+    /// State after reading `c` in `state`, if any
+    #[must_use]
+    pub fn step(&self, state: usize, c: char) -> Option<usize> {
+        let moves = &self.states[state].moves;
+        let idx = moves.partition_point(|&(_, hi, _)| hi < c);
+        moves
+            .get(idx)
+            .filter(|&&(lo, _, _)| lo <= c)
+            .map(|&(_, _, to)| to)
+    }
+
+    /// Runs the automaton over `text`, returning every prefix end, in bytes, with the patterns
+    /// accepted there. The empty prefix isn't included.
+    pub fn prefixes<'a>(&'a self, text: &'a str) -> impl Iterator<Item = (usize, &'a [usize])> {
+        let mut state = Some(0);
+        text.char_indices().map_while(move |(at, c)| {
+            let next = self.step(state?, c)?;
+            state = Some(next);
+            Some((at + c.len_utf8(), self.states[next].accepts.as_slice()))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Automaton, CharClass, Regex, RegexError};
+
+    fn matches(pattern: &str, text: &str) -> bool {
+        let regex = Regex::parse(pattern).unwrap();
+        let automaton = Automaton::new([&regex]);
+        if text.is_empty() {
+            return regex.nullable();
+        }
+        let full = automaton
+            .prefixes(text)
+            .any(|(end, accepts)| end == text.len() && !accepts.is_empty());
+        full
+    }
+
+    #[test]
+    fn classes() {
+        let class = CharClass::new(['a'..='f', 'd'..='k', 'l'..='l', '0'..='9']);
+        assert_eq!(class.ranges, [('0', '9'), ('a', 'l')]);
+        assert!(class.contains('5') && class.contains('l') && !class.contains('m'));
+        let negated = class.negate();
+        assert!(!negated.contains('b') && negated.contains('\u{E000}') && negated.contains('\0'));
+        assert_eq!(negated.negate(), class);
+        assert_eq!(CharClass::any().negate(), CharClass::default());
+        assert_eq!(class.to_string(), "[0-9a-l]");
+    }
+
+    #[test]
+    fn patterns() {
+        let cases: &[(&str, &[&str], &[&str])] = &[
+            ("abc", &["abc"], &["ab", "abcd", ""]),
+            ("a|bc|", &["a", "bc", ""], &["b", "abc"]),
+            ("(ab)*c", &["c", "abc", "ababc"], &["ac", "abab"]),
+            (
+                "[a-c_]+[^a-c]?",
+                &["a", "_b", "cc1", "a\n"],
+                &["", "1", "ab12"],
+            ),
+            (r"\d{2,3}", &["12", "123"], &["1", "1234", "1a"]),
+            (r"x{2}y{1,}", &["xxy", "xxyyy"], &["xy", "xx"]),
+            (r"\w+\s\W\.", &["ab_1 +.", "a\t-."], &["a +a", "a b."]),
+            (
+                r#""([^"\\]|\\.)*""#,
+                &[r#""""#, r#""a\"b""#],
+                &[r#""a"b""#, r#"""#],
+            ),
+            (".", &["a", "é"], &["\n", "ab"]),
+            ("[]a]", &["]", "a"], &["b"]),
+            ("[a-]", &["a", "-"], &["b"]),
+        ];
+        for (pattern, good, bad) in cases {
+            for text in *good {
+                assert!(matches(pattern, text), "{pattern} ~ {text:?}");
+            }
+            for text in *bad {
+                assert!(!matches(pattern, text), "{pattern} !~ {text:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(Regex::parse("(ab"), Err(RegexError::UnexpectedEnd));
+        assert_eq!(Regex::parse("ab)"), Err(RegexError::UnexpectedChar(2, ')')));
+        assert_eq!(Regex::parse("*a"), Err(RegexError::UnexpectedChar(0, '*')));
+        assert_eq!(Regex::parse("[z-a]"), Err(RegexError::BadRange(1)));
+        assert_eq!(Regex::parse("a{3,1}"), Err(RegexError::BadRange(1)));
+        assert_eq!(
+            Regex::parse("a{x}"),
+            Err(RegexError::UnexpectedChar(2, 'x'))
+        );
+        assert_eq!(Regex::parse("[ab"), Err(RegexError::UnexpectedEnd));
+    }
+}
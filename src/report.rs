@@ -0,0 +1,244 @@
+use crate::{Action, Map, Position, Set, Tabler};
+use std::{collections::VecDeque, fmt};
+
+/// A conflicting entry of the actions table, with the context needed to fix the grammar.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConflictReport<T>
+where
+    T: PartialEq + PartialOrd + Ord + Clone + fmt::Debug,
+{
+    /// State containing the conflict
+    pub state: usize,
+    /// Lookahead terminal of the conflicting entry
+    pub look: T,
+    /// Competing actions
+    pub actions: Vec<Action<T>>,
+    /// Positions of `state` that produce the competing actions
+    pub items: Vec<Position<T>>,
+    /// Shortest symbols sequence that leads the initial state to `state`
+    pub prefix: Vec<T>,
+    /// Shortest terminals sequence that leads the initial state to `state`: `prefix` with its
+    /// non-terminals expanded into their shortest derivations
+    pub input: Vec<T>,
+}
+
+impl<T> fmt::Display for ConflictReport<T>
+where
+    T: PartialEq + PartialOrd + Ord + Clone + fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "conflict in state {} on {:?}: {:?}",
+            self.state, self.look, self.actions
+        )?;
+        for item in &self.items {
+            writeln!(f, "  {item}")?;
+        }
+        writeln!(f, "  example: {:?} . {:?}", self.input, self.look)
+    }
+}
+
+impl<T> Tabler<T>
+where
+    T: PartialEq + PartialOrd + Ord + Clone + fmt::Debug,
+{
+    /// Reports every conflict in the actions table. Must be called before `Tabler::reduce_equals`,
+    /// since it relies on `Tabler::states`.
+    #[must_use]
+    pub fn conflict_report(&self) -> Vec<ConflictReport<T>> {
+        let paths = self.shortest_paths();
+        let yields = self.shortest_yields();
+        let mut reports = Vec::new();
+        for (state, row) in self.actions.iter().enumerate() {
+            for (look, act) in row {
+                if !matches!(act, Action::Conflict(..)) {
+                    continue;
+                }
+                let actions: Vec<_> = act
+                    .flatten()
+                    .into_iter()
+                    .collect::<Set<_>>()
+                    .into_iter()
+                    .cloned()
+                    .collect();
+                let items = self.states.get(state).map_or_else(Vec::new, |row| {
+                    row.iter()
+                        .filter(|pos| self.produces(pos, look, &actions))
+                        .cloned()
+                        .collect()
+                });
+                let prefix = paths.get(&state).cloned().unwrap_or_default();
+                let input = prefix
+                    .iter()
+                    .flat_map(|sym| {
+                        yields
+                            .get(sym)
+                            .cloned()
+                            .unwrap_or_else(|| vec![sym.clone()])
+                    })
+                    .collect();
+                reports.push(ConflictReport {
+                    state,
+                    look: look.clone(),
+                    actions,
+                    items,
+                    prefix,
+                    input,
+                });
+            }
+        }
+        reports
+    }
+
+    /// Checks if `pos` generates one of `actions` over `look`
+    fn produces(&self, pos: &Position<T>, look: &T, actions: &[Action<T>]) -> bool {
+        pos.top().map_or_else(
+            || {
+                actions.iter().any(|act| match act {
+                    Action::Reduce(rule, prod) => &pos.rule == rule && &pos.seq == prod,
+                    Action::Acc => pos.rule == self.basis_pos().rule,
+                    _ => false,
+                })
+            },
+            |top| &top == look && actions.iter().any(|a| matches!(a, Action::Shift(_))),
+        )
+    }
+
+    /// Generates the shortest symbols sequence (shifts and gotos) from the initial state of an
+    /// entry point (see `Tabler::entry`) to each reachable state. So, the marker terminals of
+    /// `Grammar::with_entries` aren't in the sequences.
+    #[must_use]
+    pub fn shortest_paths(&self) -> Map<usize, Vec<T>> {
+        let entries = self.grammar.entries.iter();
+        let mut queue: VecDeque<_> = if self.grammar.entries.is_empty() {
+            VecDeque::from([0])
+        } else {
+            entries.filter_map(|(start, _)| self.entry(start)).collect()
+        };
+        let mut paths: Map<_, _> = queue.iter().map(|&state| (state, Vec::new())).collect();
+        while let Some(state) = queue.pop_front() {
+            let Some(row) = self.actions.get(state) else {
+                continue;
+            };
+            for (sym, act) in row {
+                for act in act.flatten() {
+                    let (Action::Shift(to) | Action::Goto(to)) = act else {
+                        continue;
+                    };
+                    if paths.contains_key(to) {
+                        continue;
+                    }
+                    let mut path = paths[&state].clone();
+                    path.push(sym.clone());
+                    paths.insert(*to, path);
+                    queue.push_back(*to);
+                }
+            }
+        }
+        paths
+    }
+
+    /// Generates the shortest terminals sequence derived by each non-terminal. Non-productive
+    /// non-terminals are missing.
+    #[must_use]
+    pub fn shortest_yields(&self) -> Map<T, Vec<T>> {
+        let mut yields: Map<T, Vec<T>> = Map::new();
+        loop {
+            let mut changed = false;
+            for rule in self.grammar.rules() {
+                for prod in rule.prods() {
+                    let mut derived = Vec::new();
+                    let complete = prod.0.iter().all(|sym| {
+                        if self.grammar.is_terminal(sym) {
+                            derived.push(sym.clone());
+                            true
+                        } else if let Some(y) = yields.get(sym) {
+                            derived.extend(y.iter().cloned());
+                            true
+                        } else {
+                            false
+                        }
+                    });
+                    let shorter = yields
+                        .get(&rule.name)
+                        .map_or(true, |old| derived.len() < old.len());
+                    if complete && shorter {
+                        yields.insert(rule.name.clone(), derived);
+                        changed = true;
+                    }
+                }
+            }
+            if !changed {
+                return yields;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{grammar_map, grammars_tests, Action, Grammar, Lalr, Parser, Slr};
+
+    // https://smlweb.cpsc.ucalgary.ca/
+    #[test]
+    fn ucalgary_uni_oth_lr1() {
+        let lalr = Lalr::new(grammars_tests::ucalgary_uni_oth_lr1());
+        let reports = lalr.tables().conflict_report();
+        assert_eq!(reports.len(), 2);
+
+        for (report, look) in reports.iter().zip(["b", "c"]) {
+            assert_eq!(report.look, look);
+            assert!(report
+                .actions
+                .iter()
+                .all(|a| matches!(a, Action::Reduce("A" | "B", _))));
+            let rules: Vec<_> = report.items.iter().map(|p| p.rule).collect();
+            assert_eq!(rules, ["A", "B"]);
+            assert_eq!(report.prefix, ["d", "e", "a"]);
+            assert_eq!(report.input, ["d", "e", "a"]);
+        }
+    }
+
+    #[test]
+    fn arithmetic() {
+        let mut grammar = grammars_tests::arithmetic();
+        grammar.precs.clear();
+        grammar.prod_precs.clear();
+        let slr = Slr::new(grammar);
+        let reports = slr.tables().conflict_report();
+        assert_eq!(reports.len(), slr.tables().conflicts().count());
+
+        let report = reports
+            .iter()
+            .find(|r| r.look == "+" && r.prefix == ["E", "+", "E"])
+            .unwrap();
+        assert_eq!(report.input, ["n", "+", "n"]);
+        assert!(matches!(
+            report.actions.as_slice(),
+            [Action::Shift(_), Action::Reduce("E", _)]
+        ));
+        let items: Vec<_> = report.items.iter().map(|p| (p.seq.1, p.point)).collect();
+        // E = E . + E | E = E + E .
+        assert_eq!(items, [(1, 1), (1, 3)]);
+    }
+
+    #[test]
+    fn entries() {
+        let rules = grammar_map! {
+            "S" -> "x",
+            "E" -> "E" "+" "E" | "n"
+        };
+        let grammar = Grammar::with_entries(["S", "E"], rules, "$").unwrap();
+        let lalr = Lalr::new(grammar);
+        let reports = lalr.tables().conflict_report();
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].prefix, ["E", "+", "E"]);
+        assert_eq!(reports[0].input, ["n", "+", "n"]);
+
+        let paths = lalr.tables().shortest_paths();
+        let entry = lalr.tables().entry(&"E").unwrap();
+        assert_eq!(paths[&entry], Vec::<&str>::new());
+        assert!(!paths.contains_key(&0));
+    }
+}
@@ -0,0 +1,507 @@
+use std::{fmt, rc::Rc};
+
+use crate::{ActTable, Action, Dfa, Map, Production, ReductMap, Tabler, Token};
+
+/// Magic bytes in the beginning of every serialized table
+pub const TABLE_MAGIC: &[u8; 4] = b"LRPT";
+/// Current version of the serialized table format. Tables from other versions are rejected.
+pub const TABLE_VERSION: u16 = 1;
+
+const SHIFT: u8 = 0;
+const GOTO: u8 = 1;
+const REDUCE: u8 = 2;
+const ACC: u8 = 3;
+const CONFLICT: u8 = 4;
+
+/// Maximum nesting of conflicting actions in a serialized table, so a malicious input can't
+/// exhaust the stack
+pub const MAX_CONFLICT_DEPTH: usize = 256;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TableError {
+    /// The input doesn't start with `TABLE_MAGIC`
+    BadMagic,
+    /// The table was serialized with another format version
+    UnsupportedVersion(u16),
+    /// The input ended in the middle of the table
+    UnexpectedEnd,
+    /// A symbol from the table isn't in the symbols list
+    UnknownSymbol(String),
+    /// The symbols list differs from the one used in serialization. Contains the index and the
+    /// expected symbol
+    SymbolMismatch(usize, String),
+    /// Invalid action tag
+    InvalidAction(u8),
+    /// Reduction to a undeclared production
+    InvalidProduction(usize),
+    /// A conflicting action in a state, over a symbol, where it isn't supported
+    UnsolvedConflict(usize, String),
+    /// Conflicting actions nested deeper than `MAX_CONFLICT_DEPTH`
+    DeepConflict,
+    /// A count or index above `u32::MAX`, not representable in the format
+    TooLarge(usize),
+}
+
+impl fmt::Display for TableError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BadMagic => f.write_str("not a serialized table"),
+            Self::UnsupportedVersion(v) => f.write_fmt(format_args!(
+                "unsupported table version {v}. expected {TABLE_VERSION}"
+            )),
+            Self::UnexpectedEnd => f.write_str("unexpected end of table"),
+            Self::UnknownSymbol(s) => f.write_fmt(format_args!("unknown symbol {s}")),
+            Self::SymbolMismatch(i, s) => {
+                f.write_fmt(format_args!("symbol {i} mismatch. expected {s}"))
+            }
+            Self::InvalidAction(tag) => f.write_fmt(format_args!("invalid action tag {tag}")),
+            Self::InvalidProduction(p) => f.write_fmt(format_args!("invalid production {p}")),
+            Self::UnsolvedConflict(state, sym) => f.write_fmt(format_args!(
+                "unsolved conflict in state {state} over {sym}"
+            )),
+            Self::DeepConflict => f.write_fmt(format_args!(
+                "conflicts nested deeper than {MAX_CONFLICT_DEPTH}"
+            )),
+            Self::TooLarge(n) => f.write_fmt(format_args!("{n} is too large for a table")),
+        }
+    }
+}
+
+/// Serializes an actions table. Symbols are stored as indexes in `symbols`, which must be the same
+/// (and in the same order) at deserialization. Productions are stored once, and referenced by
+/// reductions through their rule and index.
+///
+/// Format (little-endian integers):
+/// - magic `LRPT`, version `u16`
+/// - symbols: `u32` count, `u32` length + `Debug` name of each, for validation
+/// - eof: `u32` symbol
+/// - productions: `u32` count, each with rule `u32`, index `u32`, `u32` length + symbols
+/// - states: `u32` count, each with `u32` entries count + `u32` symbol and action
+/// - action: `u8` tag + `u32` state (shift, goto) or production (reduce), nothing (acc) or the
+///   two conflicting actions (conflict)
+/// # Errors
+/// If a symbol of `table` isn't in `symbols`, raises a `TableError::UnknownSymbol`. Tables not
+/// fitting in the format raise `TableError::TooLarge` or `TableError::DeepConflict`.
+pub fn encode_table<T>(table: &ActTable<T>, eof: &T, symbols: &[T]) -> Result<Vec<u8>, TableError>
+where
+    T: Ord + fmt::Debug,
+{
+    let ids: Map<&T, u32> = symbols.iter().zip(0..).collect();
+    let id = |sym: &T| {
+        ids.get(sym)
+            .copied()
+            .ok_or_else(|| TableError::UnknownSymbol(format!("{sym:?}")))
+    };
+
+    let mut prods = Map::new();
+    for act in table.iter().flat_map(Map::values).flat_map(Action::flatten) {
+        if let Action::Reduce(rule, prod) = act {
+            let key = (id(rule)?, prod.1);
+            let next = prods.len();
+            prods.entry(key).or_insert((next, prod.clone()));
+        }
+    }
+
+    let mut out = Vec::new();
+    out.extend_from_slice(TABLE_MAGIC);
+    out.extend_from_slice(&TABLE_VERSION.to_le_bytes());
+    push_len(&mut out, symbols.len())?;
+    for sym in symbols {
+        let name = format!("{sym:?}");
+        push_len(&mut out, name.len())?;
+        out.extend_from_slice(name.as_bytes());
+    }
+    push_u32(&mut out, id(eof)?);
+
+    let mut ordered: Vec<_> = prods.iter().collect();
+    ordered.sort_by_key(|(_, (i, _))| *i);
+    push_len(&mut out, ordered.len())?;
+    for ((rule, idx), (_, prod)) in ordered {
+        push_u32(&mut out, *rule);
+        push_len(&mut out, *idx)?;
+        push_len(&mut out, prod.0.len())?;
+        for sym in &prod.0 {
+            push_u32(&mut out, id(sym)?);
+        }
+    }
+
+    push_len(&mut out, table.len())?;
+    for row in table {
+        push_len(&mut out, row.len())?;
+        for (sym, act) in row {
+            push_u32(&mut out, id(sym)?);
+            encode_action(&mut out, act, 0, &|rule, prod| {
+                Ok(prods[&(id(rule)?, prod.1)].0)
+            })?;
+        }
+    }
+    Ok(out)
+}
+
+fn encode_action<T>(
+    out: &mut Vec<u8>,
+    act: &Action<T>,
+    depth: usize,
+    prod_id: &impl Fn(&T, &Production<T>) -> Result<usize, TableError>,
+) -> Result<(), TableError> {
+    match act {
+        Action::Shift(to) => {
+            out.push(SHIFT);
+            push_len(out, *to)?;
+        }
+        Action::Goto(to) => {
+            out.push(GOTO);
+            push_len(out, *to)?;
+        }
+        Action::Reduce(rule, prod) => {
+            out.push(REDUCE);
+            push_len(out, prod_id(rule, prod)?)?;
+        }
+        Action::Acc => out.push(ACC),
+        Action::Conflict(_, _) if depth == MAX_CONFLICT_DEPTH => {
+            return Err(TableError::DeepConflict)
+        }
+        Action::Conflict(a, b) => {
+            out.push(CONFLICT);
+            encode_action(out, a, depth + 1, prod_id)?;
+            encode_action(out, b, depth + 1, prod_id)?;
+        }
+    }
+    Ok(())
+}
+
+fn push_u32(out: &mut Vec<u8>, n: u32) {
+    out.extend_from_slice(&n.to_le_bytes());
+}
+
+fn push_len(out: &mut Vec<u8>, n: usize) -> Result<(), TableError> {
+    push_u32(out, u32::try_from(n).map_err(|_| TableError::TooLarge(n))?);
+    Ok(())
+}
+
+/// Deserializes an actions table and its eof symbol, encoded by `encode_table` with the same
+/// `symbols`.
+/// # Errors
+/// Raises a `TableError` when the input isn't a valid table for `symbols`.
+pub fn decode_table<T>(bytes: &[u8], symbols: &[T]) -> Result<(ActTable<T>, T), TableError>
+where
+    T: Clone + Ord + fmt::Debug,
+{
+    let mut reader = Reader { bytes };
+    if reader.take(TABLE_MAGIC.len())? != TABLE_MAGIC {
+        return Err(TableError::BadMagic);
+    }
+    let version = u16::from_le_bytes([reader.u8()?, reader.u8()?]);
+    if version != TABLE_VERSION {
+        return Err(TableError::UnsupportedVersion(version));
+    }
+
+    let count = reader.len()?;
+    for i in 0..count {
+        let len = reader.len()?;
+        let name = String::from_utf8_lossy(reader.take(len)?);
+        if symbols.get(i).map(|s| format!("{s:?}")).as_deref() != Some(&name) {
+            return Err(TableError::SymbolMismatch(i, name.into_owned()));
+        }
+    }
+    if count != symbols.len() {
+        return Err(TableError::SymbolMismatch(count, String::new()));
+    }
+    let eof = reader.symbol(symbols)?;
+
+    let mut prods = Vec::new();
+    for _ in 0..reader.len()? {
+        let rule = reader.symbol(symbols)?;
+        let idx = reader.len()?;
+        let body = (0..reader.len()?)
+            .map(|_| reader.symbol(symbols))
+            .collect::<Result<_, _>>()?;
+        prods.push((rule, Rc::new((body, idx))));
+    }
+
+    let mut table = ActTable::new();
+    for _ in 0..reader.len()? {
+        let mut row = Map::new();
+        for _ in 0..reader.len()? {
+            let sym = reader.symbol(symbols)?;
+            row.insert(sym, reader.action(&prods, 0)?);
+        }
+        table.push(row);
+    }
+    Ok((table, eof))
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], TableError> {
+        if self.bytes.len() < n {
+            return Err(TableError::UnexpectedEnd);
+        }
+        let (head, tail) = self.bytes.split_at(n);
+        self.bytes = tail;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> Result<u8, TableError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn len(&mut self) -> Result<usize, TableError> {
+        let bytes = self.take(4)?;
+        let n = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        Ok(n as usize)
+    }
+
+    fn symbol<T: Clone>(&mut self, symbols: &[T]) -> Result<T, TableError> {
+        let id = self.len()?;
+        symbols
+            .get(id)
+            .cloned()
+            .ok_or_else(|| TableError::UnknownSymbol(id.to_string()))
+    }
+
+    fn action<T: Clone>(
+        &mut self,
+        prods: &[(T, Rc<Production<T>>)],
+        depth: usize,
+    ) -> Result<Action<T>, TableError> {
+        match self.u8()? {
+            SHIFT => Ok(Action::Shift(self.len()?)),
+            GOTO => Ok(Action::Goto(self.len()?)),
+            REDUCE => {
+                let id = self.len()?;
+                let (rule, prod) = prods.get(id).ok_or(TableError::InvalidProduction(id))?;
+                Ok(Action::Reduce(rule.clone(), prod.clone()))
+            }
+            ACC => Ok(Action::Acc),
+            CONFLICT if depth == MAX_CONFLICT_DEPTH => Err(TableError::DeepConflict),
+            CONFLICT => Ok(Action::Conflict(
+                Box::new(self.action(prods, depth + 1)?),
+                Box::new(self.action(prods, depth + 1)?),
+            )),
+            tag => Err(TableError::InvalidAction(tag)),
+        }
+    }
+}
+
+/// Exports an actions table as JSON, with symbols written by their `Display` representation.
+/// Reductions are written as `{"reduce": [rule, index]}`, referencing the `productions` list.
+#[must_use]
+pub fn table_json<T>(table: &ActTable<T>, eof: &T) -> String
+where
+    T: Ord + fmt::Display,
+{
+    let mut prods = Map::new();
+    for act in table.iter().flat_map(Map::values).flat_map(Action::flatten) {
+        if let Action::Reduce(rule, prod) = act {
+            prods.insert((rule, prod.1), &prod.0);
+        }
+    }
+    let prods: Vec<_> = prods
+        .iter()
+        .map(|((rule, idx), body)| {
+            let body: Vec<_> = body.iter().map(json_sym).collect();
+            format!(
+                "{{\"rule\":{},\"index\":{idx},\"body\":[{}]}}",
+                json_sym(rule),
+                body.join(",")
+            )
+        })
+        .collect();
+    let states: Vec<_> = table
+        .iter()
+        .map(|row| {
+            let entries: Vec<_> = row
+                .iter()
+                .map(|(sym, act)| format!("{}:{}", json_sym(sym), action_json(act)))
+                .collect();
+            format!("{{{}}}", entries.join(","))
+        })
+        .collect();
+    format!(
+        "{{\"version\":{TABLE_VERSION},\"eof\":{},\"productions\":[{}],\"states\":[{}]}}",
+        json_sym(eof),
+        prods.join(","),
+        states.join(",")
+    )
+}
+
+fn action_json<T: fmt::Display>(act: &Action<T>) -> String {
+    match act {
+        Action::Shift(to) => format!("{{\"shift\":{to}}}"),
+        Action::Goto(to) => format!("{{\"goto\":{to}}}"),
+        Action::Reduce(rule, prod) => format!("{{\"reduce\":[{},{}]}}", json_sym(rule), prod.1),
+        Action::Acc => "\"acc\"".to_string(),
+        Action::Conflict(a, b) => {
+            format!("{{\"conflict\":[{},{}]}}", action_json(a), action_json(b))
+        }
+    }
+}
+
+fn json_sym<T: fmt::Display>(sym: &T) -> String {
+    let mut out = String::from("\"");
+    for c in sym.to_string().chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+impl<T> Tabler<T>
+where
+    T: PartialEq + PartialOrd + Ord + Clone + fmt::Debug,
+{
+    /// Serializes the actions table with `encode_table`, using the grammar's symbols (in
+    /// `Grammar::symbols` order) as the symbols list.
+    /// # Panics
+    /// The table doesn't fit in the format, see `TableError::TooLarge` and
+    /// `TableError::DeepConflict`.
+    #[must_use]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let symbols: Vec<_> = self.grammar.symbols().collect();
+        encode_table(&self.actions, &self.eof(), &symbols)
+            .unwrap_or_else(|e| panic!("table not serializable: {e}"))
+    }
+
+    /// The grammar's end of input symbol
+    /// # Panics
+    /// Never.
+    #[must_use]
+    pub fn eof(&self) -> T {
+        self.grammar
+            .basis
+            .look
+            .first()
+            .expect("basis has the eof as lookahead")
+            .clone()
+    }
+}
+
+impl<T> Tabler<T>
+where
+    T: PartialEq + PartialOrd + Ord + Clone + fmt::Debug + fmt::Display,
+{
+    /// Exports the actions table with `table_json`
+    #[must_use]
+    pub fn to_json(&self) -> String {
+        table_json(&self.actions, &self.eof())
+    }
+}
+
+impl<'a, T, M, I: Iterator<Item = Token<T, M>>> Dfa<'a, T, M, I>
+where
+    T: Clone,
+    M: fmt::Debug + Clone + Ord,
+{
+    /// Creates a `Dfa` from a table serialized by `encode_table` or `Tabler::to_bytes`, with the
+    /// same `symbols`.
+    /// # Errors
+    /// The same of `decode_table`
+    pub fn from_bytes(
+        buffer: I,
+        bytes: &[u8],
+        symbols: &[M],
+        reductors: ReductMap<'a, T, M>,
+    ) -> Result<Self, TableError> {
+        let (table, eof) = decode_table(bytes, symbols)?;
+        Ok(Self::new(buffer, table, reductors, eof))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        decode_table, encode_table, grammars_tests, Dfa, Lalr, Parser, TableError, Token,
+        TABLE_VERSION,
+    };
+
+    #[test]
+    fn roundtrip() {
+        for (grammar, inputs, name) in grammars_tests::GRAMMARS {
+            let mut lalr = Lalr::new(grammar());
+            let symbols: Vec<_> = lalr.tables().grammar.symbols().collect();
+            let bytes = lalr.tables().to_bytes();
+            let (table, eof) = decode_table(&bytes, &symbols).unwrap();
+            assert_eq!(table, lalr.tables().actions, "{name}");
+            assert_eq!(eof, "$");
+
+            lalr.reduce_equals();
+            let bytes = lalr.tables().to_bytes();
+            for input in *inputs {
+                let buffer = input.iter().map(|&t| Token::new((), t));
+                let mut dfa = Dfa::from_bytes(
+                    buffer.clone(),
+                    &bytes,
+                    &symbols,
+                    lalr.empty::<std::iter::Empty<_>>(),
+                )
+                .unwrap();
+                let valid = lalr.validate(buffer.clone());
+                assert_eq!(dfa.parse(buffer).is_ok(), valid, "{name}: {input:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn invalid() {
+        let lalr = Lalr::new(grammars_tests::dragon_book());
+        let symbols: Vec<_> = lalr.tables().grammar.symbols().collect();
+        let bytes = lalr.tables().to_bytes();
+
+        assert_eq!(
+            decode_table(&bytes[1..], &symbols),
+            Err(TableError::BadMagic)
+        );
+        let mut old = bytes.clone();
+        old[4..6].copy_from_slice(&(TABLE_VERSION + 1).to_le_bytes());
+        assert_eq!(
+            decode_table(&old, &symbols),
+            Err(TableError::UnsupportedVersion(TABLE_VERSION + 1))
+        );
+        assert_eq!(
+            decode_table(&bytes[..bytes.len() - 1], &symbols),
+            Err(TableError::UnexpectedEnd)
+        );
+        assert!(matches!(
+            decode_table(&bytes, &symbols[1..]),
+            Err(TableError::SymbolMismatch(0, _))
+        ));
+        assert_eq!(
+            encode_table(&lalr.tables().actions, &"$", &symbols[1..]),
+            Err(TableError::UnknownSymbol(format!("{:?}", symbols[0])))
+        );
+    }
+
+    #[test]
+    fn deep_conflict() {
+        let symbols = ["$"];
+        let mut bytes = encode_table(&Vec::new(), &"$", &symbols).unwrap();
+        // a single state, with a single entry of nested conflicts
+        bytes.truncate(bytes.len() - 4);
+        for n in [1u32, 1, 0] {
+            bytes.extend_from_slice(&n.to_le_bytes());
+        }
+        bytes.resize(bytes.len() + 1_000_000, 4);
+        assert_eq!(
+            decode_table(&bytes, &symbols),
+            Err(TableError::DeepConflict)
+        );
+    }
+
+    #[test]
+    fn json() {
+        let lalr = Lalr::new(grammars_tests::dragon_book());
+        let json = lalr.tables().to_json();
+        assert!(json.starts_with(&format!("{{\"version\":{TABLE_VERSION},\"eof\":\"$\"")));
+        assert!(json.contains("{\"rule\":\"C\",\"index\":1,\"body\":[\"d\"]}"));
+        assert!(json.contains("\"$\":\"acc\""));
+    }
+}
@@ -37,7 +37,7 @@ where
     T: PartialEq + Ord + Clone + Debug,
 {
     pub fn proc_closures_first_row(&mut self) {
-        let start = self.prop_closure(State::from([self.table.basis_pos()]));
+        let start = self.prop_closure(self.table.basis_state());
         self.table.kernels.insert(State::new(), 0);
         self.table.states.push(start);
     }
@@ -145,6 +145,7 @@ where
             }
             self.table.actions.push(map);
         }
+        self.table.solve_conflicts();
     }
 
     #[must_use]
@@ -220,4 +221,28 @@ mod tests {
             assert!(slr.validate(to_tokens(input.iter().cloned())));
         }
     }
+
+    #[test]
+    pub fn declarations() {
+        let slr = Slr::new(grammars_tests::declarations());
+        assert_eq!(0, slr.tables().conflicts().count());
+
+        for input in grammars_tests::DECLARATIONS_INPUTS {
+            assert!(slr.validate(to_tokens(input.iter().cloned())));
+        }
+    }
+
+    #[test]
+    pub fn arithmetic() {
+        let slr = Slr::new(grammars_tests::arithmetic());
+        assert_eq!(0, slr.tables().conflicts().count());
+
+        for input in grammars_tests::ARITHMETIC_INPUTS {
+            assert!(slr.validate(to_tokens(input.iter().cloned())));
+        }
+
+        for input in grammars_tests::NON_ASSOC_ARITHMETIC_INPUTS {
+            assert!(!slr.validate(to_tokens(input.iter().cloned())));
+        }
+    }
 }
@@ -1,5 +1,5 @@
-use crate::{transitive, ActTable, Action, Grammar, Map, Position, Set, State, Table};
-use std::fmt::Debug;
+use crate::{transitive, ActTable, Action, Assoc, Grammar, Map, Position, Set, State, Table};
+use std::{cmp::Ordering, fmt::Debug};
 
 #[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Tabler<T>
@@ -7,6 +7,7 @@ where
     T: PartialEq + PartialOrd + Ord + Clone + Debug,
 {
     pub grammar: Grammar<T>,
+    pub nullable: Set<T>,
     pub first: Table<T>,
     pub follow: Table<T>,
     pub actions: ActTable<T>,
@@ -22,12 +23,14 @@ where
     pub fn new(grammar: Grammar<T>) -> Self {
         let mut buf = Self {
             grammar,
+            nullable: Set::default(),
             first: Table::default(),
             follow: Table::default(),
             actions: ActTable::default(),
             states: Vec::default(),
             kernels: Map::default(),
         };
+        buf.nullable = buf.gen_nullable();
         buf.first = buf.gen_first();
         buf.proc_first();
         buf.follow = buf.gen_follow();
@@ -35,28 +38,46 @@ where
         buf
     }
 
+    /// Generates the set of non-terminals that can derive an empty sequence. I.e:
+    /// A = . -> {A}
+    /// A = B C -> {A} if B and C are nullable
+    #[must_use]
+    pub fn gen_nullable(&self) -> Set<T> {
+        transitive(Set::new(), |nullable| {
+            self.grammar
+                .rules()
+                .filter(|r| {
+                    r.prods()
+                        .any(|rc| rc.0.iter().all(|sym| nullable.contains(sym)))
+                })
+                .map(|r| r.name.clone())
+                .collect()
+        })
+    }
+
+    #[must_use]
+    pub fn is_nullable(&self, sym: &T) -> bool {
+        self.nullable.contains(sym)
+    }
+
     /// Generates the first FIRST set iteration for the given grammar.
-    /// # Panics
-    /// Never.
+    /// A = B C d -> {A: B, C, d} if B and C are nullable
     #[must_use]
     pub fn gen_first(&self) -> Table<T> {
         let mut table = Table::new();
         for rule in self.grammar.rules() {
-            let firsts = rule
-                .prods
-                .iter()
-                .filter_map(|rc| {
-                    assert!(
-                        !rc.0.is_empty(),
-                        "productions should have at least one symbol"
-                    );
-                    if rc.0[0] == rule.name {
-                        None
-                    } else {
-                        Some(rc.0[0].clone())
+            let mut firsts = Set::new();
+            for rc in rule.prods() {
+                for sym in &rc.0 {
+                    // A = A . . . -> {A: FIRST(A)} -> {A: A} -> {}
+                    if sym != &rule.name {
+                        firsts.insert(sym.clone());
                     }
-                })
-                .collect();
+                    if !self.is_nullable(sym) {
+                        break;
+                    }
+                }
+            }
             table.insert(rule.name.clone(), firsts);
         }
         table
@@ -73,27 +94,31 @@ where
         for rule in self.grammar.rules() {
             for prod in rule.prods() {
                 let prod = &prod.0;
-                for term_idx in 0..prod.len() - 1 {
-                    // A = . . . A a -> {A: FIRST(A)} -> {A: A} -> {}
-                    if !self.grammar.is_terminal(&prod[term_idx]) {
-                        let entry = table.entry(prod[term_idx].clone()).or_default();
-                        if self.grammar.is_terminal(&prod[term_idx + 1]) {
+                for (term_idx, term) in prod.iter().enumerate() {
+                    if self.grammar.is_terminal(term) {
+                        continue;
+                    }
+                    let entry = table.entry(term.clone()).or_default();
+                    let mut nullable_tail = true;
+                    for next in &prod[term_idx + 1..] {
+                        if self.grammar.is_terminal(next) {
                             // A = . . . T a -> {T: a}
-                            entry.insert(prod[term_idx + 1].clone());
+                            entry.insert(next.clone());
                         } else {
                             // A = . . . T B -> {T: FIRST(B)}
-                            entry.extend(self.first[&prod[term_idx + 1]].clone());
+                            entry.extend(self.first[next].clone());
+                        }
+                        // A = . . . T B c -> {T: FIRST(B), c} if B is nullable
+                        if !self.is_nullable(next) {
+                            nullable_tail = false;
+                            break;
                         }
                     }
-                }
-                let last = prod.last().unwrap();
-                // A = . . . . T -> {T: FOLLOW(A)}
-                // But if A = . . . . A -> {A: FOLLOW(A)} -> {A: A} -> {}
-                if !self.grammar.is_terminal(last) && last != &rule.name {
-                    table
-                        .entry(last.clone())
-                        .or_default()
-                        .insert(rule.name.clone());
+                    // A = . . . . T -> {T: FOLLOW(A)}
+                    // But if A = . . . . A -> {A: FOLLOW(A)} -> {A: A} -> {}
+                    if nullable_tail && term != &rule.name {
+                        entry.insert(rule.name.clone());
+                    }
                 }
             }
         }
@@ -102,6 +127,9 @@ where
 
     pub fn proc_first(&mut self) {
         self.first = transitive(self.first.clone(), |t| self.first_step(&t));
+        for firsts in self.first.values_mut() {
+            firsts.retain(|t| self.grammar.is_terminal(t));
+        }
         // FIRST must be a subset of TERMINALS
         debug_assert!(self
             .first
@@ -112,6 +140,9 @@ where
 
     pub fn proc_follow(&mut self) {
         self.follow = transitive(self.follow.clone(), |t| self.follow_step(&t));
+        for follows in self.follow.values_mut() {
+            follows.retain(|t| self.grammar.is_terminal(t));
+        }
         // FOLLOW must be a subset of TERMINALS
         debug_assert!(self
             .follow
@@ -128,9 +159,9 @@ where
         for (name, firsts) in input {
             table.insert(name.clone(), Set::new());
             for first in firsts {
-                if self.grammar.is_terminal(first) {
-                    table.get_mut(name).unwrap().insert(first.clone());
-                } else {
+                // the nonterminals are kept until the fixpoint, so the cycles settle
+                table.get_mut(name).unwrap().insert(first.clone());
+                if !self.grammar.is_terminal(first) {
                     table.get_mut(name).unwrap().extend(
                         input
                             .get(first)
@@ -157,9 +188,8 @@ where
             table.insert(noterm.clone(), Set::new());
             for term in terms {
                 let entry = table.get_mut(noterm).unwrap();
-                if self.grammar.is_terminal(term) {
-                    entry.insert(term.clone());
-                } else if let Some(set) = input.get(term) {
+                entry.insert(term.clone());
+                if let Some(set) = input.get(term) {
                     entry.extend(set.clone());
                 }
             }
@@ -175,6 +205,42 @@ where
         self.grammar.basis()
     }
 
+    /// Kernel of the state 0: the basis position over each production of the start rule. More
+    /// than one with entry points (see `Grammar::with_entries`).
+    #[must_use]
+    pub fn basis_state(&self) -> State<T> {
+        let basis = self.basis_pos();
+        self.grammar.rules[&basis.rule]
+            .prods()
+            .map(|prod| Position::new(basis.rule.clone(), prod, 0, basis.look.clone()))
+            .collect()
+    }
+
+    /// Main start symbol: the first entry point or the start rule
+    #[must_use]
+    pub fn start(&self) -> T {
+        self.grammar
+            .entries
+            .first()
+            .map_or_else(|| self.basis_pos().rule, |(start, _)| start.clone())
+    }
+
+    /// Initial state of the entry point `start`, once the actions are built. Without entry
+    /// points, the start rule, or the symbol it derives, starts from the state 0.
+    #[must_use]
+    pub fn entry(&self, start: &T) -> Option<usize> {
+        let Some((_, marker)) = self.grammar.entries.iter().find(|(s, _)| s == start) else {
+            let basis = self.basis_pos();
+            let derived = basis.seq.0.len() == 1 && &basis.seq.0[0] == start;
+            return (self.grammar.entries.is_empty() && (&basis.rule == start || derived))
+                .then_some(0);
+        };
+        match self.actions.first()?.get(marker)? {
+            Action::Shift(state) => Some(*state),
+            _ => None,
+        }
+    }
+
     #[must_use]
     pub fn first_of(&self, items: &Set<T>) -> Set<T> {
         let mut firsts = Set::new();
@@ -188,6 +254,27 @@ where
         firsts
     }
 
+    /// Returns the FIRST set of a symbols sequence, followed by `look` if all of them are
+    /// nullable. I.e:
+    /// `first_seq`([B c], {$}) = FIRST(B) + {c} if B is nullable
+    /// `first_seq`([], {$}) = {$}
+    #[must_use]
+    pub fn first_seq(&self, seq: &[T], look: &Set<T>) -> Set<T> {
+        let mut firsts = Set::new();
+        for sym in seq {
+            if let Some(first) = self.first.get(sym) {
+                firsts.extend(first.iter().cloned());
+            } else {
+                firsts.insert(sym.clone());
+            }
+            if !self.is_nullable(sym) {
+                return firsts;
+            }
+        }
+        firsts.extend(look.iter().cloned());
+        firsts
+    }
+
     /// Filters by positions that contains some symbol at the top. I.e:
     /// `sym_filter`({ [S -> .C C; $], [S -> .d C; $]}, C) = { [S -> .C C; $] }
     #[must_use]
@@ -211,6 +298,111 @@ where
             .filter(|a| matches!(a, Action::Conflict(..)))
     }
 
+    /// Removes the reductions forbidden by follow restrictions and solves the shift/reduce
+    /// conflicts using the grammar's precedences. Conflicts between reductions, or where the
+    /// terminal or the production has no precedence, are kept.
+    pub fn solve_conflicts(&mut self) {
+        for row in &mut self.actions {
+            let solved: Vec<_> = row
+                .iter()
+                .filter(|(_, act)| matches!(act, Action::Conflict(..) | Action::Reduce(..)))
+                .map(|(term, act)| (term.clone(), Self::solve(&self.grammar, term, act)))
+                .collect();
+            for (term, act) in solved {
+                if let Some(act) = act {
+                    row.insert(term, act);
+                } else {
+                    row.remove(&term);
+                }
+            }
+        }
+    }
+
+    /// Solves a conflicting action over `term`. The reductions forbidden by a follow restriction
+    /// (see `Grammar::restricts`) are removed, even outside conflicts. Then, the shift is solved
+    /// against each reduction following yacc's rules:
+    /// - The production with higher precedence than `term` is reduced
+    /// - The production with lower precedence than `term` is shifted
+    /// - With the same precedence, `Assoc::Left` reduces, `Assoc::Right` shifts and
+    ///   `Assoc::NonAssoc` removes both actions
+    ///
+    /// The shift is kept unless a reduction wins over it, or is non-associative. The kept
+    /// reductions, and the shift when a production has no precedence, remain as a conflict.
+    /// Returns `None` when nothing is left.
+    #[must_use]
+    pub fn solve(grammar: &Grammar<T>, term: &T, act: &Action<T>) -> Option<Action<T>> {
+        let act = &Self::unrestricted(grammar, term, act)?;
+        // the same action can appear more than once in a conflict tree
+        let mut seen = Set::new();
+        let acts: Vec<_> = act
+            .flatten()
+            .into_iter()
+            .filter(|act| seen.insert(*act))
+            .collect();
+        if let [single] = acts.as_slice() {
+            return Some((*single).clone());
+        }
+        let (shifts, others): (Vec<_>, Vec<_>) = acts
+            .into_iter()
+            .partition(|act| matches!(act, Action::Shift(_)));
+        let [shift] = shifts.as_slice() else {
+            return Some(act.clone());
+        };
+        let mut shifted = true;
+        let mut kept = Vec::new();
+        for other in &others {
+            let Action::Reduce(rule, prod) = other else {
+                return Some(act.clone());
+            };
+            let (Some(term_prec), Some(prod_prec)) =
+                (grammar.term_prec(term), grammar.prod_prec(rule, prod))
+            else {
+                kept.push((*other).clone());
+                continue;
+            };
+            let reduces = match prod_prec.0.cmp(&term_prec.0) {
+                Ordering::Greater => true,
+                Ordering::Less => false,
+                Ordering::Equal => match term_prec.1 {
+                    Assoc::Left => true,
+                    Assoc::Right => false,
+                    Assoc::NonAssoc => {
+                        shifted = false;
+                        false
+                    }
+                },
+            };
+            if reduces {
+                shifted = false;
+                kept.push((*other).clone());
+            }
+        }
+        if shifted && kept.len() == others.len() {
+            return Some(act.clone());
+        }
+        let shift = shifted.then(|| (*shift).clone());
+        shift
+            .into_iter()
+            .chain(kept)
+            .reduce(|a, b| Action::Conflict(a.into(), b.into()))
+    }
+
+    /// `act` without the reductions that can't be followed by `term`, keeping the shape of the
+    /// conflict tree. Returns `None` if nothing is left.
+    fn unrestricted(grammar: &Grammar<T>, term: &T, act: &Action<T>) -> Option<Action<T>> {
+        match act {
+            Action::Reduce(rule, prod) if grammar.restricts(rule, prod, term) => None,
+            Action::Conflict(a, b) => match (
+                Self::unrestricted(grammar, term, a),
+                Self::unrestricted(grammar, term, b),
+            ) {
+                (Some(a), Some(b)) => Some(Action::Conflict(a.into(), b.into())),
+                (a, b) => a.or(b),
+            },
+            _ => Some(act.clone()),
+        }
+    }
+
     pub fn reduce_equals(&mut self) {
         let (travel, new_actions) = self.reduced_actions();
         self.actions = new_actions;
@@ -258,7 +450,7 @@ where
 
 #[cfg(test)]
 mod tests {
-    use crate::{grammars_tests, Map, Set, Tabler};
+    use crate::{grammar_map, grammars_tests, Action, Assoc, Grammar, Map, Set, Tabler};
 
     #[test]
     pub fn dragon_book() {
@@ -462,4 +654,99 @@ mod tests {
             ])
         );
     }
+
+    #[test]
+    pub fn declarations() {
+        let table = Tabler::new(grammars_tests::declarations());
+
+        assert_eq!(table.nullable, Set::from(["Decls", "Mut", "S", "Type"]));
+
+        assert_eq!(
+            table.first,
+            Map::from([
+                ("Decl", Set::from(["let"])),
+                ("Decls", Set::from(["let"])),
+                ("Mut", Set::from(["mut"])),
+                ("Pat", Set::from(["id", "mut"])),
+                ("S", Set::from(["let"])),
+                ("Type", Set::from([":"])),
+            ])
+        );
+
+        assert_eq!(
+            table.follow,
+            Map::from([
+                ("Decl", Set::from(["$", "let"])),
+                ("Decls", Set::from(["$", "let"])),
+                ("Mut", Set::from(["id"])),
+                ("Pat", Set::from([":", ";"])),
+                ("S", Set::from(["$"])),
+                ("Type", Set::from([";"])),
+            ])
+        );
+    }
+
+    #[test]
+    fn cycles() {
+        // FIRST and FOLLOW cycles through three rules: A, B, C
+        let rules = grammar_map! {
+            "S" -> "A",
+            "A" -> "B" "a" | "x" "B",
+            "B" -> "C" "b" | "y" "C",
+            "C" -> "A" "c" | "z" | "w" "A"
+        };
+        let table = Tabler::new(Grammar::new("S", rules, "$").unwrap());
+        let firsts = Set::from(["w", "x", "y", "z"]);
+        assert_eq!(
+            table.first,
+            Map::from([
+                ("A", firsts.clone()),
+                ("B", firsts.clone()),
+                ("C", firsts.clone()),
+                ("S", firsts),
+            ])
+        );
+        let follows = Set::from(["$", "a", "b", "c"]);
+        assert_eq!(
+            table.follow,
+            Map::from([
+                ("A", follows.clone()),
+                ("B", follows.clone()),
+                ("C", follows),
+                ("S", Set::from(["$"])),
+            ])
+        );
+    }
+
+    #[test]
+    fn solve_many_reductions() {
+        let rules = grammar_map! {
+            "S" -> "E",
+            "E" -> "E" "+" "E" | "E" "*" "E" | "x" "E" | "n"
+        };
+        let grammar = Grammar::new("S", rules, "$")
+            .unwrap()
+            .with_level(Assoc::Left, ["+"])
+            .with_level(Assoc::Left, ["*"]);
+        let reduce = |i| Action::Reduce("E", grammar.rules["E"].prods().nth(i).unwrap());
+        let conflict = |a: Action<_>, b: Action<_>| Action::Conflict(a.into(), b.into());
+        let act = conflict(Action::Shift(1), conflict(reduce(0), reduce(1)));
+        // both reductions win over the shift: a reduce/reduce conflict remains
+        assert_eq!(
+            Tabler::solve(&grammar, &"+", &act),
+            Some(conflict(reduce(0), reduce(1)))
+        );
+        // the shift wins over the first one, and loses against the second
+        assert_eq!(Tabler::solve(&grammar, &"*", &act), Some(reduce(1)));
+        // a production without precedence keeps the conflict with the shift
+        let act = conflict(Action::Shift(1), conflict(reduce(0), reduce(2)));
+        assert_eq!(
+            Tabler::solve(&grammar, &"*", &act),
+            Some(conflict(Action::Shift(1), reduce(2)))
+        );
+        assert_eq!(
+            Tabler::solve(&grammar, &"+", &act),
+            Some(conflict(reduce(0), reduce(2)))
+        );
+    }
 }
@@ -54,6 +54,7 @@ if exists("b:current_syntax")
 endif
 
 syn keyword wopKeyword use alias
+syn match wopKeyword "%\(left\|right\|nonassoc\|prec\)\>"
 syn match wopOperator "[=\|\*(\-\>)|(\:\:)|\:]"
 syn match wopString '\"[^\"]*\"'
 syn match wopIdentifier "[a-zA-Z_]\w*"
@@ -1,4 +1,5 @@
 use crate::{Ast, Gramem, RulePipe, Sym};
+use lrp::Assoc;
 use std::{
     collections::{HashMap, HashSet},
     fmt::Write,
@@ -16,6 +17,7 @@ pub struct Builder {
     pub aliases: HashMap<String, SrcRef>,
     pub rules: HashMap<String, RuleBuild>,
     pub imports: Vec<SrcRef>,
+    pub precedences: Vec<(Assoc, Vec<SrcRef>)>,
     pub entry_type: String,
 }
 
@@ -35,6 +37,8 @@ pub struct ProductionBuild {
     /// return type of `self.codeblock`
     pub ty: SrcRef,
     pub aliases: Vec<ItemAlias>,
+    /// terminal defining the production precedence (`%prec`)
+    pub prec: Option<SrcRef>,
 }
 
 impl ProductionBuild {
@@ -111,6 +115,7 @@ impl Builder {
                 }
                 Ast::Import(decl) => self.use_decl(*decl),
                 Ast::Alias(tk, alias) => self.token_decl(*tk, *alias, src),
+                Ast::Precedence(assoc, terms) => self.precedence_decl(*assoc, terms),
                 c => unreachable!("unexpected {c:?} in code builder"),
             }
         }
@@ -130,6 +135,7 @@ impl Builder {
                 codeblock: prod.1,
                 ty: rule_ty,
                 aliases: Vec::new(),
+                prec: prod.2,
             };
             rules.extend(self.get_production(&[base], &prod.0, src));
         }
@@ -198,7 +204,7 @@ impl Builder {
                 Sym::Rule => match item.item.item {
                     Ast::Rule(ref variants) => {
                         let mut prods = Vec::new();
-                        for (variant, a) in variants {
+                        for (variant, a, _) in variants {
                             assert_eq!(
                                 a,
                                 &SrcRef::new(0, 0),
@@ -307,6 +313,45 @@ impl Builder {
         self.imports.push(decl);
     }
 
+    fn precedence_decl(&mut self, assoc: Assoc, terms: &[SrcRef]) {
+        self.precedences.push((assoc, terms.to_vec()));
+    }
+
+    /// Returns the symbol definition for a gramem (its alias, if any)
+    fn definition<'a>(&self, gramem: SrcRef, src: &'a str) -> &'a str {
+        let def = self.aliases.get(gramem.from_source(src)).unwrap_or(&gramem);
+        &src[def.start..def.end]
+    }
+
+    /// Returns an expression that applies the precedence declarations to a `grammar` binding
+    /// # Panics
+    /// Never.
+    #[must_use]
+    pub fn dump_precedence(&self, src: &str) -> String {
+        let mut out = "{\n".to_string();
+        self.imports.iter().for_each(|i| {
+            writeln!(out, "\tuse {};", i.from_source(src)).unwrap();
+        });
+        out.push_str("\tgrammar");
+        for (assoc, terms) in &self.precedences {
+            write!(out, "\n\t\t.with_level(lrp::Assoc::{assoc:?}, [").unwrap();
+            for term in terms {
+                write!(out, "{}, ", self.definition(*term, src)).unwrap();
+            }
+            out.push_str("])");
+        }
+        for (r_name, prods) in &self.rules {
+            for (i, prod) in prods.iter().enumerate() {
+                if let Some(prec) = prod.prec {
+                    let term = self.definition(prec, src);
+                    write!(out, "\n\t\t.with_prec({r_name}, {i}, {term})").unwrap();
+                }
+            }
+        }
+        out.push_str("\n}");
+        out
+    }
+
     /// Returns an expressions that returns a `RuleMap`
     /// # Panics
     /// Never.
@@ -377,14 +422,14 @@ impl Builder {
             }
             writeln!(
                     out,
-                    "\tfn lrp_wop_{name}_{i}(toks: &[{entry_type}]) -> lrp::Meta<{ty}> {{\n\t\tlrp::Meta::new({{ {item_aliases} {}}}, lrp::Span::new(toks[0].item.span.start, toks.last().unwrap().item.span.end))\n\t}}",
+                    "\tfn lrp_wop_{name}_{i}(toks: Vec<{entry_type}>) -> lrp::Meta<{ty}> {{\n\t\tlrp::Meta::new({{ {item_aliases} {}}}, lrp::Span::new(toks[0].item.span.start, toks.last().unwrap().item.span.end))\n\t}}",
                     prod.codeblock.from_source(src).strip_prefix("->").unwrap().strip_suffix('%').unwrap()
                 )
                 .unwrap();
         }
         write!(out, "\tmap.insert({name}, vec![").unwrap();
         for (i, _) in prods.iter().enumerate() {
-            write!(out, "lrp_wop_{name}_{i}, ").unwrap();
+            write!(out, "lrp::reductor(lrp_wop_{name}_{i}), ").unwrap();
         }
         out.push_str("\t]);\n\n");
     }
@@ -1,6 +1,6 @@
 use builder::SrcRef;
 use logos::Logos;
-use lrp::{Meta, Span};
+use lrp::{Assoc, Meta, Span};
 
 pub mod builder;
 
@@ -8,7 +8,7 @@ pub mod builder;
 pub enum Ast {
     Token(Sym),
     EntryPoint(Box<Gramem>),
-    Program(Vec<Gramem /* Ast::RuleDecl | Ast::Import | Ast::Alias */>),
+    Program(Vec<Gramem /* Ast::RuleDecl | Ast::Import | Ast::Alias | Ast::Precedence */>),
     RuleDecl(RuleDecl),
     Rule(Vec<RulePipe>),
     RulePipe(Vec<Gramem>),
@@ -21,6 +21,7 @@ pub enum Ast {
     Import(SrcRef),
     Alias(SrcRef, SrcRef),
     IdentPath(SrcRef),
+    Precedence(Assoc, Vec<SrcRef>),
 }
 
 impl Ast {
@@ -34,7 +35,11 @@ impl Ast {
 }
 
 pub type RuleDecl = (SrcRef, SrcRef, Vec<RulePipe>);
-pub type RulePipe = (Vec<Gramem>, /* codeblock */ SrcRef);
+pub type RulePipe = (
+    Vec<Gramem>,
+    /* codeblock */ SrcRef,
+    /* precedence */ Option<SrcRef>,
+);
 pub type Gramem = Token<Meta<Ast>, Sym>;
 
 #[derive(Logos, Debug, PartialEq, PartialOrd, Clone, Copy, Ord, Eq)]
@@ -45,6 +50,18 @@ pub enum Sym {
     #[token("use")]
     UseWord,
 
+    #[token("%left")]
+    LeftWord,
+
+    #[token("%right")]
+    RightWord,
+
+    #[token("%nonassoc")]
+    NonAssocWord,
+
+    #[token("%prec")]
+    PrecWord,
+
     #[token("=")]
     Assign,
 
@@ -115,6 +132,8 @@ pub enum Sym {
     RuleDecl,
     IdentPath,
     RuleItem,
+    Precedence,
+    Prec,
 }
 
 use lrp::{Dfa, Parser, Slr, Token};
@@ -129,16 +148,19 @@ pub fn lexer<'source>(
 }
 
 #[must_use]
-pub fn build_parser<I: Iterator<Item = Gramem>>(buffer: I) -> Dfa<Meta<Ast>, Sym, I> {
+pub fn build_parser<I: Iterator<Item = Gramem>>(buffer: I) -> Dfa<'static, Meta<Ast>, Sym, I> {
     let parser = Slr::new(out::grammar());
-    parser.dfa(buffer, out::reduct_map())
+    parser
+        .dfa(&Sym::EntryPoint, buffer, out::reduct_map())
+        .spanned()
 }
 
 #[cfg(test)]
 mod tests {
     use logos::Logos;
+    use lrp::Assoc;
 
-    use crate::Sym;
+    use crate::{builder::Builder, Sym};
     #[test]
     fn strings() {
         let lex = Sym::lexer(
@@ -175,4 +197,44 @@ mod tests {
     fn bootstrap() {
         Sym::lexer(include_str!("wop.grammar")).for_each(|tk| assert_ne!(tk, Sym::Error));
     }
+
+    #[test]
+    fn precedence() {
+        let src = r#"
+            use crate::Sym::*;
+            alias "+" Add;
+            alias "*" Mul;
+            %left "+";
+            %left "*";
+            %right Neg;
+            Expr: Ast = Expr "+" Expr -> { Ast::Add }%
+                | Expr "*" Expr -> { Ast::Mul }%
+                | "-" Expr %prec Neg -> { Ast::Neg }%
+                | Num -> { Ast::Num }%;
+        "#;
+        let builder: Builder = src.parse().unwrap();
+        let levels: Vec<_> = builder
+            .precedences
+            .iter()
+            .map(|(assoc, terms)| {
+                let terms: Vec<_> = terms.iter().map(|t| t.from_source(src)).collect();
+                (*assoc, terms)
+            })
+            .collect();
+        assert_eq!(
+            levels,
+            [
+                (Assoc::Left, vec!["\"+\""]),
+                (Assoc::Left, vec!["\"*\""]),
+                (Assoc::Right, vec!["Neg"]),
+            ]
+        );
+        let precs: Vec<_> = builder.rules["Expr"].iter().map(|p| p.prec).collect();
+        assert_eq!(precs[..2], [None, None]);
+        assert_eq!(precs[2].as_ref().map(|p| p.from_source(src)), Some("Neg"));
+        assert_eq!(
+            builder.dump_precedence(src),
+            "{\n\tuse crate::Sym::*;\n\tgrammar\n\t\t.with_level(lrp::Assoc::Left, [Add, ])\n\t\t.with_level(lrp::Assoc::Left, [Mul, ])\n\t\t.with_level(lrp::Assoc::Right, [Neg, ])\n\t\t.with_prec(Expr, 2, Neg)\n}"
+        );
+    }
 }
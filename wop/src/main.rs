@@ -20,8 +20,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let res = match dfa.trace(|st| println!("{:?}", st.stack_fmt())) {
         Err(e) => {
-            println!("FATAL: {e}");
-            println!("source:");
+            println!("{}", e.diagnostic(&file));
             Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, "impossible to parse").into())
         }
         Ok(p) => {
@@ -51,17 +50,19 @@ use lrp::{{Grammar, ReductMap, Span}};
 #[allow({})]
 #[must_use]
 pub fn grammar() -> Grammar<Sym> {{
-    Grammar::new(Sym::EntryPoint, {}, Sym::Eof)
+    let grammar = Grammar::new(Sym::EntryPoint, {}, Sym::Eof).expect("invalid grammar");
+    {}
 }}"#,
         wop::builder::GRAMMAR_LINTS,
-        builder.dump_grammar(&file)
+        builder.dump_grammar(&file),
+        builder.dump_precedence(&file),
     )?;
 
     writeln!(
         writer,
         r#"
 #[allow({})]
-pub fn reduct_map() -> ReductMap<Meta<Ast>, Sym> {}"#,
+pub fn reduct_map() -> ReductMap<'static, Meta<Ast>, Sym> {}"#,
         wop::builder::REDUCTOR_LINTS,
         builder.dump_reductor(&file),
     )?;
@@ -109,6 +110,9 @@ fn print_nested(tok: &Gramem, prefix: &str, lvl: usize, txt: &str) {
                     "{tab_spc}|> reductor {i}: \x1B[1;33m\"{}\"\x1B[0;m",
                     gs.1.from_source(txt).strip_prefix("->").unwrap_or("(null)")
                 );
+                if let Some(prec) = gs.2 {
+                    println!("{tab_spc}|> precedence {i}: {}", prec.from_source(txt));
+                }
             }
         }
         Ast::Rule(gs) => {
@@ -119,6 +123,9 @@ fn print_nested(tok: &Gramem, prefix: &str, lvl: usize, txt: &str) {
                     "{tab_spc}|> reductor {i}: \x1B[1;33m\"{}\"\x1B[0;m",
                     gs.1.from_source(txt).strip_prefix("->").unwrap_or("(null)")
                 );
+                if let Some(prec) = gs.2 {
+                    println!("{tab_spc}|> precedence {i}: {}", prec.from_source(txt));
+                }
             }
         }
         Ast::RulePipe(gs) => {
@@ -130,6 +137,12 @@ fn print_nested(tok: &Gramem, prefix: &str, lvl: usize, txt: &str) {
             println!("{tab_spc}|> definition: {}", h.from_source(txt));
         }
         Ast::IdentPath(g) => println!("{tab_spc} {}", g.from_source(txt)),
+        Ast::Precedence(assoc, terms) => {
+            println!("{tab_spc}|> associativity: {assoc:?}");
+            for term in terms {
+                println!("{tab_spc}|> term: {}", term.from_source(txt));
+            }
+        }
         Ast::RuleItem(g, o, a, c) => {
             print_nested(g.as_ref(), "", lvl, txt);
             println!("{tab_spc}|> optional: {o}");
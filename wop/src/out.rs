@@ -4,43 +4,31 @@ use lrp::{Grammar, ReductMap, Span};
 #[allow(unused_imports, clippy::enum_glob_use)]
 #[must_use]
 pub fn grammar() -> Grammar<Sym> {
-    Grammar::new(
+    let grammar = Grammar::new(
         Sym::EntryPoint,
         {
             use crate::Ast;
             use crate::Sym::*;
+            use lrp::Assoc;
             let mut map = lrp::RuleMap::new();
             map.insert(
-                RuleItem,
+                Import,
                 lrp::grammar::Rule::new(
-                    RuleItem,
+                    Import,
                     vec![
-                        vec![IdentPath, Optional, TwoDots, Clone, Ident],
-                        vec![IdentPath, Optional, TwoDots, Ident],
-                        vec![IdentPath, Optional],
-                        vec![IdentPath, TwoDots, Clone, Ident],
-                        vec![IdentPath, TwoDots, Ident],
-                        vec![IdentPath],
-                        vec![StrLit, Optional, TwoDots, Clone, Ident],
-                        vec![StrLit, Optional, TwoDots, Ident],
-                        vec![StrLit, Optional],
-                        vec![StrLit, TwoDots, Clone, Ident],
-                        vec![StrLit, TwoDots, Ident],
-                        vec![StrLit],
-                        vec![OpenParen, Rule, CloseParen, Optional, TwoDots, Clone, Ident],
-                        vec![OpenParen, Rule, CloseParen, Optional, TwoDots, Ident],
-                        vec![OpenParen, Rule, CloseParen, Optional],
-                        vec![OpenParen, Rule, CloseParen, TwoDots, Clone, Ident],
-                        vec![OpenParen, Rule, CloseParen, TwoDots, Ident],
-                        vec![OpenParen, Rule, CloseParen],
+                        vec![UseWord, IdentPath, PathAccess, Glob],
+                        vec![UseWord, IdentPath],
                     ],
                 ),
             );
             map.insert(
-                IdentPath,
+                Alias,
                 lrp::grammar::Rule::new(
-                    IdentPath,
-                    vec![vec![IdentPath, PathAccess, Ident], vec![Ident]],
+                    Alias,
+                    vec![
+                        vec![AliasWord, Ident, IdentPath],
+                        vec![AliasWord, StrLit, IdentPath],
+                    ],
                 ),
             );
             map.insert(
@@ -48,38 +36,58 @@ pub fn grammar() -> Grammar<Sym> {
                 lrp::grammar::Rule::new(EntryPoint, vec![vec![Program]]),
             );
             map.insert(
-                Rule,
+                RuleDecl,
                 lrp::grammar::Rule::new(
-                    Rule,
-                    vec![
-                        vec![Rule, Pipe, RulePipe, CodeBlock],
-                        vec![Rule, Pipe, RulePipe],
-                        vec![RulePipe, CodeBlock],
-                        vec![RulePipe],
-                    ],
+                    RuleDecl,
+                    vec![vec![IdentPath, TwoDots, IdentPath, Assign, Rule]],
                 ),
             );
             map.insert(
-                RulePipe,
-                lrp::grammar::Rule::new(RulePipe, vec![vec![RulePipe, RuleItem], vec![RuleItem]]),
+                Prec,
+                lrp::grammar::Rule::new(
+                    Prec,
+                    vec![vec![PrecWord, IdentPath], vec![PrecWord, StrLit]],
+                ),
             );
             map.insert(
-                Import,
+                RuleItem,
                 lrp::grammar::Rule::new(
-                    Import,
+                    RuleItem,
                     vec![
-                        vec![UseWord, IdentPath, PathAccess, Glob],
-                        vec![UseWord, IdentPath],
+                        vec![IdentPath, Optional, TwoDots, Clone, Ident],
+                        vec![IdentPath, Optional, TwoDots, Ident],
+                        vec![IdentPath, Optional],
+                        vec![IdentPath, TwoDots, Clone, Ident],
+                        vec![IdentPath, TwoDots, Ident],
+                        vec![IdentPath],
+                        vec![StrLit, Optional, TwoDots, Clone, Ident],
+                        vec![StrLit, Optional, TwoDots, Ident],
+                        vec![StrLit, Optional],
+                        vec![StrLit, TwoDots, Clone, Ident],
+                        vec![StrLit, TwoDots, Ident],
+                        vec![StrLit],
+                        vec![OpenParen, Rule, CloseParen, Optional, TwoDots, Clone, Ident],
+                        vec![OpenParen, Rule, CloseParen, Optional, TwoDots, Ident],
+                        vec![OpenParen, Rule, CloseParen, Optional],
+                        vec![OpenParen, Rule, CloseParen, TwoDots, Clone, Ident],
+                        vec![OpenParen, Rule, CloseParen, TwoDots, Ident],
+                        vec![OpenParen, Rule, CloseParen],
                     ],
                 ),
             );
             map.insert(
-                Alias,
+                Precedence,
                 lrp::grammar::Rule::new(
-                    Alias,
+                    Precedence,
                     vec![
-                        vec![AliasWord, Ident, IdentPath],
-                        vec![AliasWord, StrLit, IdentPath],
+                        vec![Precedence, IdentPath],
+                        vec![Precedence, StrLit],
+                        vec![LeftWord, IdentPath],
+                        vec![LeftWord, StrLit],
+                        vec![RightWord, IdentPath],
+                        vec![RightWord, StrLit],
+                        vec![NonAssocWord, IdentPath],
+                        vec![NonAssocWord, StrLit],
                     ],
                 ),
             );
@@ -91,17 +99,39 @@ pub fn grammar() -> Grammar<Sym> {
                         vec![Program, Import, Sc],
                         vec![Program, Alias, Sc],
                         vec![Program, RuleDecl, Sc],
+                        vec![Program, Precedence, Sc],
                         vec![Alias, Sc],
                         vec![Import, Sc],
                         vec![RuleDecl, Sc],
+                        vec![Precedence, Sc],
                     ],
                 ),
             );
             map.insert(
-                RuleDecl,
+                RulePipe,
+                lrp::grammar::Rule::new(RulePipe, vec![vec![RulePipe, RuleItem], vec![RuleItem]]),
+            );
+            map.insert(
+                Rule,
                 lrp::grammar::Rule::new(
-                    RuleDecl,
-                    vec![vec![IdentPath, TwoDots, IdentPath, Assign, Rule]],
+                    Rule,
+                    vec![
+                        vec![Rule, Pipe, RulePipe, Prec, CodeBlock],
+                        vec![Rule, Pipe, RulePipe, Prec],
+                        vec![Rule, Pipe, RulePipe, CodeBlock],
+                        vec![Rule, Pipe, RulePipe],
+                        vec![RulePipe, Prec, CodeBlock],
+                        vec![RulePipe, Prec],
+                        vec![RulePipe, CodeBlock],
+                        vec![RulePipe],
+                    ],
+                ),
+            );
+            map.insert(
+                IdentPath,
+                lrp::grammar::Rule::new(
+                    IdentPath,
+                    vec![vec![IdentPath, PathAccess, Ident], vec![Ident]],
                 ),
             );
 
@@ -109,6 +139,13 @@ pub fn grammar() -> Grammar<Sym> {
         },
         Sym::Eof,
     )
+    .expect("invalid grammar");
+    {
+        use crate::Ast;
+        use crate::Sym::*;
+        use lrp::Assoc;
+        grammar
+    }
 }
 
 #[allow(
@@ -119,12 +156,131 @@ pub fn grammar() -> Grammar<Sym> {
     unused_assignments,
     clippy::unnecessary_literal_unwrap
 )]
-pub fn reduct_map() -> ReductMap<Meta<Ast>, Sym> {
+pub fn reduct_map() -> ReductMap<'static, Meta<Ast>, Sym> {
     use crate::Ast;
     use crate::Sym::*;
+    use lrp::Assoc;
     let mut map = lrp::ReductMap::new();
 
-    fn lrp_wop_RuleItem_0(toks: &[Gramem]) -> lrp::Meta<Ast> {
+    fn lrp_wop_Import_0(toks: Vec<Gramem>) -> lrp::Meta<Ast> {
+        lrp::Meta::new(
+            {
+                {
+                    Ast::Import(Span::new(
+                        toks[1].item.span.start,
+                        toks.last().unwrap().item.span.end,
+                    ))
+                }
+            },
+            lrp::Span::new(toks[0].item.span.start, toks.last().unwrap().item.span.end),
+        )
+    }
+    fn lrp_wop_Import_1(toks: Vec<Gramem>) -> lrp::Meta<Ast> {
+        lrp::Meta::new(
+            {
+                {
+                    Ast::Import(Span::new(
+                        toks[1].item.span.start,
+                        toks.last().unwrap().item.span.end,
+                    ))
+                }
+            },
+            lrp::Span::new(toks[0].item.span.start, toks.last().unwrap().item.span.end),
+        )
+    }
+    map.insert(
+        Import,
+        vec![
+            lrp::reductor(lrp_wop_Import_0),
+            lrp::reductor(lrp_wop_Import_1),
+        ],
+    );
+
+    fn lrp_wop_Alias_0(toks: Vec<Gramem>) -> lrp::Meta<Ast> {
+        lrp::Meta::new(
+            {
+                {
+                    Ast::Alias(toks[1].item.span, toks[2].item.span)
+                }
+            },
+            lrp::Span::new(toks[0].item.span.start, toks.last().unwrap().item.span.end),
+        )
+    }
+    fn lrp_wop_Alias_1(toks: Vec<Gramem>) -> lrp::Meta<Ast> {
+        lrp::Meta::new(
+            {
+                {
+                    Ast::Alias(toks[1].item.span, toks[2].item.span)
+                }
+            },
+            lrp::Span::new(toks[0].item.span.start, toks.last().unwrap().item.span.end),
+        )
+    }
+    map.insert(
+        Alias,
+        vec![
+            lrp::reductor(lrp_wop_Alias_0),
+            lrp::reductor(lrp_wop_Alias_1),
+        ],
+    );
+
+    fn lrp_wop_EntryPoint_0(toks: Vec<Gramem>) -> lrp::Meta<Ast> {
+        lrp::Meta::new(
+            {
+                let p = toks[0].clone();
+                {
+                    Ast::EntryPoint(Box::new(p))
+                }
+            },
+            lrp::Span::new(toks[0].item.span.start, toks.last().unwrap().item.span.end),
+        )
+    }
+    map.insert(EntryPoint, vec![lrp::reductor(lrp_wop_EntryPoint_0)]);
+
+    fn lrp_wop_RuleDecl_0(toks: Vec<Gramem>) -> lrp::Meta<Ast> {
+        lrp::Meta::new(
+            {
+                {
+                    let ident = toks[0].item.item.get_src_ref().unwrap();
+                    let ty = toks[2].item.item.get_src_ref().unwrap();
+                    let rule_vec = match toks[4].item.item {
+                        Ast::Rule(ref v) => v.clone(),
+                        _ => unreachable!(),
+                    };
+                    Ast::RuleDecl((ident, ty, rule_vec))
+                }
+            },
+            lrp::Span::new(toks[0].item.span.start, toks.last().unwrap().item.span.end),
+        )
+    }
+    map.insert(RuleDecl, vec![lrp::reductor(lrp_wop_RuleDecl_0)]);
+
+    fn lrp_wop_Prec_0(toks: Vec<Gramem>) -> lrp::Meta<Ast> {
+        lrp::Meta::new(
+            {
+                {
+                    Ast::IdentPath(toks[1].item.span)
+                }
+            },
+            lrp::Span::new(toks[0].item.span.start, toks.last().unwrap().item.span.end),
+        )
+    }
+    fn lrp_wop_Prec_1(toks: Vec<Gramem>) -> lrp::Meta<Ast> {
+        lrp::Meta::new(
+            {
+                {
+                    Ast::IdentPath(toks[1].item.span)
+                }
+            },
+            lrp::Span::new(toks[0].item.span.start, toks.last().unwrap().item.span.end),
+        )
+    }
+    map.insert(
+        Prec,
+        vec![lrp::reductor(lrp_wop_Prec_0), lrp::reductor(lrp_wop_Prec_1)],
+    );
+
+    fn lrp_wop_RuleItem_0(toks: Vec<Gramem>) -> lrp::Meta<Ast> {
         lrp::Meta::new(
             {
                 let item = Some(toks[0].clone());
@@ -143,7 +299,7 @@ pub fn reduct_map() -> ReductMap<Meta<Ast>, Sym> {
             lrp::Span::new(toks[0].item.span.start, toks.last().unwrap().item.span.end),
         )
     }
-    fn lrp_wop_RuleItem_1(toks: &[Gramem]) -> lrp::Meta<Ast> {
+    fn lrp_wop_RuleItem_1(toks: Vec<Gramem>) -> lrp::Meta<Ast> {
         lrp::Meta::new(
             {
                 let item = Some(toks[0].clone());
@@ -162,7 +318,7 @@ pub fn reduct_map() -> ReductMap<Meta<Ast>, Sym> {
             lrp::Span::new(toks[0].item.span.start, toks.last().unwrap().item.span.end),
         )
     }
-    fn lrp_wop_RuleItem_2(toks: &[Gramem]) -> lrp::Meta<Ast> {
+    fn lrp_wop_RuleItem_2(toks: Vec<Gramem>) -> lrp::Meta<Ast> {
         lrp::Meta::new(
             {
                 let item = Some(toks[0].clone());
@@ -185,7 +341,7 @@ pub fn reduct_map() -> ReductMap<Meta<Ast>, Sym> {
             lrp::Span::new(toks[0].item.span.start, toks.last().unwrap().item.span.end),
         )
     }
-    fn lrp_wop_RuleItem_3(toks: &[Gramem]) -> lrp::Meta<Ast> {
+    fn lrp_wop_RuleItem_3(toks: Vec<Gramem>) -> lrp::Meta<Ast> {
         lrp::Meta::new(
             {
                 let item = Some(toks[0].clone());
@@ -206,7 +362,7 @@ pub fn reduct_map() -> ReductMap<Meta<Ast>, Sym> {
             lrp::Span::new(toks[0].item.span.start, toks.last().unwrap().item.span.end),
         )
     }
-    fn lrp_wop_RuleItem_4(toks: &[Gramem]) -> lrp::Meta<Ast> {
+    fn lrp_wop_RuleItem_4(toks: Vec<Gramem>) -> lrp::Meta<Ast> {
         lrp::Meta::new(
             {
                 let item = Some(toks[0].clone());
@@ -227,7 +383,7 @@ pub fn reduct_map() -> ReductMap<Meta<Ast>, Sym> {
             lrp::Span::new(toks[0].item.span.start, toks.last().unwrap().item.span.end),
         )
     }
-    fn lrp_wop_RuleItem_5(toks: &[Gramem]) -> lrp::Meta<Ast> {
+    fn lrp_wop_RuleItem_5(toks: Vec<Gramem>) -> lrp::Meta<Ast> {
         lrp::Meta::new(
             {
                 let item = Some(toks[0].clone());
@@ -252,7 +408,7 @@ pub fn reduct_map() -> ReductMap<Meta<Ast>, Sym> {
             lrp::Span::new(toks[0].item.span.start, toks.last().unwrap().item.span.end),
         )
     }
-    fn lrp_wop_RuleItem_6(toks: &[Gramem]) -> lrp::Meta<Ast> {
+    fn lrp_wop_RuleItem_6(toks: Vec<Gramem>) -> lrp::Meta<Ast> {
         lrp::Meta::new(
             {
                 let item = Some(toks[0].clone());
@@ -271,7 +427,7 @@ pub fn reduct_map() -> ReductMap<Meta<Ast>, Sym> {
             lrp::Span::new(toks[0].item.span.start, toks.last().unwrap().item.span.end),
         )
     }
-    fn lrp_wop_RuleItem_7(toks: &[Gramem]) -> lrp::Meta<Ast> {
+    fn lrp_wop_RuleItem_7(toks: Vec<Gramem>) -> lrp::Meta<Ast> {
         lrp::Meta::new(
             {
                 let item = Some(toks[0].clone());
@@ -290,7 +446,7 @@ pub fn reduct_map() -> ReductMap<Meta<Ast>, Sym> {
             lrp::Span::new(toks[0].item.span.start, toks.last().unwrap().item.span.end),
         )
     }
-    fn lrp_wop_RuleItem_8(toks: &[Gramem]) -> lrp::Meta<Ast> {
+    fn lrp_wop_RuleItem_8(toks: Vec<Gramem>) -> lrp::Meta<Ast> {
         lrp::Meta::new(
             {
                 let item = Some(toks[0].clone());
@@ -313,7 +469,7 @@ pub fn reduct_map() -> ReductMap<Meta<Ast>, Sym> {
             lrp::Span::new(toks[0].item.span.start, toks.last().unwrap().item.span.end),
         )
     }
-    fn lrp_wop_RuleItem_9(toks: &[Gramem]) -> lrp::Meta<Ast> {
+    fn lrp_wop_RuleItem_9(toks: Vec<Gramem>) -> lrp::Meta<Ast> {
         lrp::Meta::new(
             {
                 let item = Some(toks[0].clone());
@@ -334,7 +490,7 @@ pub fn reduct_map() -> ReductMap<Meta<Ast>, Sym> {
             lrp::Span::new(toks[0].item.span.start, toks.last().unwrap().item.span.end),
         )
     }
-    fn lrp_wop_RuleItem_10(toks: &[Gramem]) -> lrp::Meta<Ast> {
+    fn lrp_wop_RuleItem_10(toks: Vec<Gramem>) -> lrp::Meta<Ast> {
         lrp::Meta::new(
             {
                 let item = Some(toks[0].clone());
@@ -355,7 +511,7 @@ pub fn reduct_map() -> ReductMap<Meta<Ast>, Sym> {
             lrp::Span::new(toks[0].item.span.start, toks.last().unwrap().item.span.end),
         )
     }
-    fn lrp_wop_RuleItem_11(toks: &[Gramem]) -> lrp::Meta<Ast> {
+    fn lrp_wop_RuleItem_11(toks: Vec<Gramem>) -> lrp::Meta<Ast> {
         lrp::Meta::new(
             {
                 let item = Some(toks[0].clone());
@@ -380,7 +536,7 @@ pub fn reduct_map() -> ReductMap<Meta<Ast>, Sym> {
             lrp::Span::new(toks[0].item.span.start, toks.last().unwrap().item.span.end),
         )
     }
-    fn lrp_wop_RuleItem_12(toks: &[Gramem]) -> lrp::Meta<Ast> {
+    fn lrp_wop_RuleItem_12(toks: Vec<Gramem>) -> lrp::Meta<Ast> {
         lrp::Meta::new(
             {
                 let rule = toks[1].clone();
@@ -399,7 +555,7 @@ pub fn reduct_map() -> ReductMap<Meta<Ast>, Sym> {
             lrp::Span::new(toks[0].item.span.start, toks.last().unwrap().item.span.end),
         )
     }
-    fn lrp_wop_RuleItem_13(toks: &[Gramem]) -> lrp::Meta<Ast> {
+    fn lrp_wop_RuleItem_13(toks: Vec<Gramem>) -> lrp::Meta<Ast> {
         lrp::Meta::new(
             {
                 let rule = toks[1].clone();
@@ -418,7 +574,7 @@ pub fn reduct_map() -> ReductMap<Meta<Ast>, Sym> {
             lrp::Span::new(toks[0].item.span.start, toks.last().unwrap().item.span.end),
         )
     }
-    fn lrp_wop_RuleItem_14(toks: &[Gramem]) -> lrp::Meta<Ast> {
+    fn lrp_wop_RuleItem_14(toks: Vec<Gramem>) -> lrp::Meta<Ast> {
         lrp::Meta::new(
             {
                 let rule = toks[1].clone();
@@ -441,7 +597,7 @@ pub fn reduct_map() -> ReductMap<Meta<Ast>, Sym> {
             lrp::Span::new(toks[0].item.span.start, toks.last().unwrap().item.span.end),
         )
     }
-    fn lrp_wop_RuleItem_15(toks: &[Gramem]) -> lrp::Meta<Ast> {
+    fn lrp_wop_RuleItem_15(toks: Vec<Gramem>) -> lrp::Meta<Ast> {
         lrp::Meta::new(
             {
                 let rule = toks[1].clone();
@@ -462,7 +618,7 @@ pub fn reduct_map() -> ReductMap<Meta<Ast>, Sym> {
             lrp::Span::new(toks[0].item.span.start, toks.last().unwrap().item.span.end),
         )
     }
-    fn lrp_wop_RuleItem_16(toks: &[Gramem]) -> lrp::Meta<Ast> {
+    fn lrp_wop_RuleItem_16(toks: Vec<Gramem>) -> lrp::Meta<Ast> {
         lrp::Meta::new(
             {
                 let rule = toks[1].clone();
@@ -483,7 +639,7 @@ pub fn reduct_map() -> ReductMap<Meta<Ast>, Sym> {
             lrp::Span::new(toks[0].item.span.start, toks.last().unwrap().item.span.end),
         )
     }
-    fn lrp_wop_RuleItem_17(toks: &[Gramem]) -> lrp::Meta<Ast> {
+    fn lrp_wop_RuleItem_17(toks: Vec<Gramem>) -> lrp::Meta<Ast> {
         lrp::Meta::new(
             {
                 let rule = toks[1].clone();
@@ -511,348 +667,561 @@ pub fn reduct_map() -> ReductMap<Meta<Ast>, Sym> {
     map.insert(
         RuleItem,
         vec![
-            lrp_wop_RuleItem_0,
-            lrp_wop_RuleItem_1,
-            lrp_wop_RuleItem_2,
-            lrp_wop_RuleItem_3,
-            lrp_wop_RuleItem_4,
-            lrp_wop_RuleItem_5,
-            lrp_wop_RuleItem_6,
-            lrp_wop_RuleItem_7,
-            lrp_wop_RuleItem_8,
-            lrp_wop_RuleItem_9,
-            lrp_wop_RuleItem_10,
-            lrp_wop_RuleItem_11,
-            lrp_wop_RuleItem_12,
-            lrp_wop_RuleItem_13,
-            lrp_wop_RuleItem_14,
-            lrp_wop_RuleItem_15,
-            lrp_wop_RuleItem_16,
-            lrp_wop_RuleItem_17,
+            lrp::reductor(lrp_wop_RuleItem_0),
+            lrp::reductor(lrp_wop_RuleItem_1),
+            lrp::reductor(lrp_wop_RuleItem_2),
+            lrp::reductor(lrp_wop_RuleItem_3),
+            lrp::reductor(lrp_wop_RuleItem_4),
+            lrp::reductor(lrp_wop_RuleItem_5),
+            lrp::reductor(lrp_wop_RuleItem_6),
+            lrp::reductor(lrp_wop_RuleItem_7),
+            lrp::reductor(lrp_wop_RuleItem_8),
+            lrp::reductor(lrp_wop_RuleItem_9),
+            lrp::reductor(lrp_wop_RuleItem_10),
+            lrp::reductor(lrp_wop_RuleItem_11),
+            lrp::reductor(lrp_wop_RuleItem_12),
+            lrp::reductor(lrp_wop_RuleItem_13),
+            lrp::reductor(lrp_wop_RuleItem_14),
+            lrp::reductor(lrp_wop_RuleItem_15),
+            lrp::reductor(lrp_wop_RuleItem_16),
+            lrp::reductor(lrp_wop_RuleItem_17),
         ],
     );
 
-    fn lrp_wop_IdentPath_0(toks: &[Gramem]) -> lrp::Meta<Ast> {
+    fn lrp_wop_Precedence_0(toks: Vec<Gramem>) -> lrp::Meta<Ast> {
         lrp::Meta::new(
             {
-                let ip = toks[0].clone();
-                let ext = toks[2].clone();
                 {
-                    let span = Span::new(ip.item.span.start, ext.item.span.end);
-                    Ast::IdentPath(span)
+                    let Ast::Precedence(assoc, ref items) = toks[0].item.item else {
+                        unreachable!()
+                    };
+                    let mut items = items.clone();
+                    items.push(toks[1].item.span);
+                    Ast::Precedence(assoc, items)
                 }
             },
             lrp::Span::new(toks[0].item.span.start, toks.last().unwrap().item.span.end),
         )
     }
-    fn lrp_wop_IdentPath_1(toks: &[Gramem]) -> lrp::Meta<Ast> {
+    fn lrp_wop_Precedence_1(toks: Vec<Gramem>) -> lrp::Meta<Ast> {
         lrp::Meta::new(
             {
                 {
-                    Ast::IdentPath(toks[0].item.span)
+                    let Ast::Precedence(assoc, ref items) = toks[0].item.item else {
+                        unreachable!()
+                    };
+                    let mut items = items.clone();
+                    items.push(toks[1].item.span);
+                    Ast::Precedence(assoc, items)
                 }
             },
             lrp::Span::new(toks[0].item.span.start, toks.last().unwrap().item.span.end),
         )
     }
-    map.insert(IdentPath, vec![lrp_wop_IdentPath_0, lrp_wop_IdentPath_1]);
-
-    fn lrp_wop_EntryPoint_0(toks: &[Gramem]) -> lrp::Meta<Ast> {
+    fn lrp_wop_Precedence_2(toks: Vec<Gramem>) -> lrp::Meta<Ast> {
         lrp::Meta::new(
             {
-                let p = toks[0].clone();
                 {
-                    Ast::EntryPoint(Box::new(p))
+                    let assoc = match toks[0].ty {
+                        LeftWord => Assoc::Left,
+                        RightWord => Assoc::Right,
+                        _ => Assoc::NonAssoc,
+                    };
+                    Ast::Precedence(assoc, vec![toks[1].item.span])
                 }
             },
             lrp::Span::new(toks[0].item.span.start, toks.last().unwrap().item.span.end),
         )
     }
-    map.insert(EntryPoint, vec![lrp_wop_EntryPoint_0]);
-
-    fn lrp_wop_Rule_0(toks: &[Gramem]) -> lrp::Meta<Ast> {
+    fn lrp_wop_Precedence_3(toks: Vec<Gramem>) -> lrp::Meta<Ast> {
         lrp::Meta::new(
             {
-                let cb = Some(toks[3].clone());
                 {
-                    let mut rule_vec = match toks[0].item.item {
-                        Ast::Rule(ref vv) => vv.clone(),
-                        _ => unreachable!(),
-                    };
-                    match toks[2].item.item {
-                        Ast::RulePipe(ref v) => rule_vec.push((
-                            v.clone(),
-                            cb.map_or_else(|| Span::new(0, 0), |g| g.item.span),
-                        )),
-                        _ => unreachable!(),
+                    let assoc = match toks[0].ty {
+                        LeftWord => Assoc::Left,
+                        RightWord => Assoc::Right,
+                        _ => Assoc::NonAssoc,
                     };
-                    Ast::Rule(rule_vec)
+                    Ast::Precedence(assoc, vec![toks[1].item.span])
                 }
             },
             lrp::Span::new(toks[0].item.span.start, toks.last().unwrap().item.span.end),
         )
     }
-    fn lrp_wop_Rule_1(toks: &[Gramem]) -> lrp::Meta<Ast> {
+    fn lrp_wop_Precedence_4(toks: Vec<Gramem>) -> lrp::Meta<Ast> {
         lrp::Meta::new(
             {
-                let mut cb = Some(toks[0].clone());
-                cb = None;
-                let cb = cb;
                 {
-                    let mut rule_vec = match toks[0].item.item {
-                        Ast::Rule(ref vv) => vv.clone(),
-                        _ => unreachable!(),
-                    };
-                    match toks[2].item.item {
-                        Ast::RulePipe(ref v) => rule_vec.push((
-                            v.clone(),
-                            cb.map_or_else(|| Span::new(0, 0), |g| g.item.span),
-                        )),
-                        _ => unreachable!(),
+                    let assoc = match toks[0].ty {
+                        LeftWord => Assoc::Left,
+                        RightWord => Assoc::Right,
+                        _ => Assoc::NonAssoc,
                     };
-                    Ast::Rule(rule_vec)
+                    Ast::Precedence(assoc, vec![toks[1].item.span])
                 }
             },
             lrp::Span::new(toks[0].item.span.start, toks.last().unwrap().item.span.end),
         )
     }
-    fn lrp_wop_Rule_2(toks: &[Gramem]) -> lrp::Meta<Ast> {
+    fn lrp_wop_Precedence_5(toks: Vec<Gramem>) -> lrp::Meta<Ast> {
         lrp::Meta::new(
             {
-                let cb = Some(toks[1].clone());
                 {
-                    let Ast::RulePipe(ref prod) = toks[0].item.item else {
-                        unreachable!()
+                    let assoc = match toks[0].ty {
+                        LeftWord => Assoc::Left,
+                        RightWord => Assoc::Right,
+                        _ => Assoc::NonAssoc,
                     };
-                    Ast::Rule(vec![(
-                        prod.clone(),
-                        cb.map_or_else(|| Span::new(0, 0), |g| g.item.span),
-                    )])
+                    Ast::Precedence(assoc, vec![toks[1].item.span])
                 }
             },
             lrp::Span::new(toks[0].item.span.start, toks.last().unwrap().item.span.end),
         )
     }
-    fn lrp_wop_Rule_3(toks: &[Gramem]) -> lrp::Meta<Ast> {
+    fn lrp_wop_Precedence_6(toks: Vec<Gramem>) -> lrp::Meta<Ast> {
         lrp::Meta::new(
             {
-                let mut cb = Some(toks[0].clone());
-                cb = None;
-                let cb = cb;
                 {
-                    let Ast::RulePipe(ref prod) = toks[0].item.item else {
-                        unreachable!()
+                    let assoc = match toks[0].ty {
+                        LeftWord => Assoc::Left,
+                        RightWord => Assoc::Right,
+                        _ => Assoc::NonAssoc,
                     };
-                    Ast::Rule(vec![(
-                        prod.clone(),
-                        cb.map_or_else(|| Span::new(0, 0), |g| g.item.span),
-                    )])
+                    Ast::Precedence(assoc, vec![toks[1].item.span])
                 }
             },
             lrp::Span::new(toks[0].item.span.start, toks.last().unwrap().item.span.end),
         )
     }
-    map.insert(
-        Rule,
-        vec![
-            lrp_wop_Rule_0,
-            lrp_wop_Rule_1,
-            lrp_wop_Rule_2,
-            lrp_wop_Rule_3,
+    fn lrp_wop_Precedence_7(toks: Vec<Gramem>) -> lrp::Meta<Ast> {
+        lrp::Meta::new(
+            {
+                {
+                    let assoc = match toks[0].ty {
+                        LeftWord => Assoc::Left,
+                        RightWord => Assoc::Right,
+                        _ => Assoc::NonAssoc,
+                    };
+                    Ast::Precedence(assoc, vec![toks[1].item.span])
+                }
+            },
+            lrp::Span::new(toks[0].item.span.start, toks.last().unwrap().item.span.end),
+        )
+    }
+    map.insert(
+        Precedence,
+        vec![
+            lrp::reductor(lrp_wop_Precedence_0),
+            lrp::reductor(lrp_wop_Precedence_1),
+            lrp::reductor(lrp_wop_Precedence_2),
+            lrp::reductor(lrp_wop_Precedence_3),
+            lrp::reductor(lrp_wop_Precedence_4),
+            lrp::reductor(lrp_wop_Precedence_5),
+            lrp::reductor(lrp_wop_Precedence_6),
+            lrp::reductor(lrp_wop_Precedence_7),
         ],
     );
 
-    fn lrp_wop_RulePipe_0(toks: &[Gramem]) -> lrp::Meta<Ast> {
+    fn lrp_wop_Program_0(toks: Vec<Gramem>) -> lrp::Meta<Ast> {
         lrp::Meta::new(
             {
-                let item = toks[1].clone();
+                let ext = Some(toks[1].clone());
                 {
-                    let mut v = match toks[0].item.item {
-                        Ast::RulePipe(ref v) => v.clone(),
+                    let program = &toks[0];
+                    let mut program_vec = match program.item.item {
+                        Ast::Program(ref v) => v.clone(),
                         _ => unreachable!(),
                     };
-                    v.push(item);
-                    Ast::RulePipe(v)
+                    program_vec.push(ext.unwrap());
+                    Ast::Program(program_vec)
                 }
             },
             lrp::Span::new(toks[0].item.span.start, toks.last().unwrap().item.span.end),
         )
     }
-    fn lrp_wop_RulePipe_1(toks: &[Gramem]) -> lrp::Meta<Ast> {
+    fn lrp_wop_Program_1(toks: Vec<Gramem>) -> lrp::Meta<Ast> {
         lrp::Meta::new(
             {
+                let ext = Some(toks[1].clone());
                 {
-                    Ast::RulePipe(toks[..1].to_vec())
+                    let program = &toks[0];
+                    let mut program_vec = match program.item.item {
+                        Ast::Program(ref v) => v.clone(),
+                        _ => unreachable!(),
+                    };
+                    program_vec.push(ext.unwrap());
+                    Ast::Program(program_vec)
                 }
             },
             lrp::Span::new(toks[0].item.span.start, toks.last().unwrap().item.span.end),
         )
     }
-    map.insert(RulePipe, vec![lrp_wop_RulePipe_0, lrp_wop_RulePipe_1]);
-
-    fn lrp_wop_Import_0(toks: &[Gramem]) -> lrp::Meta<Ast> {
+    fn lrp_wop_Program_2(toks: Vec<Gramem>) -> lrp::Meta<Ast> {
         lrp::Meta::new(
             {
+                let ext = Some(toks[1].clone());
                 {
-                    Ast::Import(Span::new(
-                        toks[1].item.span.start,
-                        toks.last().unwrap().item.span.end,
-                    ))
+                    let program = &toks[0];
+                    let mut program_vec = match program.item.item {
+                        Ast::Program(ref v) => v.clone(),
+                        _ => unreachable!(),
+                    };
+                    program_vec.push(ext.unwrap());
+                    Ast::Program(program_vec)
                 }
             },
             lrp::Span::new(toks[0].item.span.start, toks.last().unwrap().item.span.end),
         )
     }
-    fn lrp_wop_Import_1(toks: &[Gramem]) -> lrp::Meta<Ast> {
+    fn lrp_wop_Program_3(toks: Vec<Gramem>) -> lrp::Meta<Ast> {
         lrp::Meta::new(
             {
+                let ext = Some(toks[1].clone());
                 {
-                    Ast::Import(Span::new(
-                        toks[1].item.span.start,
-                        toks.last().unwrap().item.span.end,
-                    ))
+                    let program = &toks[0];
+                    let mut program_vec = match program.item.item {
+                        Ast::Program(ref v) => v.clone(),
+                        _ => unreachable!(),
+                    };
+                    program_vec.push(ext.unwrap());
+                    Ast::Program(program_vec)
+                }
+            },
+            lrp::Span::new(toks[0].item.span.start, toks.last().unwrap().item.span.end),
+        )
+    }
+    fn lrp_wop_Program_4(toks: Vec<Gramem>) -> lrp::Meta<Ast> {
+        lrp::Meta::new(
+            {
+                let p = toks[0..1].to_vec();
+                {
+                    Ast::Program(p)
                 }
             },
             lrp::Span::new(toks[0].item.span.start, toks.last().unwrap().item.span.end),
         )
     }
-    map.insert(Import, vec![lrp_wop_Import_0, lrp_wop_Import_1]);
+    fn lrp_wop_Program_5(toks: Vec<Gramem>) -> lrp::Meta<Ast> {
+        lrp::Meta::new(
+            {
+                let p = toks[0..1].to_vec();
+                {
+                    Ast::Program(p)
+                }
+            },
+            lrp::Span::new(toks[0].item.span.start, toks.last().unwrap().item.span.end),
+        )
+    }
+    fn lrp_wop_Program_6(toks: Vec<Gramem>) -> lrp::Meta<Ast> {
+        lrp::Meta::new(
+            {
+                let p = toks[0..1].to_vec();
+                {
+                    Ast::Program(p)
+                }
+            },
+            lrp::Span::new(toks[0].item.span.start, toks.last().unwrap().item.span.end),
+        )
+    }
+    fn lrp_wop_Program_7(toks: Vec<Gramem>) -> lrp::Meta<Ast> {
+        lrp::Meta::new(
+            {
+                let p = toks[0..1].to_vec();
+                {
+                    Ast::Program(p)
+                }
+            },
+            lrp::Span::new(toks[0].item.span.start, toks.last().unwrap().item.span.end),
+        )
+    }
+    map.insert(
+        Program,
+        vec![
+            lrp::reductor(lrp_wop_Program_0),
+            lrp::reductor(lrp_wop_Program_1),
+            lrp::reductor(lrp_wop_Program_2),
+            lrp::reductor(lrp_wop_Program_3),
+            lrp::reductor(lrp_wop_Program_4),
+            lrp::reductor(lrp_wop_Program_5),
+            lrp::reductor(lrp_wop_Program_6),
+            lrp::reductor(lrp_wop_Program_7),
+        ],
+    );
 
-    fn lrp_wop_Alias_0(toks: &[Gramem]) -> lrp::Meta<Ast> {
+    fn lrp_wop_RulePipe_0(toks: Vec<Gramem>) -> lrp::Meta<Ast> {
         lrp::Meta::new(
             {
+                let item = toks[1].clone();
                 {
-                    Ast::Alias(toks[1].item.span, toks[2].item.span)
+                    let mut v = match toks[0].item.item {
+                        Ast::RulePipe(ref v) => v.clone(),
+                        _ => unreachable!(),
+                    };
+                    v.push(item);
+                    Ast::RulePipe(v)
                 }
             },
             lrp::Span::new(toks[0].item.span.start, toks.last().unwrap().item.span.end),
         )
     }
-    fn lrp_wop_Alias_1(toks: &[Gramem]) -> lrp::Meta<Ast> {
+    fn lrp_wop_RulePipe_1(toks: Vec<Gramem>) -> lrp::Meta<Ast> {
         lrp::Meta::new(
             {
                 {
-                    Ast::Alias(toks[1].item.span, toks[2].item.span)
+                    Ast::RulePipe(toks[..1].to_vec())
                 }
             },
             lrp::Span::new(toks[0].item.span.start, toks.last().unwrap().item.span.end),
         )
     }
-    map.insert(Alias, vec![lrp_wop_Alias_0, lrp_wop_Alias_1]);
+    map.insert(
+        RulePipe,
+        vec![
+            lrp::reductor(lrp_wop_RulePipe_0),
+            lrp::reductor(lrp_wop_RulePipe_1),
+        ],
+    );
 
-    fn lrp_wop_Program_0(toks: &[Gramem]) -> lrp::Meta<Ast> {
+    fn lrp_wop_Rule_0(toks: Vec<Gramem>) -> lrp::Meta<Ast> {
         lrp::Meta::new(
             {
-                let ext = Some(toks[1].clone());
+                let pr = Some(toks[3].clone());
+                let cb = Some(toks[4].clone());
                 {
-                    let program = &toks[0];
-                    let mut program_vec = match program.item.item {
-                        Ast::Program(ref v) => v.clone(),
+                    let mut rule_vec = match toks[0].item.item {
+                        Ast::Rule(ref vv) => vv.clone(),
                         _ => unreachable!(),
                     };
-                    program_vec.push(ext.unwrap());
-                    Ast::Program(program_vec)
+                    let prec = pr.and_then(|p| p.item.item.get_src_ref());
+                    match toks[2].item.item {
+                        Ast::RulePipe(ref v) => rule_vec.push((
+                            v.clone(),
+                            cb.map_or_else(|| Span::new(0, 0), |g| g.item.span),
+                            prec,
+                        )),
+                        _ => unreachable!(),
+                    };
+                    Ast::Rule(rule_vec)
                 }
             },
             lrp::Span::new(toks[0].item.span.start, toks.last().unwrap().item.span.end),
         )
     }
-    fn lrp_wop_Program_1(toks: &[Gramem]) -> lrp::Meta<Ast> {
+    fn lrp_wop_Rule_1(toks: Vec<Gramem>) -> lrp::Meta<Ast> {
         lrp::Meta::new(
             {
-                let ext = Some(toks[1].clone());
+                let pr = Some(toks[3].clone());
+                let mut cb = Some(toks[0].clone());
+                cb = None;
+                let cb = cb;
                 {
-                    let program = &toks[0];
-                    let mut program_vec = match program.item.item {
-                        Ast::Program(ref v) => v.clone(),
+                    let mut rule_vec = match toks[0].item.item {
+                        Ast::Rule(ref vv) => vv.clone(),
                         _ => unreachable!(),
                     };
-                    program_vec.push(ext.unwrap());
-                    Ast::Program(program_vec)
+                    let prec = pr.and_then(|p| p.item.item.get_src_ref());
+                    match toks[2].item.item {
+                        Ast::RulePipe(ref v) => rule_vec.push((
+                            v.clone(),
+                            cb.map_or_else(|| Span::new(0, 0), |g| g.item.span),
+                            prec,
+                        )),
+                        _ => unreachable!(),
+                    };
+                    Ast::Rule(rule_vec)
                 }
             },
             lrp::Span::new(toks[0].item.span.start, toks.last().unwrap().item.span.end),
         )
     }
-    fn lrp_wop_Program_2(toks: &[Gramem]) -> lrp::Meta<Ast> {
+    fn lrp_wop_Rule_2(toks: Vec<Gramem>) -> lrp::Meta<Ast> {
         lrp::Meta::new(
             {
-                let ext = Some(toks[1].clone());
+                let mut pr = Some(toks[0].clone());
+                pr = None;
+                let pr = pr;
+                let cb = Some(toks[3].clone());
                 {
-                    let program = &toks[0];
-                    let mut program_vec = match program.item.item {
-                        Ast::Program(ref v) => v.clone(),
+                    let mut rule_vec = match toks[0].item.item {
+                        Ast::Rule(ref vv) => vv.clone(),
                         _ => unreachable!(),
                     };
-                    program_vec.push(ext.unwrap());
-                    Ast::Program(program_vec)
+                    let prec = pr.and_then(|p| p.item.item.get_src_ref());
+                    match toks[2].item.item {
+                        Ast::RulePipe(ref v) => rule_vec.push((
+                            v.clone(),
+                            cb.map_or_else(|| Span::new(0, 0), |g| g.item.span),
+                            prec,
+                        )),
+                        _ => unreachable!(),
+                    };
+                    Ast::Rule(rule_vec)
                 }
             },
             lrp::Span::new(toks[0].item.span.start, toks.last().unwrap().item.span.end),
         )
     }
-    fn lrp_wop_Program_3(toks: &[Gramem]) -> lrp::Meta<Ast> {
+    fn lrp_wop_Rule_3(toks: Vec<Gramem>) -> lrp::Meta<Ast> {
         lrp::Meta::new(
             {
-                let p = toks[0..1].to_vec();
+                let mut pr = Some(toks[0].clone());
+                pr = None;
+                let pr = pr;
+                let mut cb = Some(toks[0].clone());
+                cb = None;
+                let cb = cb;
                 {
-                    Ast::Program(p)
+                    let mut rule_vec = match toks[0].item.item {
+                        Ast::Rule(ref vv) => vv.clone(),
+                        _ => unreachable!(),
+                    };
+                    let prec = pr.and_then(|p| p.item.item.get_src_ref());
+                    match toks[2].item.item {
+                        Ast::RulePipe(ref v) => rule_vec.push((
+                            v.clone(),
+                            cb.map_or_else(|| Span::new(0, 0), |g| g.item.span),
+                            prec,
+                        )),
+                        _ => unreachable!(),
+                    };
+                    Ast::Rule(rule_vec)
                 }
             },
             lrp::Span::new(toks[0].item.span.start, toks.last().unwrap().item.span.end),
         )
     }
-    fn lrp_wop_Program_4(toks: &[Gramem]) -> lrp::Meta<Ast> {
+    fn lrp_wop_Rule_4(toks: Vec<Gramem>) -> lrp::Meta<Ast> {
         lrp::Meta::new(
             {
-                let p = toks[0..1].to_vec();
+                let pr = Some(toks[1].clone());
+                let cb = Some(toks[2].clone());
                 {
-                    Ast::Program(p)
+                    let Ast::RulePipe(ref prod) = toks[0].item.item else {
+                        unreachable!()
+                    };
+                    let prec = pr.and_then(|p| p.item.item.get_src_ref());
+                    Ast::Rule(vec![(
+                        prod.clone(),
+                        cb.map_or_else(|| Span::new(0, 0), |g| g.item.span),
+                        prec,
+                    )])
                 }
             },
             lrp::Span::new(toks[0].item.span.start, toks.last().unwrap().item.span.end),
         )
     }
-    fn lrp_wop_Program_5(toks: &[Gramem]) -> lrp::Meta<Ast> {
+    fn lrp_wop_Rule_5(toks: Vec<Gramem>) -> lrp::Meta<Ast> {
         lrp::Meta::new(
             {
-                let p = toks[0..1].to_vec();
+                let pr = Some(toks[1].clone());
+                let mut cb = Some(toks[0].clone());
+                cb = None;
+                let cb = cb;
                 {
-                    Ast::Program(p)
+                    let Ast::RulePipe(ref prod) = toks[0].item.item else {
+                        unreachable!()
+                    };
+                    let prec = pr.and_then(|p| p.item.item.get_src_ref());
+                    Ast::Rule(vec![(
+                        prod.clone(),
+                        cb.map_or_else(|| Span::new(0, 0), |g| g.item.span),
+                        prec,
+                    )])
+                }
+            },
+            lrp::Span::new(toks[0].item.span.start, toks.last().unwrap().item.span.end),
+        )
+    }
+    fn lrp_wop_Rule_6(toks: Vec<Gramem>) -> lrp::Meta<Ast> {
+        lrp::Meta::new(
+            {
+                let mut pr = Some(toks[0].clone());
+                pr = None;
+                let pr = pr;
+                let cb = Some(toks[1].clone());
+                {
+                    let Ast::RulePipe(ref prod) = toks[0].item.item else {
+                        unreachable!()
+                    };
+                    let prec = pr.and_then(|p| p.item.item.get_src_ref());
+                    Ast::Rule(vec![(
+                        prod.clone(),
+                        cb.map_or_else(|| Span::new(0, 0), |g| g.item.span),
+                        prec,
+                    )])
+                }
+            },
+            lrp::Span::new(toks[0].item.span.start, toks.last().unwrap().item.span.end),
+        )
+    }
+    fn lrp_wop_Rule_7(toks: Vec<Gramem>) -> lrp::Meta<Ast> {
+        lrp::Meta::new(
+            {
+                let mut pr = Some(toks[0].clone());
+                pr = None;
+                let pr = pr;
+                let mut cb = Some(toks[0].clone());
+                cb = None;
+                let cb = cb;
+                {
+                    let Ast::RulePipe(ref prod) = toks[0].item.item else {
+                        unreachable!()
+                    };
+                    let prec = pr.and_then(|p| p.item.item.get_src_ref());
+                    Ast::Rule(vec![(
+                        prod.clone(),
+                        cb.map_or_else(|| Span::new(0, 0), |g| g.item.span),
+                        prec,
+                    )])
                 }
             },
             lrp::Span::new(toks[0].item.span.start, toks.last().unwrap().item.span.end),
         )
     }
     map.insert(
-        Program,
+        Rule,
         vec![
-            lrp_wop_Program_0,
-            lrp_wop_Program_1,
-            lrp_wop_Program_2,
-            lrp_wop_Program_3,
-            lrp_wop_Program_4,
-            lrp_wop_Program_5,
+            lrp::reductor(lrp_wop_Rule_0),
+            lrp::reductor(lrp_wop_Rule_1),
+            lrp::reductor(lrp_wop_Rule_2),
+            lrp::reductor(lrp_wop_Rule_3),
+            lrp::reductor(lrp_wop_Rule_4),
+            lrp::reductor(lrp_wop_Rule_5),
+            lrp::reductor(lrp_wop_Rule_6),
+            lrp::reductor(lrp_wop_Rule_7),
         ],
     );
 
-    fn lrp_wop_RuleDecl_0(toks: &[Gramem]) -> lrp::Meta<Ast> {
+    fn lrp_wop_IdentPath_0(toks: Vec<Gramem>) -> lrp::Meta<Ast> {
         lrp::Meta::new(
             {
+                let ip = toks[0].clone();
+                let ext = toks[2].clone();
                 {
-                    let ident = toks[0].item.item.get_src_ref().unwrap();
-                    let ty = toks[2].item.item.get_src_ref().unwrap();
-                    let rule_vec = match toks[4].item.item {
-                        Ast::Rule(ref v) => v.clone(),
-                        _ => unreachable!(),
-                    };
-                    Ast::RuleDecl((ident, ty, rule_vec))
+                    let span = Span::new(ip.item.span.start, ext.item.span.end);
+                    Ast::IdentPath(span)
+                }
+            },
+            lrp::Span::new(toks[0].item.span.start, toks.last().unwrap().item.span.end),
+        )
+    }
+    fn lrp_wop_IdentPath_1(toks: Vec<Gramem>) -> lrp::Meta<Ast> {
+        lrp::Meta::new(
+            {
+                {
+                    Ast::IdentPath(toks[0].item.span)
                 }
             },
             lrp::Span::new(toks[0].item.span.start, toks.last().unwrap().item.span.end),
         )
     }
-    map.insert(RuleDecl, vec![lrp_wop_RuleDecl_0]);
+    map.insert(
+        IdentPath,
+        vec![
+            lrp::reductor(lrp_wop_IdentPath_0),
+            lrp::reductor(lrp_wop_IdentPath_1),
+        ],
+    );
 
     map
 }